@@ -7,15 +7,13 @@
 //!
 //! Run with: cargo run --example cube_window
 
-use epicx::graphics::{Graphics, GraphicsConfig};
+use epicx::graphics::{Graphics, GraphicsConfig, WindowHandle};
 use epicx::math::Color;
 use std::time::Instant;
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::{Window, WindowId};
-use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
-use windows::Win32::Foundation::HWND;
 
 /// Application state - MUCH SIMPLER with Level B!
 struct App {
@@ -99,7 +97,8 @@ impl ApplicationHandler for App {
         println!("║  Before: Device + CommandQueue + SwapChain + Allocator + ... ║");
         println!("║  After:  Just Graphics!                                      ║");
         println!("║                                                              ║");
-        println!("║  Controls: ESC to exit                                       ║");
+        println!("║  Controls: ESC to exit, F12 for RenderDoc/PIX capture        ║");
+        println!("║            V toggles vsync, B cycles 2/3 back buffers        ║");
         println!("╚══════════════════════════════════════════════════════════════╝");
         println!();
         
@@ -111,11 +110,7 @@ impl ApplicationHandler for App {
         let window = event_loop.create_window(window_attrs).expect("Failed to create window");
         let size = window.inner_size();
         
-        // Get HWND
-        let hwnd = match window.window_handle().unwrap().as_raw() {
-            RawWindowHandle::Win32(handle) => HWND(handle.hwnd.get() as *mut std::ffi::c_void),
-            _ => panic!("Unsupported platform"),
-        };
+        let handle = WindowHandle::from_window(&window).expect("Unsupported platform");
         
         // SIMPLIFIED: Just create Graphics with config!
         println!("[EPICX] Creating Graphics (Level B)...");
@@ -125,7 +120,7 @@ impl ApplicationHandler for App {
             ..Default::default()
         };
         
-        let graphics = Graphics::new(hwnd, config).expect("Failed to create graphics");
+        let graphics = Graphics::new(handle, config).expect("Failed to create graphics");
         println!("[EPICX] Graphics created ({}x{}) - All DX12 resources encapsulated!", size.width, size.height);
         println!("[EPICX] Starting render loop...\n");
         
@@ -144,6 +139,36 @@ impl ApplicationHandler for App {
                     println!("\n[EPICX] ESC pressed, exiting...");
                     event_loop.exit();
                 }
+                if event.physical_key == winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::F12)
+                    && event.state == winit::event::ElementState::Pressed
+                {
+                    if let Some(graphics) = &mut self.graphics {
+                        graphics.trigger_gpu_capture(1);
+                    }
+                }
+                if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                    if let Some(graphics) = &mut self.graphics {
+                        // Applied live; no need to recreate Graphics
+                        let mut config = graphics.swap_chain_config().clone();
+                        match event.physical_key {
+                            winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyV) => {
+                                config.vsync = !config.vsync;
+                            }
+                            winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyB) => {
+                                config.buffer_count = if config.buffer_count == 2 { 3 } else { 2 };
+                            }
+                            _ => return,
+                        }
+                        match graphics.reconfigure(config) {
+                            Ok(_) => println!(
+                                "[EPICX] Swap chain: vsync {}, {} buffers",
+                                graphics.config().vsync,
+                                graphics.config().buffer_count
+                            ),
+                            Err(e) => eprintln!("[EPICX] Reconfigure failed: {}", e),
+                        }
+                    }
+                }
             }
             WindowEvent::Resized(new_size) => {
                 if new_size.width > 0 && new_size.height > 0 {
@@ -12,15 +12,13 @@
 //!
 //! Run with: cargo run --example game_scene --release
 
-use epicx::graphics::{Graphics, GraphicsConfig};
+use epicx::graphics::{Graphics, GraphicsConfig, WindowHandle};
 use epicx::math::{Vec3, Vec2, Color};
 use std::time::Instant;
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::{Window, WindowId};
-use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
-use windows::Win32::Foundation::HWND;
 
 // ============================================================================
 // 3D SCENE PRIMITIVES (SDF-based for ray marching)
@@ -631,10 +629,7 @@ impl ApplicationHandler for App {
         let window = event_loop.create_window(window_attrs).expect("Failed to create window");
         let size = window.inner_size();
         
-        let hwnd = match window.window_handle().unwrap().as_raw() {
-            RawWindowHandle::Win32(handle) => HWND(handle.hwnd.get() as *mut std::ffi::c_void),
-            _ => panic!("Unsupported platform"),
-        };
+        let handle = WindowHandle::from_window(&window).expect("Unsupported platform");
         
         println!("[EPICX] Initializing DirectX12...");
         let config = GraphicsConfig {
@@ -643,7 +638,7 @@ impl ApplicationHandler for App {
             ..Default::default()
         };
         
-        let graphics = Graphics::new(hwnd, config).expect("Failed to create graphics");
+        let graphics = Graphics::new(handle, config).expect("Failed to create graphics");
         println!("[EPICX] DirectX12 ready ({}x{})", size.width, size.height);
         
         // Initialize renderer
@@ -0,0 +1,138 @@
+//! HDR output demo
+//!
+//! Requests HDR10 output (falls back to SDR + ACES tone mapping on SDR
+//! displays) and clears with an emissive color well above 1.0.
+//!
+//! Controls: Up/Down to change exposure, ESC to exit
+//!
+//! Run with: cargo run --example hdr_scene
+
+use epicx::dx12::SwapChainFormat;
+use epicx::graphics::{Graphics, GraphicsConfig, WindowHandle};
+use epicx::math::Color;
+use std::time::Instant;
+use winit::application::ApplicationHandler;
+use winit::event::{ElementState, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::{Window, WindowId};
+
+struct App {
+    window: Option<Window>,
+    graphics: Option<Graphics>,
+    start_time: Instant,
+}
+
+impl App {
+    fn new() -> Self {
+        Self {
+            window: None,
+            graphics: None,
+            start_time: Instant::now(),
+        }
+    }
+
+    fn render(&mut self) {
+        let Some(graphics) = &mut self.graphics else { return };
+        let elapsed = self.start_time.elapsed().as_secs_f32();
+
+        // Emissive orange pulsing between 0.5x and 8x SDR white
+        let intensity = 0.5 + 7.5 * (0.5 + 0.5 * (elapsed * 0.8).sin());
+        let emissive = Color::rgb(1.0 * intensity, 0.45 * intensity, 0.1 * intensity);
+
+        let frame = match graphics.begin_frame() {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("[EPICX] Begin frame error: {:?}", e);
+                return;
+            }
+        };
+
+        frame.clear_scene(emissive);
+
+        if let Err(e) = graphics.end_frame(frame) {
+            eprintln!("[EPICX] End frame error: {:?}", e);
+        }
+    }
+
+    fn update_title(&self) {
+        let (Some(window), Some(graphics)) = (&self.window, &self.graphics) else { return };
+        window.set_title(&format!(
+            "EPICX - HDR | Output: {:?} | Exposure: {:.2}",
+            graphics.output_format(),
+            graphics.tone_mapping().exposure
+        ));
+    }
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let window_attrs = Window::default_attributes()
+            .with_title("EPICX - HDR")
+            .with_inner_size(winit::dpi::LogicalSize::new(1280, 720));
+
+        let window = event_loop.create_window(window_attrs).expect("Failed to create window");
+        let size = window.inner_size();
+
+        let handle = WindowHandle::from_window(&window).expect("Unsupported platform");
+
+        let config = GraphicsConfig {
+            width: size.width,
+            height: size.height,
+            output_format: SwapChainFormat::Hdr10,
+            ..Default::default()
+        };
+
+        let graphics = Graphics::new(handle, config).expect("Failed to create graphics");
+        println!("[EPICX] Output format: {:?}", graphics.output_format());
+
+        self.window = Some(window);
+        self.graphics = Some(graphics);
+        self.update_title();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                let step = match event.physical_key {
+                    PhysicalKey::Code(KeyCode::Escape) => {
+                        event_loop.exit();
+                        return;
+                    }
+                    PhysicalKey::Code(KeyCode::ArrowUp) => 1.25,
+                    PhysicalKey::Code(KeyCode::ArrowDown) => 0.8,
+                    _ => return,
+                };
+                if let Some(graphics) = &mut self.graphics {
+                    let exposure = graphics.tone_mapping().exposure * step;
+                    graphics.set_exposure(exposure);
+                }
+                self.update_title();
+            }
+            WindowEvent::Resized(new_size) => {
+                if let Some(graphics) = &mut self.graphics {
+                    let _ = graphics.resize(new_size.width, new_size.height);
+                }
+            }
+            WindowEvent::RedrawRequested => self.render(),
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let event_loop = EventLoop::new()?;
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    let mut app = App::new();
+    event_loop.run_app(&mut app)?;
+
+    Ok(())
+}
@@ -0,0 +1,220 @@
+//! Runtime language switching demo
+//!
+//! Installs an English and a Spanish catalog and switches between them from
+//! a dropdown. The app watches `language_atom()` and re-translates its
+//! labels only when the language actually changed. EPICX has no font
+//! rasterizer yet, so the translated strings go to the window title and the
+//! console; the dropdown entries are marked by color swatches.
+//!
+//! Controls: click the box to open the dropdown, click an entry to switch,
+//! ESC to exit
+//!
+//! Run with: cargo run --example i18n_demo
+
+use epicx::backend::DrawBatch;
+use epicx::graphics::{Graphics, GraphicsConfig, WindowHandle};
+use epicx::i18n::{self, Catalog};
+use epicx::math::{Color, Rect, Vec2};
+use epicx::t;
+use winit::application::ApplicationHandler;
+use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::{Window, WindowId};
+
+const EN: &str = "\
+app.title = Language demo
+app.language = Language
+language.name = English
+switches.one = {count} switch
+switches.other = {count} switches
+";
+
+const ES: &str = "\
+app.title = Demo de idiomas
+app.language = Idioma
+language.name = Español
+switches.one = {count} cambio
+switches.other = {count} cambios
+";
+
+/// Dropdown entries: language code and swatch color
+const LANGUAGES: [(&str, Color); 2] = [("en", Color::rgb(0.2, 0.4, 0.85)), ("es", Color::rgb(0.85, 0.3, 0.2))];
+
+const DROPDOWN: Rect = Rect::new(40.0, 40.0, 260.0, 40.0);
+
+struct App {
+    window: Option<Window>,
+    graphics: Option<Graphics>,
+    mouse: Vec2,
+    open: bool,
+    switches: u32,
+    /// `language_atom` version the labels were translated for
+    seen_version: Option<u64>,
+}
+
+impl App {
+    fn new() -> Self {
+        Self {
+            window: None,
+            graphics: None,
+            mouse: Vec2::ZERO,
+            open: false,
+            switches: 0,
+            seen_version: None,
+        }
+    }
+
+    /// Box of the `index`-th entry of the open dropdown
+    fn entry(index: usize) -> Rect {
+        DROPDOWN.translate(Vec2::new(0.0, DROPDOWN.height * (index + 1) as f32))
+    }
+
+    fn click(&mut self) {
+        if DROPDOWN.contains(self.mouse) {
+            self.open = !self.open;
+            return;
+        }
+        if !self.open {
+            return;
+        }
+        self.open = false;
+        let picked = (0..LANGUAGES.len()).find(|&i| Self::entry(i).contains(self.mouse));
+        if let Some((code, _)) = picked.map(|i| LANGUAGES[i]) {
+            if code != i18n::language() {
+                match i18n::set_language(code) {
+                    Ok(()) => self.switches += 1,
+                    Err(e) => eprintln!("[EPICX] {}", e),
+                }
+            }
+        }
+    }
+
+    /// Re-translate the labels if the language changed since the last frame
+    fn update_labels(&mut self) {
+        let version = i18n::language_atom().version();
+        if self.seen_version == Some(version) {
+            return;
+        }
+        self.seen_version = Some(version);
+
+        let title = format!(
+            "{} | {}: {} | {}",
+            t!("app.title"),
+            t!("app.language"),
+            t!("language.name"),
+            t!("switches", count = self.switches)
+        );
+        println!("[EPICX] {}", title);
+        if let Some(window) = &self.window {
+            window.set_title(&title);
+        }
+    }
+
+    fn render(&mut self) {
+        self.update_labels();
+        let Some(graphics) = &mut self.graphics else { return };
+
+        let current = i18n::language();
+        let swatch = |code: &str| LANGUAGES.iter().find(|(c, _)| *c == code).map_or(Color::WHITE, |(_, color)| *color);
+        let row = |batch: &mut DrawBatch, rect: Rect, code: &str, highlight: bool| {
+            let background = if highlight { Color::rgb(0.3, 0.3, 0.38) } else { Color::rgb(0.2, 0.2, 0.26) };
+            batch.quad(rect, background);
+            batch.quad(Rect::new(rect.x + 8.0, rect.y + 8.0, rect.height - 16.0, rect.height - 16.0), swatch(code));
+        };
+
+        let mut batch = DrawBatch::new().with_clear(Color::from_hex(0x1a1a2e));
+        row(&mut batch, DROPDOWN, &current, DROPDOWN.contains(self.mouse));
+        // Arrow area on the right of the closed box
+        let arrow = Rect::new(DROPDOWN.x + DROPDOWN.width - 32.0, DROPDOWN.y + 16.0, 16.0, 8.0);
+        batch.quad(arrow, Color::rgb(0.8, 0.8, 0.85));
+        if self.open {
+            for (i, (code, _)) in LANGUAGES.iter().enumerate() {
+                let rect = Self::entry(i);
+                row(&mut batch, rect, code, rect.contains(self.mouse) || *code == current);
+            }
+        }
+
+        let mut frame = match graphics.begin_frame() {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("[EPICX] Begin frame error: {:?}", e);
+                return;
+            }
+        };
+        if let Err(e) = graphics.submit(&mut frame, &batch) {
+            eprintln!("[EPICX] Submit error: {:?}", e);
+        }
+        if let Err(e) = graphics.end_frame(frame) {
+            eprintln!("[EPICX] End frame error: {:?}", e);
+        }
+    }
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let window_attrs = Window::default_attributes()
+            .with_title("EPICX - i18n")
+            .with_inner_size(winit::dpi::PhysicalSize::new(960, 540));
+
+        let window = event_loop.create_window(window_attrs).expect("Failed to create window");
+        let size = window.inner_size();
+
+        let handle = WindowHandle::from_window(&window).expect("Unsupported platform");
+
+        let config = GraphicsConfig {
+            width: size.width,
+            height: size.height,
+            ..Default::default()
+        };
+
+        let graphics = Graphics::new(handle, config).expect("Failed to create graphics");
+
+        self.window = Some(window);
+        self.graphics = Some(graphics);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::KeyboardInput { event, .. }
+                if event.state == ElementState::Pressed
+                    && event.physical_key == PhysicalKey::Code(KeyCode::Escape) =>
+            {
+                event_loop.exit();
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse = Vec2::new(position.x as f32, position.y as f32);
+            }
+            WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => self.click(),
+            WindowEvent::Resized(new_size) => {
+                if let Some(graphics) = &mut self.graphics {
+                    let _ = graphics.resize(new_size.width, new_size.height);
+                }
+            }
+            WindowEvent::RedrawRequested => self.render(),
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut catalog = Catalog::new("en").with_fallback("en");
+    catalog.add_language("en", EN)?;
+    catalog.add_language("es", ES)?;
+    i18n::set_catalog(catalog);
+
+    let event_loop = EventLoop::new()?;
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    let mut app = App::new();
+    event_loop.run_app(&mut app)?;
+
+    Ok(())
+}
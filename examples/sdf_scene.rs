@@ -9,17 +9,16 @@
 //!
 //! Run with: cargo run --example sdf_scene
 
-use epicx::graphics::{Graphics, GraphicsConfig};
+use epicx::events::{InputState, WinitInput};
+use epicx::graphics::{Graphics, GraphicsConfig, WindowHandle};
 use epicx::math::{Vec3, Vec2, Color};
 use epicx::sdf::{Sdf, Sphere, Box3D};
 use std::time::Instant;
 use std::f32::consts::PI;
 use winit::application::ApplicationHandler;
-use winit::event::WindowEvent;
+use winit::event::{DeviceEvent, DeviceId, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::{Window, WindowId};
-use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
-use windows::Win32::Foundation::HWND;
 
 /// Material properties
 #[derive(Clone, Copy)]
@@ -52,6 +51,8 @@ struct Scene {
     time: f32,
     camera_pos: Vec3,
     camera_target: Vec3,
+    /// Mouse-look rotation around the target
+    orbit_yaw: f32,
 }
 
 impl Scene {
@@ -64,6 +65,7 @@ impl Scene {
             time: 0.0,
             camera_pos: Vec3::new(0.0, 5.0, 12.0),
             camera_target: Vec3::new(0.0, 1.0, 0.0),
+            orbit_yaw: 0.0,
         };
         
         // Add ground (large flat box)
@@ -114,8 +116,8 @@ impl Scene {
         }
         
         // Animate camera slightly
-        self.camera_pos.x = 2.0 * (self.time * 0.2).sin();
-        self.camera_pos.z = 12.0 + 2.0 * (self.time * 0.15).cos();
+        let animated = Vec3::new(2.0 * (self.time * 0.2).sin(), 5.0, 12.0 + 2.0 * (self.time * 0.15).cos());
+        self.camera_pos = self.camera_target + Self::rotate_y(animated - self.camera_target, self.orbit_yaw);
     }
     
     /// Rotate point around Y axis
@@ -318,6 +320,8 @@ struct App {
     window: Option<Window>,
     graphics: Option<Graphics>,
     scene: Scene,
+    input: InputState,
+    winit_input: WinitInput,
     start_time: Instant,
     last_frame_time: Instant,
     frame_count: u64,
@@ -331,6 +335,8 @@ impl App {
             window: None,
             graphics: None,
             scene: Scene::new(),
+            input: InputState::new(),
+            winit_input: WinitInput::new(),
             start_time: Instant::now(),
             last_frame_time: Instant::now(),
             frame_count: 0,
@@ -348,6 +354,12 @@ impl App {
         let dt = (now - self.last_frame_time).as_secs_f32();
         self.last_frame_time = now;
         
+        // Mouse-look orbits the camera (raw motion, so it never hits the screen edge)
+        if self.input.is_relative_active() {
+            self.scene.orbit_yaw += self.input.mouse_delta().x * 0.005;
+        }
+        self.input.end_frame();
+
         // Update scene
         self.scene.update(dt);
         
@@ -419,7 +431,7 @@ impl ApplicationHandler for App {
         println!("║  - Fresnel Reflections                                       ║");
         println!("║  - Distance Fog                                              ║");
         println!("║                                                              ║");
-        println!("║  Controls: ESC to exit                                       ║");
+        println!("║  Controls: M toggles mouse-look, ESC to exit                 ║");
         println!("╚══════════════════════════════════════════════════════════════╝");
         println!();
         
@@ -431,11 +443,7 @@ impl ApplicationHandler for App {
         let window = event_loop.create_window(window_attrs).expect("Failed to create window");
         let size = window.inner_size();
         
-        // Get HWND
-        let hwnd = match window.window_handle().unwrap().as_raw() {
-            RawWindowHandle::Win32(handle) => HWND(handle.hwnd.get() as *mut std::ffi::c_void),
-            _ => panic!("Unsupported platform"),
-        };
+        let handle = WindowHandle::from_window(&window).expect("Unsupported platform");
         
         // Create Graphics (Level B) - Simple!
         println!("[EPICX] Creating Graphics...");
@@ -445,7 +453,7 @@ impl ApplicationHandler for App {
             ..Default::default()
         };
         
-        let graphics = Graphics::new(hwnd, config).expect("Failed to create graphics");
+        let graphics = Graphics::new(handle, config).expect("Failed to create graphics");
         println!("[EPICX] Graphics ready ({}x{})", size.width, size.height);
         
         // Print ASCII preview of scene
@@ -457,6 +465,10 @@ impl ApplicationHandler for App {
     }
     
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        if let Some(input_event) = self.winit_input.window_event(&event) {
+            self.input.handle_event(&input_event);
+        }
+
         match event {
             WindowEvent::CloseRequested => {
                 println!("\n[EPICX] Window closed");
@@ -467,6 +479,12 @@ impl ApplicationHandler for App {
                     println!("\n[EPICX] ESC pressed, exiting...");
                     event_loop.exit();
                 }
+                if event.physical_key == winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyM)
+                    && event.state.is_pressed()
+                    && !event.repeat
+                {
+                    self.input.set_relative_mode(!self.input.relative_mode());
+                }
             }
             WindowEvent::Resized(new_size) => {
                 if new_size.width > 0 && new_size.height > 0 {
@@ -482,8 +500,15 @@ impl ApplicationHandler for App {
         }
     }
     
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _id: DeviceId, event: DeviceEvent) {
+        if let Some(input_event) = self.winit_input.device_event(&event) {
+            self.input.handle_event(&input_event);
+        }
+    }
+
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
         if let Some(window) = &self.window {
+            self.winit_input.sync_cursor(window, &self.input);
             window.request_redraw();
         }
     }
@@ -0,0 +1,116 @@
+//! SDF Scene Viewer - loads a scene file and previews it in the terminal
+//!
+//! Run with:
+//!   cargo run --example sdf_viewer -- examples/scenes/sdf_scene.json
+//!   cargo run --example sdf_viewer -- examples/scenes/game_scene.json --hlsl
+//!   cargo run --example sdf_viewer -- scene.json --save copy.json
+
+use epicx::math::{Vec2, Vec3};
+use epicx::sdf::{SceneLight, SdfScene};
+
+const DEFAULT_SCENE: &str = "examples/scenes/sdf_scene.json";
+
+/// Shade one pixel by ray marching the scene (uv in -1..1)
+fn render_pixel(scene: &SdfScene, uv: Vec2, aspect: f32) -> Vec3 {
+    let description = scene.description();
+    let camera = &description.camera;
+    let forward = (camera.target - camera.position).normalize();
+    let right = forward.cross(Vec3::Y).normalize();
+    let up = right.cross(forward);
+    let focal = 1.0 / (camera.fov.to_radians() * 0.5).tan();
+    let rd = (forward * focal + right * uv.x * aspect + up * uv.y).normalize();
+
+    let sky = Vec3::new(0.4, 0.6, 0.9) * (1.0 - uv.y * 0.3) + Vec3::new(0.7, 0.8, 0.95) * (uv.y * 0.3 + 0.5);
+
+    let mut t = 0.0f32;
+    for _ in 0..100 {
+        let p = camera.position + rd * t;
+        let (d, material) = scene.distance_material(p);
+        if d < 0.001 {
+            let normal = epicx::sdf::Sdf::normal(scene, p);
+            let albedo = material.map(|m| m.color).unwrap_or(Vec3::ONE);
+            let mut color = description.ambient * albedo;
+            for light in &description.lights {
+                let (dir, light_color) = match light {
+                    SceneLight::Directional { direction, color } => (direction.normalize(), *color),
+                    SceneLight::Point { position, color, range } => {
+                        let to_light = *position - p;
+                        let falloff = (1.0 - to_light.length() / range).clamp(0.0, 1.0);
+                        (to_light.normalize(), *color * falloff)
+                    }
+                };
+                color += albedo * light_color * normal.dot(dir).max(0.0);
+            }
+            let fog = (1.0 - (-t * 0.03).exp()).clamp(0.0, 1.0);
+            return color * (1.0 - fog) + sky * fog;
+        }
+        t += d;
+        if t > 50.0 {
+            break;
+        }
+    }
+    sky
+}
+
+fn print_ascii_preview(scene: &SdfScene, width: u32, height: u32) {
+    // Terminal cells are about twice as tall as they are wide
+    let aspect = width as f32 / height as f32 * 0.5;
+    let chars: Vec<char> = " .:-=+*#%@".chars().collect();
+
+    for y in 0..height {
+        let line: String = (0..width)
+            .map(|x| {
+                let uv = Vec2::new(
+                    (x as f32 / width as f32) * 2.0 - 1.0,
+                    1.0 - (y as f32 / height as f32) * 2.0,
+                );
+                let c = render_pixel(scene, uv, aspect);
+                let brightness = (c.x * 0.299 + c.y * 0.587 + c.z * 0.114).clamp(0.0, 0.999);
+                chars[(brightness * chars.len() as f32) as usize]
+            })
+            .collect();
+        println!("{}", line);
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let save = args.iter().position(|a| a == "--save").map(|i| i + 1);
+    let path = args
+        .iter()
+        .enumerate()
+        .find(|&(i, a)| !a.starts_with("--") && Some(i) != save)
+        .map(|(_, a)| a.as_str())
+        .unwrap_or(DEFAULT_SCENE);
+
+    let scene = match SdfScene::from_file(path) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("[EPICX] Failed to load {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+
+    let description = scene.description();
+    println!(
+        "[SCENE] {}: {} nodes, {} materials, {} lights",
+        path,
+        description.nodes.len(),
+        description.materials.len(),
+        description.lights.len()
+    );
+    println!();
+    print_ascii_preview(&scene, 80, 30);
+
+    if args.iter().any(|a| a == "--hlsl") {
+        println!("\n[SCENE] Generated HLSL:\n{}", description.to_hlsl());
+    }
+
+    if let Some(index) = save {
+        let out = args.get(index).ok_or("--save needs a path")?;
+        scene.to_file(out)?;
+        println!("[SCENE] Saved to {}", out);
+    }
+
+    Ok(())
+}
@@ -5,7 +5,7 @@
 //!
 //! Run with: cargo run --example simple_cube
 
-use epicx::dx12::{detect_gpu, Device, CommandQueue, SwapChain, SwapChainConfig, Fence};
+use epicx::dx12::{detect_gpu, Device, CommandQueue, SwapChain, SwapChainConfig, Fence, ResourceStateTracker};
 use epicx::math::{Vec3, Color};
 use std::time::Instant;
 use winit::application::ApplicationHandler;
@@ -346,19 +346,10 @@ impl App {
                 .expect("Failed to create command list");
             
             // Transition to render target
-            let barrier = D3D12_RESOURCE_BARRIER {
-                Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-                Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-                Anonymous: D3D12_RESOURCE_BARRIER_0 {
-                    Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                        pResource: std::mem::transmute_copy(back_buffer),
-                        Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-                        StateBefore: D3D12_RESOURCE_STATE_PRESENT,
-                        StateAfter: D3D12_RESOURCE_STATE_RENDER_TARGET,
-                    }),
-                },
-            };
-            cmd_list.ResourceBarrier(&[barrier]);
+            let mut states = ResourceStateTracker::new();
+            states.track(back_buffer, D3D12_RESOURCE_STATE_PRESENT);
+            states.transition(back_buffer, D3D12_RESOURCE_STATE_RENDER_TARGET);
+            states.flush(&cmd_list);
             
             // Clear with the rendered color
             let rtv = swap_chain.current_rtv();
@@ -366,19 +357,8 @@ impl App {
             cmd_list.ClearRenderTargetView(rtv, &color, None);
             
             // Transition back to present
-            let barrier = D3D12_RESOURCE_BARRIER {
-                Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-                Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-                Anonymous: D3D12_RESOURCE_BARRIER_0 {
-                    Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                        pResource: std::mem::transmute_copy(back_buffer),
-                        Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-                        StateBefore: D3D12_RESOURCE_STATE_RENDER_TARGET,
-                        StateAfter: D3D12_RESOURCE_STATE_PRESENT,
-                    }),
-                },
-            };
-            cmd_list.ResourceBarrier(&[barrier]);
+            states.transition(back_buffer, D3D12_RESOURCE_STATE_PRESENT);
+            states.flush(&cmd_list);
             
             // Close and execute
             cmd_list.Close().expect("Failed to close command list");
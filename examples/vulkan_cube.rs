@@ -9,7 +9,7 @@
 //!
 //! Run with: cargo run --example vulkan_cube
 
-use epicx::dx12::{detect_gpu, Device, CommandQueue, SwapChain, SwapChainConfig, CommandAllocator, CommandList};
+use epicx::dx12::{detect_gpu, Device, CommandQueue, SwapChain, SwapChainConfig, CommandAllocator, CommandList, ResourceStateTracker};
 use epicx::math::{Vec3, Vec2, Color};
 use std::time::Instant;
 use winit::application::ApplicationHandler;
@@ -281,42 +281,22 @@ impl App {
         let back_buffer = swap_chain.current_back_buffer();
         let rtv = swap_chain.current_rtv();
         
+        // Track the back buffer; the tracker emits only the barriers needed
+        let mut states = ResourceStateTracker::new();
+        states.track(back_buffer, D3D12_RESOURCE_STATE_PRESENT);
+        states.transition(back_buffer, D3D12_RESOURCE_STATE_RENDER_TARGET);
+        states.flush(&cmd_list);
+        
         unsafe {
-            // Transition to render target
-            let barrier = D3D12_RESOURCE_BARRIER {
-                Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-                Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-                Anonymous: D3D12_RESOURCE_BARRIER_0 {
-                    Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                        pResource: std::mem::transmute_copy(back_buffer),
-                        Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-                        StateBefore: D3D12_RESOURCE_STATE_PRESENT,
-                        StateAfter: D3D12_RESOURCE_STATE_RENDER_TARGET,
-                    }),
-                },
-            };
-            cmd_list.raw().ResourceBarrier(&[barrier]);
-            
             // Clear with cube color
             let color = [clear_color.r, clear_color.g, clear_color.b, 1.0];
             cmd_list.raw().ClearRenderTargetView(rtv, &color, None);
-            
-            // Transition back to present
-            let barrier = D3D12_RESOURCE_BARRIER {
-                Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-                Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-                Anonymous: D3D12_RESOURCE_BARRIER_0 {
-                    Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                        pResource: std::mem::transmute_copy(back_buffer),
-                        Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-                        StateBefore: D3D12_RESOURCE_STATE_RENDER_TARGET,
-                        StateAfter: D3D12_RESOURCE_STATE_PRESENT,
-                    }),
-                },
-            };
-            cmd_list.raw().ResourceBarrier(&[barrier]);
         }
         
+        // Transition back to present
+        states.transition(back_buffer, D3D12_RESOURCE_STATE_PRESENT);
+        states.flush(&cmd_list);
+        
         // Close and execute
         if let Err(e) = cmd_list.close() {
             eprintln!("Command list close error: {:?}", e);
@@ -0,0 +1,349 @@
+//! Packed asset archives (`.epak`)
+//!
+//! Layout, all integers little-endian:
+//!
+//! ```text
+//! "EPAK" | version u32 | entry count u64 | index offset u64
+//! entry data, back to back
+//! index: per entry
+//!     name length u32 | name (UTF-8, `/` separators)
+//!     offset u64 | size u64 | flags u32 | XXH64 of the data u64
+//! ```
+//!
+//! Offsets and sizes are 64-bit, so archives may exceed 4 GB. Entries are
+//! stored uncompressed; `flags` is reserved for compression.
+
+use super::{normalize_path, AssetError, AssetResult};
+use crate::hashing::xxh64;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"EPAK";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 4 + 4 + 8 + 8;
+
+#[derive(Debug, Clone, Copy)]
+struct EntryInfo {
+    offset: u64,
+    size: u64,
+    hash: u64,
+}
+
+fn archive_error(path: &Path, message: impl Into<String>) -> AssetError {
+    AssetError::Archive { path: path.display().to_string(), message: message.into() }
+}
+
+fn io_error(path: &Path, source: std::io::Error) -> AssetError {
+    AssetError::Io { path: path.display().to_string(), source }
+}
+
+/// Every file under `dir`, as (normalized relative name, full path), sorted
+fn collect_files(dir: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                files.push((normalize_path(&relative.to_string_lossy()), path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn write_header(out: &mut impl Write, count: u64, index_offset: u64) -> std::io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+    out.write_all(&count.to_le_bytes())?;
+    out.write_all(&index_offset.to_le_bytes())
+}
+
+fn write_index_entry(out: &mut impl Write, name: &str, info: &EntryInfo) -> std::io::Result<()> {
+    out.write_all(&(name.len() as u32).to_le_bytes())?;
+    out.write_all(name.as_bytes())?;
+    out.write_all(&info.offset.to_le_bytes())?;
+    out.write_all(&info.size.to_le_bytes())?;
+    out.write_all(&0u32.to_le_bytes())?;
+    out.write_all(&info.hash.to_le_bytes())
+}
+
+/// Pack every file under `dir` into an archive at `out_path`
+///
+/// Returns the number of entries written. Usable from a build script.
+pub fn pack(dir: impl AsRef<Path>, out_path: impl AsRef<Path>) -> AssetResult<usize> {
+    let (dir, out_path) = (dir.as_ref(), out_path.as_ref());
+    let file = File::create(out_path).map_err(|e| io_error(out_path, e))?;
+    // The archive may be written inside the directory being packed
+    let archive_path = out_path.canonicalize().map_err(|e| io_error(out_path, e))?;
+    let files: Vec<_> = collect_files(dir)
+        .map_err(|e| io_error(dir, e))?
+        .into_iter()
+        .filter(|(_, path)| path.canonicalize().map_or(true, |path| path != archive_path))
+        .collect();
+    let mut out = BufWriter::new(file);
+
+    // The index offset is patched in at the end
+    write_header(&mut out, files.len() as u64, 0).map_err(|e| io_error(out_path, e))?;
+
+    let mut index = Vec::with_capacity(files.len());
+    let mut offset = HEADER_SIZE;
+    for (name, path) in &files {
+        let data = std::fs::read(path).map_err(|e| io_error(path, e))?;
+        out.write_all(&data).map_err(|e| io_error(out_path, e))?;
+        index.push((name, EntryInfo { offset, size: data.len() as u64, hash: xxh64(&data, 0) }));
+        offset += data.len() as u64;
+    }
+
+    for (name, info) in &index {
+        write_index_entry(&mut out, name, info).map_err(|e| io_error(out_path, e))?;
+    }
+
+    let mut file = out.into_inner().map_err(|e| io_error(out_path, e.into_error()))?;
+    file.seek(SeekFrom::Start(16)).map_err(|e| io_error(out_path, e))?;
+    file.write_all(&offset.to_le_bytes()).map_err(|e| io_error(out_path, e))?;
+    Ok(index.len())
+}
+
+/// Read the header and index of an archive `file_size` bytes long
+fn read_index(reader: &mut (impl Read + Seek), path: &Path, file_size: u64) -> AssetResult<HashMap<String, EntryInfo>> {
+    let mut header = [0u8; HEADER_SIZE as usize];
+    reader.read_exact(&mut header).map_err(|_| archive_error(path, "truncated header"))?;
+    if &header[0..4] != MAGIC {
+        return Err(archive_error(path, "not an epak archive"));
+    }
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if version != VERSION {
+        return Err(archive_error(path, format!("unsupported version {}", version)));
+    }
+    let count = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    let index_offset = u64::from_le_bytes(header[16..24].try_into().unwrap());
+    if index_offset < HEADER_SIZE || index_offset > file_size {
+        return Err(archive_error(path, "index offset out of range"));
+    }
+
+    reader.seek(SeekFrom::Start(index_offset)).map_err(|e| io_error(path, e))?;
+    let truncated = |_| archive_error(path, "truncated index");
+    let mut position = index_offset;
+    let mut entries = HashMap::new();
+    for _ in 0..count {
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len).map_err(truncated)?;
+        position += 4;
+        // A corrupt length must not allocate more than the file could hold
+        let len = u64::from(u32::from_le_bytes(len));
+        if len > file_size.saturating_sub(position) {
+            return Err(archive_error(path, "entry name runs past the end of the file"));
+        }
+        let mut name = vec![0u8; len as usize];
+        reader.read_exact(&mut name).map_err(truncated)?;
+        let name = String::from_utf8(name).map_err(|_| archive_error(path, "entry name is not UTF-8"))?;
+
+        let mut fields = [0u8; 28];
+        reader.read_exact(&mut fields).map_err(truncated)?;
+        position += len + 28;
+        let offset = u64::from_le_bytes(fields[0..8].try_into().unwrap());
+        let size = u64::from_le_bytes(fields[8..16].try_into().unwrap());
+        let flags = u32::from_le_bytes(fields[16..20].try_into().unwrap());
+        let hash = u64::from_le_bytes(fields[20..28].try_into().unwrap());
+        if flags != 0 {
+            return Err(archive_error(path, format!("'{}' uses unsupported flags {:#x}", name, flags)));
+        }
+        if offset.checked_add(size).is_none_or(|end| end > index_offset) {
+            return Err(archive_error(path, format!("'{}' lies outside the data area", name)));
+        }
+        entries.insert(name, EntryInfo { offset, size, hash });
+    }
+    Ok(entries)
+}
+
+/// Read one entry's data and check its hash
+fn read_data(reader: &mut (impl Read + Seek), path: &Path, name: &str, info: EntryInfo) -> AssetResult<Vec<u8>> {
+    reader.seek(SeekFrom::Start(info.offset)).map_err(|e| io_error(path, e))?;
+    let size = usize::try_from(info.size).map_err(|_| archive_error(path, format!("'{}' is too large", name)))?;
+    let mut data = vec![0u8; size];
+    reader.read_exact(&mut data).map_err(|e| io_error(path, e))?;
+
+    let actual = xxh64(&data, 0);
+    if actual != info.hash {
+        return Err(AssetError::Corrupt { entry: name.to_string(), expected: info.hash, actual });
+    }
+    Ok(data)
+}
+
+/// A mounted archive
+#[derive(Debug)]
+pub struct Archive {
+    path: PathBuf,
+    entries: HashMap<String, EntryInfo>,
+}
+
+impl Archive {
+    /// Open an archive and read its index
+    pub fn open(path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path).map_err(|e| io_error(&path, e))?;
+        let file_size = file.metadata().map_err(|e| io_error(&path, e))?.len();
+        let mut reader = BufReader::new(file);
+        let entries = read_index(&mut reader, &path, file_size)?;
+        Ok(Self { path, entries })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Entry names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.entries.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(&normalize_path(name))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Read an entry, checking its hash (None if the archive lacks it)
+    pub fn read(&self, name: &str) -> Option<AssetResult<Vec<u8>>> {
+        let name = normalize_path(name);
+        let info = *self.entries.get(&name)?;
+        Some(self.read_entry(&name, info))
+    }
+
+    fn read_entry(&self, name: &str, info: EntryInfo) -> AssetResult<Vec<u8>> {
+        let mut file = File::open(&self.path).map_err(|e| io_error(&self.path, e))?;
+        read_data(&mut file, &self.path, name, info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("epicx-archive-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A file of `len` bytes that reads as zeros except for `chunks`
+    ///
+    /// Stands in for archives too large to write in a test.
+    struct SparseFile {
+        chunks: Vec<(u64, Vec<u8>)>,
+        len: u64,
+        position: u64,
+    }
+
+    impl Read for SparseFile {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.len.saturating_sub(self.position) as usize);
+            let (start, end) = (self.position, self.position + n as u64);
+            buf[..n].fill(0);
+            for (offset, data) in &self.chunks {
+                let (from, to) = (start.max(*offset), end.min(offset + data.len() as u64));
+                if from < to {
+                    buf[(from - start) as usize..(to - start) as usize]
+                        .copy_from_slice(&data[(from - offset) as usize..(to - offset) as usize]);
+                }
+            }
+            self.position = end;
+            Ok(n)
+        }
+    }
+
+    impl Seek for SparseFile {
+        fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+            self.position = match position {
+                SeekFrom::Start(offset) => offset,
+                SeekFrom::End(delta) => self.len.saturating_add_signed(delta),
+                SeekFrom::Current(delta) => self.position.saturating_add_signed(delta),
+            };
+            Ok(self.position)
+        }
+    }
+
+    #[test]
+    fn pack_and_read_round_trip() {
+        let dir = temp_dir("round-trip");
+        std::fs::create_dir_all(dir.join("textures/ui")).unwrap();
+        std::fs::write(dir.join("config.json"), "{}").unwrap();
+        std::fs::write(dir.join("textures/ui/button.png"), [0u8, 1, 2, 255]).unwrap();
+        std::fs::write(dir.join("empty.txt"), "").unwrap();
+
+        // Packing into the directory twice must not pick up the archive itself
+        let out = dir.join("assets.epak");
+        assert_eq!(pack(&dir, &out).unwrap(), 3);
+        assert_eq!(pack(&dir, &out).unwrap(), 3);
+
+        let archive = Archive::open(&out).unwrap();
+        assert_eq!(archive.names(), ["config.json", "empty.txt", "textures/ui/button.png"]);
+        assert!(archive.contains("textures\\ui\\button.png"));
+        assert_eq!(archive.read("config.json").unwrap().unwrap(), b"{}");
+        assert_eq!(archive.read("./textures/ui/button.png").unwrap().unwrap(), [0u8, 1, 2, 255]);
+        assert!(archive.read("empty.txt").unwrap().unwrap().is_empty());
+        assert!(archive.read("missing.txt").is_none());
+
+        // Flipping a data byte is caught by the hash
+        let mut bytes = std::fs::read(&out).unwrap();
+        bytes[HEADER_SIZE as usize] ^= 0xFF;
+        std::fs::write(&out, bytes).unwrap();
+        let archive = Archive::open(&out).unwrap();
+        assert!(matches!(archive.read("config.json"), Some(Err(AssetError::Corrupt { .. }))));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn offsets_past_4gb() {
+        let data = b"far away".to_vec();
+        let info = EntryInfo { offset: 5 << 30, size: data.len() as u64, hash: xxh64(&data, 0) };
+        let index_offset = info.offset + info.size;
+
+        let mut header = Vec::new();
+        write_header(&mut header, 1, index_offset).unwrap();
+        let mut index = Vec::new();
+        write_index_entry(&mut index, "big.bin", &info).unwrap();
+        let len = index_offset + index.len() as u64;
+        let mut file = SparseFile {
+            chunks: vec![(0, header), (info.offset, data.clone()), (index_offset, index)],
+            len,
+            position: 0,
+        };
+
+        let path = Path::new("big.epak");
+        let entries = read_index(&mut file, path, len).unwrap();
+        let read = entries["big.bin"];
+        assert_eq!((read.offset, read.size), (info.offset, info.size));
+        assert_eq!(read_data(&mut file, path, "big.bin", read).unwrap(), data);
+    }
+
+    #[test]
+    fn corrupt_name_length_is_rejected_before_allocating() {
+        let mut bytes = Vec::new();
+        write_header(&mut bytes, 1, HEADER_SIZE).unwrap();
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        let len = bytes.len() as u64;
+        let mut file = SparseFile { chunks: vec![(0, bytes)], len, position: 0 };
+
+        match read_index(&mut file, Path::new("bad.epak"), len) {
+            Err(AssetError::Archive { message, .. }) => assert!(message.contains("past the end"), "{}", message),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,650 @@
+//! Assets - typed, reference-counted handles with hot reload
+//!
+//! `AssetServer::load` returns a `Handle<T>` for a path relative to the
+//! server's root. Loading the same path again returns the same handle; a
+//! file with identical contents under another path gets its own handle
+//! sharing the decoded value, so each path still reloads on its own. Call
+//! `maintain` once per frame: it reloads files that changed on disk,
+//! notifying subscribers, and releases assets no handle has used for the
+//! grace period.
+//!
+//! For distribution, `pack` bundles a directory into one `.epak` file and
+//! `AssetServer::mount` serves paths from it. Loose files under the root
+//! still win, so edited files override the archive during development.
+//!
+//! `TransitionLoader` preloads the next scene's assets in the background.
+
+mod archive;
+mod transition;
+
+pub use archive::{pack, Archive};
+pub use transition::{TransitionLoader, TransitionProgress};
+
+use crate::hashing::xxh64;
+use parking_lot::{Mutex, RwLock};
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use thiserror::Error;
+
+/// Asset errors
+#[derive(Error, Debug)]
+pub enum AssetError {
+    #[error("Failed to read '{path}': {source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("Failed to load '{path}': {message}")]
+    Load { path: String, message: String },
+    #[error("Invalid archive '{path}': {message}")]
+    Archive { path: String, message: String },
+    #[error("Corrupt archive entry '{entry}': hash {actual:016x}, expected {expected:016x}")]
+    Corrupt { entry: String, expected: u64, actual: u64 },
+}
+
+pub type AssetResult<T> = Result<T, AssetError>;
+
+/// A type that can be loaded from a file's bytes
+pub trait Asset: Sized + Send + Sync + 'static {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String>;
+
+    /// Approximate memory used, heap included
+    ///
+    /// Feeds `AssetServer::stats` and the `TransitionLoader` frame budget.
+    fn memory_size(&self) -> usize;
+}
+
+impl Asset for Vec<u8> {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        Ok(bytes.to_vec())
+    }
+
+    fn memory_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl Asset for String {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+    }
+
+    fn memory_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl Asset for crate::sdf::SceneDescription {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let json = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+        Self::from_json(json).map_err(|e| e.to_string())
+    }
+
+    fn memory_size(&self) -> usize {
+        crate::sdf::SceneDescription::memory_size(self)
+    }
+}
+
+/// Unique id of a loaded asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetId(u64);
+
+impl AssetId {
+    fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+struct Slot<T> {
+    id: AssetId,
+    path: String,
+    value: RwLock<Arc<T>>,
+    version: AtomicU64,
+}
+
+/// Shared handle to a loaded asset
+///
+/// Cloning is cheap. The asset stays loaded while any handle exists, and
+/// `get` always returns the latest reloaded value.
+pub struct Handle<T> {
+    slot: Arc<Slot<T>>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self { slot: self.slot.clone() }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.slot.id == other.slot.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handle").field("id", &self.slot.id).field("path", &self.slot.path).finish()
+    }
+}
+
+impl<T> Handle<T> {
+    pub fn id(&self) -> AssetId {
+        self.slot.id
+    }
+
+    /// Path the asset was loaded from
+    pub fn path(&self) -> &str {
+        &self.slot.path
+    }
+
+    /// Current value
+    pub fn get(&self) -> Arc<T> {
+        self.slot.value.read().clone()
+    }
+
+    /// Number of times the asset has been reloaded
+    pub fn version(&self) -> u64 {
+        self.slot.version.load(Ordering::Acquire)
+    }
+}
+
+/// Type-erased slot so the server can manage assets of any type
+trait ErasedSlot: Send + Sync {
+    fn reload(&self, bytes: &[u8]) -> Result<(), String>;
+    fn memory_size(&self) -> usize;
+    /// Identity of the current value, shared by deduplicated assets
+    fn value_key(&self) -> usize;
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+impl<T: Asset> ErasedSlot for Slot<T> {
+    fn reload(&self, bytes: &[u8]) -> Result<(), String> {
+        let value = T::from_bytes(bytes)?;
+        *self.value.write() = Arc::new(value);
+        self.version.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    fn memory_size(&self) -> usize {
+        self.value.read().memory_size()
+    }
+
+    fn value_key(&self) -> usize {
+        Arc::as_ptr(&*self.value.read()) as *const () as usize
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+struct Entry {
+    type_id: TypeId,
+    slot: Arc<dyn ErasedSlot>,
+    id: AssetId,
+    path: String,
+    content_hash: u64,
+    modified: Option<SystemTime>,
+    unused_since: Option<Instant>,
+}
+
+/// Something `maintain` did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetEvent {
+    Reloaded { id: AssetId, path: String },
+    /// A changed file failed to load; the previous value is kept
+    ReloadFailed { id: AssetId, path: String, message: String },
+    Released { id: AssetId, path: String },
+}
+
+/// Asset server statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AssetStats {
+    pub loaded: usize,
+    /// Approximate memory used by loaded assets
+    pub bytes: usize,
+    /// Loaded assets with no handles, waiting out the grace period
+    pub unused: usize,
+    /// Loads answered with an existing asset or value
+    pub dedup_hits: u64,
+    pub reloads: u64,
+    pub released: u64,
+}
+
+type Subscriber = Box<dyn Fn(&AssetEvent) + Send + Sync>;
+
+#[derive(Default)]
+struct Registry {
+    entries: HashMap<AssetId, Entry>,
+    by_path: HashMap<(TypeId, String), AssetId>,
+    /// One asset per distinct contents, whose value new loads can share
+    by_content: HashMap<(TypeId, u64), AssetId>,
+}
+
+impl Registry {
+    /// Stop offering `id` for `hash`, handing the key to another asset with those contents
+    fn unindex_content(&mut self, type_id: TypeId, hash: u64, id: AssetId) {
+        let key = (type_id, hash);
+        if self.by_content.get(&key) != Some(&id) {
+            return;
+        }
+        let other = self
+            .entries
+            .values()
+            .find(|e| e.id != id && e.type_id == type_id && e.content_hash == hash)
+            .map(|e| e.id);
+        match other {
+            Some(other) => self.by_content.insert(key, other),
+            None => self.by_content.remove(&key),
+        };
+    }
+}
+
+/// Loads assets and manages their lifetime
+pub struct AssetServer {
+    root: PathBuf,
+    grace_period: Duration,
+    registry: Mutex<Registry>,
+    /// Mounted archives, searched last-mounted first
+    archives: RwLock<Vec<Archive>>,
+    subscribers: RwLock<Vec<Subscriber>>,
+    stats: Mutex<AssetStats>,
+}
+
+/// Normalize a path to the form used as a key (`/` separators, no `./`)
+pub fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl AssetServer {
+    /// Create a server loading paths relative to `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            grace_period: Duration::from_secs(5),
+            registry: Mutex::new(Registry::default()),
+            archives: RwLock::new(Vec::new()),
+            subscribers: RwLock::new(Vec::new()),
+            stats: Mutex::new(AssetStats::default()),
+        }
+    }
+
+    /// How long an asset with no handles stays loaded (default 5 seconds)
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn file_path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    /// Mount an archive; its entries resolve when no loose file exists
+    pub fn mount(&self, archive_path: impl AsRef<Path>) -> AssetResult<()> {
+        let archive = Archive::open(archive_path)?;
+        log::info!("Mounted {} ({} entries)", archive.path().display(), archive.len());
+        self.archives.write().push(archive);
+        Ok(())
+    }
+
+    fn read(&self, path: &str) -> AssetResult<Vec<u8>> {
+        match std::fs::read(self.file_path(path)) {
+            Ok(bytes) => Ok(bytes),
+            Err(source) if source.kind() == std::io::ErrorKind::NotFound => self
+                .archives
+                .read()
+                .iter()
+                .rev()
+                .find_map(|archive| archive.read(path))
+                .unwrap_or(Err(AssetError::Io { path: path.to_string(), source })),
+            Err(source) => Err(AssetError::Io { path: path.to_string(), source }),
+        }
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        std::fs::metadata(self.file_path(path)).and_then(|m| m.modified()).ok()
+    }
+
+    /// Load an asset, or get the already loaded one
+    pub fn load<T: Asset>(&self, path: &str) -> AssetResult<Handle<T>> {
+        let path = normalize_path(path);
+        let type_id = TypeId::of::<T>();
+
+        if let Some(handle) = self.lookup::<T>(&path) {
+            self.stats.lock().dedup_hits += 1;
+            return Ok(handle);
+        }
+
+        let bytes = self.read(&path)?;
+        let content_hash = xxh64(&bytes, 0);
+        // Decode outside the registry lock so other loads aren't blocked
+        let value = match self.shared_value::<T>(content_hash) {
+            Some(value) => {
+                self.stats.lock().dedup_hits += 1;
+                value
+            }
+            None => Arc::new(T::from_bytes(&bytes).map_err(|message| AssetError::Load { path: path.clone(), message })?),
+        };
+
+        let mut registry = self.registry.lock();
+        // Loaded by another thread while this one was reading or decoding
+        if let Some(entry) = registry.by_path.get(&(type_id, path.clone())).and_then(|id| registry.entries.get(id)) {
+            return Ok(Self::downcast(entry.slot.clone()));
+        }
+
+        let id = AssetId::new();
+        let slot = Arc::new(Slot {
+            id,
+            path: path.clone(),
+            value: RwLock::new(value),
+            version: AtomicU64::new(0),
+        });
+        registry.entries.insert(
+            id,
+            Entry {
+                type_id,
+                slot: slot.clone(),
+                id,
+                path: path.clone(),
+                content_hash,
+                modified: self.modified(&path),
+                unused_since: None,
+            },
+        );
+        registry.by_path.insert((type_id, path), id);
+        registry.by_content.entry((type_id, content_hash)).or_insert(id);
+        Ok(Handle { slot })
+    }
+
+    /// Value of a loaded asset with the same contents, if any
+    fn shared_value<T: Asset>(&self, content_hash: u64) -> Option<Arc<T>> {
+        let registry = self.registry.lock();
+        let id = registry.by_content.get(&(TypeId::of::<T>(), content_hash))?;
+        let slot = registry.entries.get(id)?.slot.clone();
+        Some(Self::downcast::<T>(slot).get())
+    }
+
+    /// Get an already loaded asset without touching the file system
+    pub fn get<T: Asset>(&self, path: &str) -> Option<Handle<T>> {
+        self.lookup(&normalize_path(path))
+    }
+
+    fn lookup<T: Asset>(&self, path: &str) -> Option<Handle<T>> {
+        let registry = self.registry.lock();
+        let id = registry.by_path.get(&(TypeId::of::<T>(), path.to_string()))?;
+        registry.entries.get(id).map(|entry| Self::downcast(entry.slot.clone()))
+    }
+
+    fn downcast<T: Asset>(slot: Arc<dyn ErasedSlot>) -> Handle<T> {
+        let slot = slot.into_any().downcast::<Slot<T>>().expect("asset type matches its key");
+        Handle { slot }
+    }
+
+    /// Call `f` for every reload and release
+    pub fn subscribe(&self, f: impl Fn(&AssetEvent) + Send + Sync + 'static) {
+        self.subscribers.write().push(Box::new(f));
+    }
+
+    /// Reload changed files and release unused assets (call once per frame)
+    pub fn maintain(&self) -> Vec<AssetEvent> {
+        self.maintain_at(Instant::now())
+    }
+
+    /// `maintain` with an explicit current time
+    pub fn maintain_at(&self, now: Instant) -> Vec<AssetEvent> {
+        let mut events = Vec::new();
+        let mut registry = self.registry.lock();
+
+        // Hot reload
+        let mut rehashed = Vec::new();
+        for entry in registry.entries.values_mut() {
+            let path = &entry.path;
+            let modified = self.modified(path);
+            if modified.is_none() || modified == entry.modified {
+                continue;
+            }
+            entry.modified = modified;
+            let result = self
+                .read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| entry.slot.reload(&bytes).map(|()| xxh64(&bytes, 0)));
+            match result {
+                Ok(hash) => {
+                    rehashed.push((entry.type_id, entry.content_hash, hash, entry.id));
+                    entry.content_hash = hash;
+                    events.push(AssetEvent::Reloaded { id: entry.id, path: path.clone() });
+                }
+                Err(message) => {
+                    log::warn!("Reloading '{}' failed: {}", path, message);
+                    events.push(AssetEvent::ReloadFailed { id: entry.id, path: path.clone(), message });
+                }
+            }
+        }
+        // A reloaded asset gets a value of its own; others with its old contents keep theirs
+        for (type_id, old, new, id) in rehashed {
+            registry.unindex_content(type_id, old, id);
+            registry.by_content.entry((type_id, new)).or_insert(id);
+        }
+
+        // Release assets only the registry still references
+        let mut released = Vec::new();
+        for entry in registry.entries.values_mut() {
+            if Arc::strong_count(&entry.slot) > 1 {
+                entry.unused_since = None;
+                continue;
+            }
+            let since = *entry.unused_since.get_or_insert(now);
+            if now.duration_since(since) >= self.grace_period {
+                released.push(entry.id);
+            }
+        }
+        for id in released {
+            let entry = registry.entries.remove(&id).expect("released asset exists");
+            registry.by_path.remove(&(entry.type_id, entry.path.clone()));
+            registry.unindex_content(entry.type_id, entry.content_hash, id);
+            events.push(AssetEvent::Released { id, path: entry.path });
+        }
+        drop(registry);
+
+        {
+            let mut stats = self.stats.lock();
+            for event in &events {
+                match event {
+                    AssetEvent::Reloaded { .. } => stats.reloads += 1,
+                    AssetEvent::Released { .. } => stats.released += 1,
+                    AssetEvent::ReloadFailed { .. } => {}
+                }
+            }
+        }
+        let subscribers = self.subscribers.read();
+        for event in &events {
+            for subscriber in subscribers.iter() {
+                subscriber(event);
+            }
+        }
+        events
+    }
+
+    /// Current statistics (for the memory overlay)
+    pub fn stats(&self) -> AssetStats {
+        let registry = self.registry.lock();
+        let mut stats = *self.stats.lock();
+        stats.loaded = registry.entries.len();
+        // Values shared by identical files count once
+        let mut counted = HashSet::new();
+        stats.bytes = registry
+            .entries
+            .values()
+            .filter(|e| counted.insert(e.slot.value_key()))
+            .map(|e| e.slot.memory_size())
+            .sum();
+        stats.unused = registry.entries.values().filter(|e| Arc::strong_count(&e.slot) == 1).count();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Scratch directory that is removed when the test ends, pass or fail
+    struct TempRoot(PathBuf);
+
+    impl std::ops::Deref for TempRoot {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempRoot {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn temp_root(name: &str) -> TempRoot {
+        let root = std::env::temp_dir().join(format!("epicx-assets-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        TempRoot(root)
+    }
+
+    /// Write a file dated `ahead_secs` from now, so a rewrite within one
+    /// timestamp tick still looks changed
+    fn write(root: &Path, name: &str, contents: impl AsRef<[u8]>, ahead_secs: u64) {
+        let path = root.join(name);
+        std::fs::write(&path, contents).unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(ahead_secs)).unwrap();
+    }
+
+    #[test]
+    fn same_path_returns_same_handle() {
+        let root = temp_root("same-path");
+        write(&root, "a.txt", "hello", 0);
+        let server = AssetServer::new(root.to_path_buf());
+
+        let first = server.load::<String>("a.txt").unwrap();
+        let second = server.load::<String>("./a.txt").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(server.stats().loaded, 1);
+        assert_eq!(server.stats().dedup_hits, 1);
+    }
+
+    #[test]
+    fn identical_files_share_value_but_not_handle() {
+        let root = temp_root("identical");
+        write(&root, "a.txt", "hello", 0);
+        write(&root, "b.txt", "hello", 0);
+        let server = AssetServer::new(root.to_path_buf());
+
+        let a = server.load::<String>("a.txt").unwrap();
+        let b = server.load::<String>("b.txt").unwrap();
+        assert_ne!(a, b);
+        assert!(Arc::ptr_eq(&a.get(), &b.get()));
+        let stats = server.stats();
+        assert_eq!((stats.loaded, stats.bytes, stats.dedup_hits), (2, 5, 1));
+    }
+
+    #[test]
+    fn released_after_last_handle_and_grace_period() {
+        let root = temp_root("release");
+        write(&root, "a.txt", "hello", 0);
+        let server = AssetServer::new(root.to_path_buf()).with_grace_period(Duration::from_secs(1));
+        let start = Instant::now();
+
+        let handle = server.load::<String>("a.txt").unwrap();
+        let id = handle.id();
+        assert!(server.maintain_at(start + Duration::from_secs(5)).is_empty());
+
+        drop(handle);
+        assert!(server.maintain_at(start).is_empty());
+        assert_eq!(server.stats().unused, 1);
+        assert!(server.maintain_at(start + Duration::from_millis(500)).is_empty());
+        let events = server.maintain_at(start + Duration::from_secs(1));
+        assert_eq!(events, vec![AssetEvent::Released { id, path: "a.txt".to_string() }]);
+        assert_eq!(server.stats().loaded, 0);
+        assert!(server.get::<String>("a.txt").is_none());
+        assert_ne!(server.load::<String>("a.txt").unwrap().id(), id);
+    }
+
+    #[test]
+    fn reload_notifies_every_subscriber() {
+        let root = temp_root("fan-out");
+        write(&root, "a.txt", "hello", 0);
+        let server = AssetServer::new(root.to_path_buf());
+        let handle = server.load::<String>("a.txt").unwrap();
+
+        let counts: Vec<Arc<AtomicUsize>> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        for count in &counts {
+            let count = count.clone();
+            server.subscribe(move |event| {
+                if matches!(event, AssetEvent::Reloaded { .. }) {
+                    count.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+
+        write(&root, "a.txt", "changed", 10);
+        let events = server.maintain();
+        assert_eq!(events, vec![AssetEvent::Reloaded { id: handle.id(), path: "a.txt".to_string() }]);
+        assert!(counts.iter().all(|c| c.load(Ordering::SeqCst) == 1));
+        assert_eq!(handle.version(), 1);
+        assert_eq!(*handle.get(), "changed");
+
+        // Unchanged files aren't reloaded again
+        assert!(server.maintain().is_empty());
+    }
+
+    #[test]
+    fn reloading_one_of_two_identical_files_leaves_the_other() {
+        let root = temp_root("split");
+        write(&root, "a.txt", "hello", 0);
+        write(&root, "b.txt", "hello", 0);
+        let server = AssetServer::new(root.to_path_buf());
+        let a = server.load::<String>("a.txt").unwrap();
+        let b = server.load::<String>("b.txt").unwrap();
+
+        write(&root, "b.txt", "bye", 10);
+        let events = server.maintain();
+        assert_eq!(events, vec![AssetEvent::Reloaded { id: b.id(), path: "b.txt".to_string() }]);
+        assert_eq!(*a.get(), "hello");
+        assert_eq!(*b.get(), "bye");
+        assert_eq!(a.version(), 0);
+
+        // A new copy of the old contents still shares a's value
+        write(&root, "c.txt", "hello", 0);
+        let c = server.load::<String>("c.txt").unwrap();
+        assert!(Arc::ptr_eq(&a.get(), &c.get()));
+    }
+
+    #[test]
+    fn failed_reload_keeps_previous_value() {
+        let root = temp_root("failed-reload");
+        write(&root, "a.bin", [0x68, 0x69], 0);
+        let server = AssetServer::new(root.to_path_buf());
+        let handle = server.load::<String>("a.bin").unwrap();
+
+        write(&root, "a.bin", [0xff, 0xfe], 10);
+        let events = server.maintain();
+        assert!(matches!(events.as_slice(), [AssetEvent::ReloadFailed { .. }]));
+        assert_eq!(*handle.get(), "hi");
+        assert_eq!(handle.version(), 0);
+    }
+}
@@ -0,0 +1,332 @@
+//! Preloading the assets of the next scene over several frames
+//!
+//! `TransitionLoader` takes a manifest of paths, decodes them on worker
+//! threads through the `AssetServer`, and hands finished assets to their
+//! upload callbacks from `update` at no more than a per-frame byte budget.
+//! The scene switch waits for `is_ready`; `finish` does the rest at once
+//! when the player outruns the budget (show a loading overlay first).
+
+use super::{Asset, AssetError, AssetResult, AssetServer, Handle};
+use parking_lot::Mutex;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+type Upload = Box<dyn FnOnce() + Send>;
+type Job = Box<dyn FnOnce(&AssetServer) -> Decoded + Send>;
+
+/// A decoded asset waiting for its upload
+struct Decoded {
+    path: String,
+    result: AssetResult<(usize, Box<dyn Any + Send>, Upload)>,
+}
+
+/// Where a transition is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransitionProgress {
+    /// Assets in the manifest
+    pub total: usize,
+    /// Assets decoded and uploaded
+    pub resident: usize,
+    /// Assets that failed to load (see `TransitionLoader::errors`)
+    pub failed: usize,
+    /// Bytes uploaded by the last `update`
+    pub frame_bytes: usize,
+}
+
+impl TransitionProgress {
+    /// Fraction done, failures included, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.resident + self.failed) as f32 / self.total as f32
+        }
+    }
+}
+
+/// Loads a scene's assets in the background with a per-frame upload budget
+pub struct TransitionLoader {
+    server: Arc<AssetServer>,
+    frame_budget: usize,
+    worker_count: usize,
+    queue: Arc<Mutex<VecDeque<Job>>>,
+    cancelled: Arc<AtomicBool>,
+    sender: Sender<Decoded>,
+    receiver: Receiver<Decoded>,
+    /// Workers still running; they exit once the queue is empty
+    live_workers: Arc<AtomicUsize>,
+    /// Decoded but over the budget, uploaded in a later frame
+    waiting: VecDeque<Decoded>,
+    resident: HashMap<String, Box<dyn Any + Send>>,
+    errors: Vec<AssetError>,
+    progress: TransitionProgress,
+}
+
+impl TransitionLoader {
+    /// Create a loader with an 8 MB frame budget and two workers
+    pub fn new(server: Arc<AssetServer>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            server,
+            frame_budget: 8 * 1024 * 1024,
+            worker_count: 2,
+            queue: Arc::new(Mutex::new(VecDeque::new())),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            sender,
+            receiver,
+            live_workers: Arc::new(AtomicUsize::new(0)),
+            waiting: VecDeque::new(),
+            resident: HashMap::new(),
+            errors: Vec::new(),
+            progress: TransitionProgress::default(),
+        }
+    }
+
+    /// Bytes uploaded per `update` (at least one asset is always uploaded)
+    pub fn with_frame_budget(mut self, bytes: usize) -> Self {
+        self.frame_budget = bytes;
+        self
+    }
+
+    /// Number of decode threads (default 2)
+    pub fn with_workers(mut self, count: usize) -> Self {
+        self.worker_count = count.max(1);
+        self
+    }
+
+    /// Add an asset to the manifest
+    pub fn add<T: Asset>(&mut self, path: &str) {
+        self.add_with_upload::<T>(path, |_| {});
+    }
+
+    /// Add an asset and the work that makes it resident (e.g. a GPU upload)
+    ///
+    /// `upload` runs on the thread calling `update`, within the budget.
+    pub fn add_with_upload<T: Asset>(&mut self, path: &str, upload: impl FnOnce(&Handle<T>) + Send + 'static) {
+        let path = path.to_string();
+        self.progress.total += 1;
+        self.queue.lock().push_back(Box::new(move |server: &AssetServer| {
+            // A panicking decoder becomes a failure instead of a missing result
+            let loaded = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                server.load::<T>(&path).map(|handle| (handle.get().memory_size(), handle))
+            }));
+            let result = match loaded {
+                Ok(result) => result.map(|(bytes, handle)| {
+                    let uploaded = handle.clone();
+                    let upload: Upload = Box::new(move || upload(&uploaded));
+                    (bytes, Box::new(handle) as Box<dyn Any + Send>, upload)
+                }),
+                Err(_) => Err(AssetError::Load { path: path.clone(), message: "decoder panicked".to_string() }),
+            };
+            Decoded { path, result }
+        }));
+    }
+
+    /// Top the workers back up to `worker_count`
+    fn start(&self) {
+        // Workers decide to exit while holding the queue lock, so none can
+        // leave a job behind between this count and the spawns below
+        let queue = self.queue.lock();
+        let live = self.live_workers.load(Ordering::Acquire);
+        let missing = self.worker_count.saturating_sub(live).min(queue.len());
+        self.live_workers.fetch_add(missing, Ordering::AcqRel);
+        drop(queue);
+
+        for _ in 0..missing {
+            let (server, queue, cancelled) = (self.server.clone(), self.queue.clone(), self.cancelled.clone());
+            let (sender, live_workers) = (self.sender.clone(), self.live_workers.clone());
+            std::thread::spawn(move || loop {
+                let job = {
+                    let mut queue = queue.lock();
+                    let job = if cancelled.load(Ordering::Relaxed) { None } else { queue.pop_front() };
+                    match job {
+                        Some(job) => job,
+                        None => {
+                            live_workers.fetch_sub(1, Ordering::AcqRel);
+                            return;
+                        }
+                    }
+                };
+                if sender.send(job(&server)).is_err() {
+                    live_workers.fetch_sub(1, Ordering::AcqRel);
+                    return;
+                }
+            });
+        }
+    }
+
+    /// Make decoded assets resident up to the frame budget (call once per frame)
+    pub fn update(&mut self) -> TransitionProgress {
+        self.start();
+        self.waiting.extend(self.receiver.try_iter());
+
+        let mut frame_bytes = 0;
+        while let Some(decoded) = self.waiting.front() {
+            let bytes = decoded.result.as_ref().map_or(0, |(bytes, _, _)| *bytes);
+            if frame_bytes > 0 && frame_bytes + bytes > self.frame_budget {
+                break;
+            }
+            let decoded = self.waiting.pop_front().expect("front exists");
+            frame_bytes += bytes;
+            self.make_resident(decoded);
+        }
+        self.progress.frame_bytes = frame_bytes;
+        self.progress
+    }
+
+    /// Wait for every asset and upload it, ignoring the budget
+    pub fn finish(&mut self) -> TransitionProgress {
+        self.start();
+        let mut frame_bytes = 0;
+        while !self.is_ready() {
+            let decoded = match self.waiting.pop_front() {
+                Some(decoded) => decoded,
+                // Every queued job sends a result, decoder panics included
+                None => match self.receiver.recv() {
+                    Ok(decoded) => decoded,
+                    Err(_) => break,
+                },
+            };
+            frame_bytes += decoded.result.as_ref().map_or(0, |(bytes, _, _)| *bytes);
+            self.make_resident(decoded);
+        }
+        self.progress.frame_bytes = frame_bytes;
+        self.progress
+    }
+
+    fn make_resident(&mut self, decoded: Decoded) {
+        match decoded.result {
+            Ok((_, handle, upload)) => {
+                upload();
+                self.resident.insert(decoded.path, handle);
+                self.progress.resident += 1;
+            }
+            Err(e) => {
+                log::error!("Transition asset '{}' failed: {}", decoded.path, e);
+                self.errors.push(e);
+                self.progress.failed += 1;
+            }
+        }
+    }
+
+    pub fn progress(&self) -> TransitionProgress {
+        self.progress
+    }
+
+    /// Check if every asset in the manifest is resident or has failed
+    pub fn is_ready(&self) -> bool {
+        self.progress.resident + self.progress.failed == self.progress.total
+    }
+
+    /// Load failures so far
+    pub fn errors(&self) -> &[AssetError] {
+        &self.errors
+    }
+
+    /// Handle to a resident asset (None until its upload has run)
+    pub fn handle<T: Asset>(&self, path: &str) -> Option<Handle<T>> {
+        self.resident.get(path)?.downcast_ref::<Handle<T>>().cloned()
+    }
+}
+
+impl Drop for TransitionLoader {
+    fn drop(&mut self) {
+        // Workers stop after their current asset
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    static DECODING: AtomicUsize = AtomicUsize::new(0);
+    static PEAK_DECODING: AtomicUsize = AtomicUsize::new(0);
+
+    /// An asset that takes a while to decode and counts concurrent decodes
+    struct Slow(usize);
+
+    impl Asset for Slow {
+        fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+            let decoding = DECODING.fetch_add(1, Ordering::SeqCst) + 1;
+            PEAK_DECODING.fetch_max(decoding, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(10));
+            DECODING.fetch_sub(1, Ordering::SeqCst);
+            Ok(Slow(bytes.len()))
+        }
+
+        fn memory_size(&self) -> usize {
+            self.0
+        }
+    }
+
+    /// A directory of `count` files of `size` bytes named `0.bin`, `1.bin`, ...
+    fn temp_root(name: &str, count: usize, size: usize) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("epicx-transition-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        for i in 0..count {
+            // Different contents, so nothing is deduplicated
+            std::fs::write(root.join(format!("{}.bin", i)), vec![i as u8; size]).unwrap();
+        }
+        root
+    }
+
+    fn loader(root: &Path) -> TransitionLoader {
+        TransitionLoader::new(Arc::new(AssetServer::new(root)))
+    }
+
+    #[test]
+    fn adding_while_loading_keeps_the_worker_count() {
+        let root = temp_root("workers", 12, 100);
+        let mut loader = loader(&root).with_workers(2);
+        for i in 0..4 {
+            loader.add::<Slow>(&format!("{}.bin", i));
+        }
+        loader.update();
+        for i in 4..12 {
+            loader.add::<Slow>(&format!("{}.bin", i));
+            loader.update();
+            assert!(loader.live_workers.load(Ordering::Acquire) <= 2);
+        }
+
+        let progress = loader.finish();
+        assert_eq!((progress.resident, progress.failed), (12, 0));
+        assert!(PEAK_DECODING.load(Ordering::SeqCst) <= 2);
+        assert_eq!(loader.handle::<Slow>("11.bin").unwrap().get().0, 100);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn uploads_stay_within_the_frame_budget() {
+        let root = temp_root("budget", 6, 1000);
+        let mut loader = loader(&root).with_frame_budget(2500);
+        let uploaded = Arc::new(AtomicUsize::new(0));
+        for i in 0..6 {
+            let uploaded = uploaded.clone();
+            loader.add_with_upload::<Vec<u8>>(&format!("{}.bin", i), move |handle| {
+                uploaded.fetch_add(handle.get().len(), Ordering::Relaxed);
+            });
+        }
+        loader.add::<Vec<u8>>("missing.bin");
+
+        let mut busy_frames = 0;
+        while !loader.is_ready() {
+            let progress = loader.update();
+            assert!(progress.frame_bytes <= 2500);
+            busy_frames += (progress.frame_bytes > 0) as usize;
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(busy_frames >= 3);
+        assert_eq!(uploaded.load(Ordering::Relaxed), 6000);
+        assert_eq!((loader.progress().resident, loader.progress().failed), (6, 1));
+        assert_eq!(loader.errors().len(), 1);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,432 @@
+//! DirectX12 backend
+//!
+//! Owns the Level A objects a window needs (device, queue, swap chain,
+//! allocator) and records frames on them. `graphics::Graphics` layers
+//! tone mapping, state tracking and diagnostics on top through the
+//! `RenderBackend` trait.
+
+use super::{
+    BackendError, BackendResult, BufferDesc, BufferUsage, ColoredQuad, DrawBatch, PipelineDesc,
+    RenderBackend, TextureDesc, TextureFormat, VertexAttribute, VertexFormat,
+};
+use crate::dx12::{
+    self, builtin, Buffer, CommandAllocator, CommandList, CommandQueue, Device, Dx12Error, Dx12Result, Pipeline,
+    PipelineOptions, PipelineState, ResourceStateTracker, RootSignature, ShaderCompiler, ShaderType, SwapChain,
+    SwapChainConfig, SwapChainFormat, Texture, VertexBuffer, WindowHandle,
+};
+use crate::math::{Color, Rect};
+use std::ffi::CString;
+use windows::core::PCSTR;
+use windows::Win32::Graphics::Direct3D::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST;
+use windows::Win32::Graphics::Direct3D12::*;
+use windows::Win32::Graphics::Dxgi::Common::*;
+
+fn dxgi_format(format: TextureFormat) -> DXGI_FORMAT {
+    match format {
+        TextureFormat::Rgba8Unorm => DXGI_FORMAT_R8G8B8A8_UNORM,
+        TextureFormat::Rgba8UnormSrgb => DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+        TextureFormat::Rgba16Float => DXGI_FORMAT_R16G16B16A16_FLOAT,
+        TextureFormat::Depth32Float => DXGI_FORMAT_D32_FLOAT,
+    }
+}
+
+fn vertex_format(format: VertexFormat) -> DXGI_FORMAT {
+    match format {
+        VertexFormat::Float2 => DXGI_FORMAT_R32G32_FLOAT,
+        VertexFormat::Float3 => DXGI_FORMAT_R32G32B32_FLOAT,
+        VertexFormat::Float4 => DXGI_FORMAT_R32G32B32A32_FLOAT,
+    }
+}
+
+/// Create a pipeline from a portable vertex layout
+pub(crate) fn create_pipeline_with_layout(
+    device: &Device,
+    root_signature: &RootSignature,
+    vertex_shader: &[u8],
+    pixel_shader: &[u8],
+    vertex_layout: &[VertexAttribute],
+    options: PipelineOptions,
+) -> Dx12Result<PipelineState> {
+    // The names must outlive pipeline creation
+    let names = vertex_layout
+        .iter()
+        .map(|a| CString::new(a.semantic.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Dx12Error::PipelineCreation(e.to_string()))?;
+    let layout: Vec<D3D12_INPUT_ELEMENT_DESC> = vertex_layout
+        .iter()
+        .zip(&names)
+        .map(|(attribute, name)| D3D12_INPUT_ELEMENT_DESC {
+            SemanticName: PCSTR(name.as_ptr() as *const u8),
+            SemanticIndex: 0,
+            Format: vertex_format(attribute.format),
+            InputSlot: 0,
+            AlignedByteOffset: attribute.offset,
+            InputSlotClass: D3D12_INPUT_CLASSIFICATION_PER_VERTEX_DATA,
+            InstanceDataStepRate: 0,
+        })
+        .collect();
+
+    Pipeline::create_graphics_pipeline_with(device, root_signature, vertex_shader, pixel_shader, &layout, options)
+}
+
+/// Vertex of the built-in quad pipeline (`builtin::VERTEX_2D`)
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct QuadVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+/// DirectX12 implementation of `RenderBackend`
+///
+/// Pipelines share one root signature (`RootSignature::new_simple`).
+/// Quads are drawn with an alpha-blended pipeline, so translucent colors
+/// blend over what is already in the target, as in `SoftwareBackend`.
+pub struct Dx12Backend {
+    device: Device,
+    command_queue: CommandQueue,
+    swap_chain: SwapChain,
+    allocator: CommandAllocator,
+    root_signature: RootSignature,
+    /// Quad pipeline and the view format it was built for
+    quad_pipeline: Option<(DXGI_FORMAT, PipelineState)>,
+}
+
+impl Dx12Backend {
+    /// Create the backend for a window on `device`
+    pub fn new(device: Device, window: WindowHandle, config: SwapChainConfig) -> BackendResult<Self> {
+        let command_queue = CommandQueue::graphics(&device)?;
+        let swap_chain = SwapChain::new(&device, &command_queue, window.raw(), config)?;
+        let allocator = CommandAllocator::graphics(&device)?;
+        let root_signature = RootSignature::new_simple(&device)?;
+        Ok(Self {
+            device,
+            command_queue,
+            swap_chain,
+            allocator,
+            root_signature,
+            quad_pipeline: None,
+        })
+    }
+
+    /// Get the device
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Get the command queue
+    pub fn command_queue(&self) -> &CommandQueue {
+        &self.command_queue
+    }
+
+    /// Get mutable command queue
+    pub fn command_queue_mut(&mut self) -> &mut CommandQueue {
+        &mut self.command_queue
+    }
+
+    /// Get the swap chain
+    pub fn swap_chain(&self) -> &SwapChain {
+        &self.swap_chain
+    }
+
+    /// Get the swap chain mutably (flush before changing its buffers)
+    pub fn swap_chain_mut(&mut self) -> &mut SwapChain {
+        &mut self.swap_chain
+    }
+
+    /// Get the root signature shared by all pipelines
+    pub fn root_signature(&self) -> &RootSignature {
+        &self.root_signature
+    }
+
+    /// Wait for all submitted GPU work
+    pub fn flush(&mut self) -> Dx12Result<()> {
+        self.command_queue.flush()
+    }
+
+    /// Switch the output format, falling back to SDR if the display can't show it
+    pub fn set_output_format(&mut self, format: SwapChainFormat) -> Dx12Result<SwapChainFormat> {
+        self.swap_chain.set_output_format(&self.device, format)
+    }
+
+    /// Wait for the GPU and apply new swap chain settings
+    ///
+    /// See `SwapChain::reconfigure`; the previous settings are restored on failure.
+    pub fn reconfigure(&mut self, config: SwapChainConfig) -> Dx12Result<()> {
+        self.flush()?;
+        self.swap_chain.reconfigure(&self.device, config)
+    }
+
+    /// Build the quad pipeline for the current view format if needed
+    fn prepare_quad_pipeline(&mut self) -> Dx12Result<()> {
+        let format = self.swap_chain.rtv_format();
+        if matches!(&self.quad_pipeline, Some((built_for, _)) if *built_for == format) {
+            return Ok(());
+        }
+
+        let compiler = ShaderCompiler::new();
+        let vertex_shader = compiler.compile(builtin::VERTEX_2D, "main", ShaderType::Vertex)?;
+        let pixel_shader = compiler.compile(builtin::PIXEL_SIMPLE, "main", ShaderType::Pixel)?;
+        let layout = [
+            VertexAttribute { semantic: "POSITION".to_string(), format: VertexFormat::Float2, offset: 0 },
+            VertexAttribute { semantic: "COLOR".to_string(), format: VertexFormat::Float4, offset: 8 },
+        ];
+        let options = PipelineOptions {
+            alpha_blend: true,
+            render_target_format: Some(format),
+            ..Default::default()
+        };
+        let state = create_pipeline_with_layout(
+            &self.device,
+            &self.root_signature,
+            vertex_shader.bytecode(),
+            pixel_shader.bytecode(),
+            &layout,
+            options,
+        )?;
+        self.quad_pipeline = Some((format, state));
+        Ok(())
+    }
+}
+
+/// A frame recorded by `Dx12Backend`
+///
+/// The back buffer is a render target from `begin_frame` to `end_frame`.
+/// Dropping a frame without `end_frame` closes and discards its list.
+pub struct Dx12Frame {
+    cmd_list: CommandList,
+    rtv: D3D12_CPU_DESCRIPTOR_HANDLE,
+    back_buffer: ID3D12Resource,
+    states: ResourceStateTracker,
+    width: u32,
+    height: u32,
+    srgb_view: bool,
+    /// Quad vertices, kept alive until the GPU has executed the frame
+    uploads: Vec<VertexBuffer>,
+    submitted: bool,
+}
+
+impl Dx12Frame {
+    /// Get the command list the frame is recorded into
+    pub fn command_list(&self) -> &CommandList {
+        &self.cmd_list
+    }
+
+    /// Get the target size in pixels
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Check if the back buffer view encodes to sRGB on write
+    pub fn is_srgb_view(&self) -> bool {
+        self.srgb_view
+    }
+
+    /// Clear parts of the target (all of it if `rects` is empty)
+    ///
+    /// `color` is written as is, so it must already be encoded for the view.
+    pub fn clear_rects(&self, color: Color, rects: &[Rect]) {
+        self.cmd_list.clear_render_target_rects(self.rtv, [color.r, color.g, color.b, color.a], rects);
+    }
+
+    /// Convert a display color to what the view expects
+    ///
+    /// Use this for vertex colors of custom draws, as the quads do.
+    pub fn encode(&self, color: Color) -> Color {
+        if self.srgb_view {
+            // The view re-encodes on write
+            color.to_linear()
+        } else {
+            color
+        }
+    }
+
+    /// Two clockwise triangles covering the quad's pixels
+    ///
+    /// Edges are rounded like `SoftwareBackend` so both fill the same pixels.
+    fn quad_vertices(&self, quad: &ColoredQuad) -> [QuadVertex; 6] {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let x = |v: f32| v.round() / width * 2.0 - 1.0;
+        let y = |v: f32| 1.0 - v.round() / height * 2.0;
+        let (left, right) = (x(quad.rect.x), x(quad.rect.x + quad.rect.width));
+        let (top, bottom) = (y(quad.rect.y), y(quad.rect.y + quad.rect.height));
+        let c = self.encode(quad.color);
+        let color = [c.r, c.g, c.b, c.a];
+        let vertex = |x: f32, y: f32| QuadVertex { position: [x, y], color };
+        [
+            vertex(left, top),
+            vertex(right, top),
+            vertex(left, bottom),
+            vertex(right, top),
+            vertex(right, bottom),
+            vertex(left, bottom),
+        ]
+    }
+}
+
+impl Drop for Dx12Frame {
+    fn drop(&mut self) {
+        if !self.submitted {
+            let _ = self.cmd_list.close();
+        }
+    }
+}
+
+impl RenderBackend for Dx12Backend {
+    type Buffer = Buffer;
+    type Texture = Texture;
+    type Pipeline = PipelineState;
+    type Frame = Dx12Frame;
+
+    fn name(&self) -> &'static str {
+        "DirectX12"
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.swap_chain.width(), self.swap_chain.height())
+    }
+
+    fn create_buffer(&mut self, desc: &BufferDesc) -> BackendResult<Buffer> {
+        // Everything but readback lives in the upload heap so it can be written directly
+        let usage = match desc.usage {
+            BufferUsage::Readback => dx12::BufferUsage::Readback,
+            _ => dx12::BufferUsage::Upload,
+        };
+        let desc = dx12::BufferDesc { size: desc.size, usage, stride: desc.stride };
+        Ok(Buffer::new(&self.device, desc)?)
+    }
+
+    fn write_buffer(&mut self, buffer: &Buffer, data: &[u8]) -> BackendResult<()> {
+        if data.len() as u64 > buffer.size() {
+            return Err(BackendError::Backend(format!(
+                "write of {} bytes to a {} byte buffer",
+                data.len(),
+                buffer.size()
+            )));
+        }
+        Ok(buffer.write(data)?)
+    }
+
+    fn create_texture(&mut self, desc: &TextureDesc) -> BackendResult<Texture> {
+        let desc = dx12::TextureDesc {
+            width: desc.width,
+            height: desc.height,
+            depth: 1,
+            mip_levels: desc.mip_levels,
+            format: dxgi_format(desc.format),
+            dimension: D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+        };
+        Ok(Texture::new(&self.device, desc)?)
+    }
+
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> BackendResult<PipelineState> {
+        if desc.color_format == TextureFormat::Depth32Float {
+            return Err(BackendError::Unsupported("pipelines need a color target format"));
+        }
+        let options = PipelineOptions {
+            render_target_format: Some(dxgi_format(desc.color_format)),
+            ..Default::default()
+        };
+        create_pipeline_with_layout(
+            &self.device,
+            &self.root_signature,
+            &desc.vertex_shader,
+            &desc.pixel_shader,
+            &desc.vertex_layout,
+            options,
+        )
+        .map_err(|e| BackendError::Backend(format!("pipeline '{}': {}", desc.label, e)))
+    }
+
+    fn begin_frame(&mut self) -> BackendResult<Dx12Frame> {
+        self.allocator.reset()?;
+        let cmd_list = CommandList::new(&self.device, &self.allocator, None)?;
+        let back_buffer = self.swap_chain.current_back_buffer().clone();
+
+        let mut states = ResourceStateTracker::new();
+        states.track(&back_buffer, D3D12_RESOURCE_STATE_PRESENT);
+        states.transition(&back_buffer, D3D12_RESOURCE_STATE_RENDER_TARGET);
+        states.expect_final(&back_buffer, D3D12_RESOURCE_STATE_PRESENT);
+        states.flush(&cmd_list);
+
+        Ok(Dx12Frame {
+            cmd_list,
+            rtv: self.swap_chain.current_rtv(),
+            back_buffer,
+            states,
+            width: self.swap_chain.width(),
+            height: self.swap_chain.height(),
+            srgb_view: self.swap_chain.is_srgb_view(),
+            uploads: Vec::new(),
+            submitted: false,
+        })
+    }
+
+    /// Colors are display (sRGB) values; translucent quads blend over the target
+    fn submit(&mut self, frame: &mut Dx12Frame, batch: &DrawBatch) -> BackendResult<()> {
+        if let Some(color) = batch.clear {
+            frame.clear_rects(frame.encode(color), &[]);
+        }
+        if batch.quads.is_empty() {
+            return Ok(());
+        }
+
+        let vertices: Vec<QuadVertex> = batch.quads.iter().flat_map(|quad| frame.quad_vertices(quad)).collect();
+        let buffer = VertexBuffer::new(
+            &self.device,
+            std::mem::size_of_val(vertices.as_slice()) as u64,
+            std::mem::size_of::<QuadVertex>() as u32,
+        )?;
+        buffer.write(&vertices)?;
+        self.prepare_quad_pipeline()?;
+        let (_, pipeline) = self.quad_pipeline.as_ref().expect("prepared above");
+
+        let list = &frame.cmd_list;
+        list.set_render_targets(&[frame.rtv], None);
+        list.set_viewport(0.0, 0.0, frame.width as f32, frame.height as f32);
+        list.set_scissor_rect(0, 0, frame.width as i32, frame.height as i32);
+        list.set_root_signature(&self.root_signature);
+        list.set_pipeline_state(pipeline);
+        list.set_primitive_topology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+        list.set_vertex_buffers(0, &[*buffer.view()]);
+        list.draw_instanced(vertices.len() as u32, 1, 0, 0);
+        frame.uploads.push(buffer);
+        Ok(())
+    }
+
+    fn end_frame(&mut self, mut frame: Dx12Frame) -> BackendResult<()> {
+        frame.states.transition(&frame.back_buffer, D3D12_RESOURCE_STATE_PRESENT);
+        frame.states.flush(&frame.cmd_list);
+        if cfg!(debug_assertions) {
+            for error in frame.states.validate() {
+                log::error!("Back buffer state validation: {}", error);
+            }
+        }
+
+        frame.cmd_list.close()?;
+        frame.submitted = true;
+        self.command_queue.execute(&[&frame.cmd_list]);
+        let presented = self.swap_chain.present();
+        // The frame's uploads must outlive the GPU work, even if present failed
+        let flushed = self.command_queue.flush();
+        presented?;
+        flushed?;
+        Ok(())
+    }
+
+    fn resize(&mut self, width: u32, height: u32) -> BackendResult<()> {
+        self.flush()?;
+        self.swap_chain.resize(&self.device, width, height)?;
+        Ok(())
+    }
+}
+
+impl From<BackendError> for Dx12Error {
+    fn from(error: BackendError) -> Self {
+        match error {
+            BackendError::Dx12(error) => error,
+            other => Dx12Error::Backend(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Rendering backend seam
+//!
+//! Level B code that wants to stay portable talks to a `RenderBackend`
+//! instead of naming DirectX12 types. Descriptors here are plain data; each
+//! backend maps them to its own API.
+//!
+//! Backends:
+//! - `Dx12Backend`: the GPU backend, built on dx12 (Level A); `Graphics`
+//!   renders through it
+//! - `SoftwareBackend`: CPU rasterizer for headless use; keeps the seam honest
+
+mod dx12;
+mod software;
+
+pub use dx12::{Dx12Backend, Dx12Frame};
+pub use software::{SoftwareBackend, SoftwareBuffer, SoftwareFrame, SoftwarePipeline, SoftwareTexture};
+
+use crate::math::{Color, Rect};
+use thiserror::Error;
+
+/// Backend errors
+#[derive(Error, Debug)]
+pub enum BackendError {
+    #[error("Backend error: {0}")]
+    Backend(String),
+    #[error("Not supported by this backend: {0}")]
+    Unsupported(&'static str),
+    #[error("Invalid handle: {0}")]
+    InvalidHandle(String),
+    #[error(transparent)]
+    Dx12(#[from] crate::dx12::Dx12Error),
+}
+
+pub type BackendResult<T> = Result<T, BackendError>;
+
+/// What a buffer is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferUsage {
+    Vertex,
+    Index,
+    Uniform,
+    Storage,
+    Readback,
+}
+
+/// Portable buffer description
+#[derive(Debug, Clone)]
+pub struct BufferDesc {
+    pub size: u64,
+    pub usage: BufferUsage,
+    /// Element stride in bytes (0 for raw buffers)
+    pub stride: u32,
+}
+
+/// Portable texture formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureFormat {
+    #[default]
+    Rgba8Unorm,
+    Rgba8UnormSrgb,
+    Rgba16Float,
+    Depth32Float,
+}
+
+impl TextureFormat {
+    /// Size of one texel in bytes
+    pub fn bytes_per_pixel(&self) -> u32 {
+        match self {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb | TextureFormat::Depth32Float => 4,
+            TextureFormat::Rgba16Float => 8,
+        }
+    }
+}
+
+/// Portable 2D texture description
+#[derive(Debug, Clone)]
+pub struct TextureDesc {
+    pub width: u32,
+    pub height: u32,
+    pub mip_levels: u32,
+    pub format: TextureFormat,
+}
+
+impl Default for TextureDesc {
+    fn default() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+            mip_levels: 1,
+            format: TextureFormat::default(),
+        }
+    }
+}
+
+/// Format of one vertex attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexFormat {
+    Float2,
+    Float3,
+    Float4,
+}
+
+impl VertexFormat {
+    /// Size of the attribute in bytes
+    pub fn size(&self) -> u32 {
+        match self {
+            VertexFormat::Float2 => 8,
+            VertexFormat::Float3 => 12,
+            VertexFormat::Float4 => 16,
+        }
+    }
+}
+
+/// One vertex attribute of a pipeline's input layout
+#[derive(Debug, Clone)]
+pub struct VertexAttribute {
+    /// Semantic name (HLSL) or attribute name
+    pub semantic: String,
+    pub format: VertexFormat,
+    /// Byte offset in the vertex
+    pub offset: u32,
+}
+
+/// Portable graphics pipeline description
+///
+/// Shader bytecode is backend specific (DXIL/DXBC for DX12); backends
+/// without programmable shaders ignore it.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineDesc {
+    pub label: String,
+    pub vertex_shader: Vec<u8>,
+    pub pixel_shader: Vec<u8>,
+    pub vertex_layout: Vec<VertexAttribute>,
+    pub color_format: TextureFormat,
+}
+
+/// A solid-colored rectangle; translucent colors blend over the target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColoredQuad {
+    pub rect: Rect,
+    pub color: Color,
+}
+
+/// A batch of 2D work submitted to a frame
+#[derive(Debug, Clone, Default)]
+pub struct DrawBatch {
+    /// Clear the whole target before drawing
+    pub clear: Option<Color>,
+    pub quads: Vec<ColoredQuad>,
+}
+
+impl DrawBatch {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clear the target first
+    pub fn with_clear(mut self, color: Color) -> Self {
+        self.clear = Some(color);
+        self
+    }
+
+    /// Add a quad
+    pub fn quad(&mut self, rect: Rect, color: Color) {
+        self.quads.push(ColoredQuad { rect, color });
+    }
+}
+
+/// A rendering backend
+///
+/// Resources are owned by the caller through the associated handle types;
+/// a frame is begun, has batches submitted to it and is ended (presented).
+pub trait RenderBackend {
+    type Buffer;
+    type Texture;
+    type Pipeline;
+    type Frame;
+
+    /// Backend name for logs
+    fn name(&self) -> &'static str;
+
+    /// Current target size in pixels
+    fn size(&self) -> (u32, u32);
+
+    /// Create a buffer
+    fn create_buffer(&mut self, desc: &BufferDesc) -> BackendResult<Self::Buffer>;
+
+    /// Write bytes to the start of a buffer
+    fn write_buffer(&mut self, buffer: &Self::Buffer, data: &[u8]) -> BackendResult<()>;
+
+    /// Create a texture
+    fn create_texture(&mut self, desc: &TextureDesc) -> BackendResult<Self::Texture>;
+
+    /// Create a graphics pipeline
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> BackendResult<Self::Pipeline>;
+
+    /// Begin a frame
+    fn begin_frame(&mut self) -> BackendResult<Self::Frame>;
+
+    /// Record a batch into a frame
+    fn submit(&mut self, frame: &mut Self::Frame, batch: &DrawBatch) -> BackendResult<()>;
+
+    /// Finish and present a frame
+    fn end_frame(&mut self, frame: Self::Frame) -> BackendResult<()>;
+
+    /// Resize the target
+    fn resize(&mut self, width: u32, height: u32) -> BackendResult<()>;
+}
@@ -0,0 +1,207 @@
+//! Software (CPU) backend
+//!
+//! Renders into an in-memory RGBA8 framebuffer. Shaders are not executed;
+//! only the portable 2D batch is rasterized.
+
+use super::{
+    BackendError, BackendResult, BufferDesc, DrawBatch, PipelineDesc, RenderBackend, TextureDesc,
+};
+use crate::math::{Color, Rect};
+
+/// Buffer handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoftwareBuffer(usize);
+
+/// Texture handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoftwareTexture {
+    index: usize,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Pipeline handle (layout only; shaders are ignored)
+#[derive(Debug, Clone)]
+pub struct SoftwarePipeline {
+    pub label: String,
+}
+
+/// A frame being rendered by the software backend
+#[derive(Debug)]
+pub struct SoftwareFrame {
+    index: u64,
+}
+
+impl SoftwareFrame {
+    /// Get the frame number
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+}
+
+/// CPU implementation of `RenderBackend`
+#[derive(Debug, Clone)]
+pub struct SoftwareBackend {
+    width: u32,
+    height: u32,
+    /// Packed 0xAARRGGBB, row-major
+    pixels: Vec<u32>,
+    buffers: Vec<Vec<u8>>,
+    textures: Vec<Vec<u8>>,
+    frame_index: u64,
+    in_frame: bool,
+}
+
+impl SoftwareBackend {
+    /// Create a backend with a `width` x `height` framebuffer
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0xFF00_0000; (width * height) as usize],
+            buffers: Vec::new(),
+            textures: Vec::new(),
+            frame_index: 0,
+            in_frame: false,
+        }
+    }
+
+    /// Get the framebuffer as packed 0xAARRGGBB pixels
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// Read a pixel
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(unpack(self.pixels[(y * self.width + x) as usize]))
+    }
+
+    /// Get the contents of a buffer
+    pub fn buffer_data(&self, buffer: &SoftwareBuffer) -> &[u8] {
+        &self.buffers[buffer.0]
+    }
+
+    /// Get the contents of a texture
+    pub fn texture_data(&self, texture: &SoftwareTexture) -> &[u8] {
+        &self.textures[texture.index]
+    }
+
+    fn fill(&mut self, rect: Rect, color: Color) {
+        let x0 = rect.x.round().max(0.0) as u32;
+        let y0 = rect.y.round().max(0.0) as u32;
+        let x1 = ((rect.x + rect.width).round().max(0.0) as u32).min(self.width);
+        let y1 = ((rect.y + rect.height).round().max(0.0) as u32).min(self.height);
+
+        let opaque = color.a >= 1.0;
+        for y in y0..y1 {
+            let row = (y * self.width) as usize;
+            for x in x0..x1 {
+                let pixel = &mut self.pixels[row + x as usize];
+                *pixel = if opaque { pack(color) } else { pack(color.blend_over(unpack(*pixel), false)) };
+            }
+        }
+    }
+}
+
+fn pack(color: Color) -> u32 {
+    let c = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u32;
+    (c(color.a) << 24) | (c(color.r) << 16) | (c(color.g) << 8) | c(color.b)
+}
+
+fn unpack(pixel: u32) -> Color {
+    let c = |shift: u32| ((pixel >> shift) & 0xFF) as f32 / 255.0;
+    Color::rgba(c(16), c(8), c(0), c(24))
+}
+
+impl RenderBackend for SoftwareBackend {
+    type Buffer = SoftwareBuffer;
+    type Texture = SoftwareTexture;
+    type Pipeline = SoftwarePipeline;
+    type Frame = SoftwareFrame;
+
+    fn name(&self) -> &'static str {
+        "Software"
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn create_buffer(&mut self, desc: &BufferDesc) -> BackendResult<SoftwareBuffer> {
+        self.buffers.push(vec![0; desc.size as usize]);
+        Ok(SoftwareBuffer(self.buffers.len() - 1))
+    }
+
+    fn write_buffer(&mut self, buffer: &SoftwareBuffer, data: &[u8]) -> BackendResult<()> {
+        let storage = self
+            .buffers
+            .get_mut(buffer.0)
+            .ok_or_else(|| BackendError::InvalidHandle(format!("{:?}", buffer)))?;
+        if data.len() > storage.len() {
+            return Err(BackendError::Backend(format!(
+                "write of {} bytes to a {} byte buffer",
+                data.len(),
+                storage.len()
+            )));
+        }
+        storage[..data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn create_texture(&mut self, desc: &TextureDesc) -> BackendResult<SoftwareTexture> {
+        let size = desc.width as usize * desc.height as usize * desc.format.bytes_per_pixel() as usize;
+        self.textures.push(vec![0; size]);
+        Ok(SoftwareTexture {
+            index: self.textures.len() - 1,
+            width: desc.width,
+            height: desc.height,
+        })
+    }
+
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> BackendResult<SoftwarePipeline> {
+        Ok(SoftwarePipeline { label: desc.label.clone() })
+    }
+
+    fn begin_frame(&mut self) -> BackendResult<SoftwareFrame> {
+        if self.in_frame {
+            return Err(BackendError::Backend("a frame is already in progress".to_string()));
+        }
+        self.in_frame = true;
+        self.frame_index += 1;
+        Ok(SoftwareFrame { index: self.frame_index })
+    }
+
+    fn submit(&mut self, frame: &mut SoftwareFrame, batch: &DrawBatch) -> BackendResult<()> {
+        if frame.index != self.frame_index || !self.in_frame {
+            return Err(BackendError::InvalidHandle(format!("stale frame {}", frame.index)));
+        }
+        if let Some(color) = batch.clear {
+            self.pixels.fill(pack(color));
+        }
+        for quad in &batch.quads {
+            self.fill(quad.rect, quad.color);
+        }
+        Ok(())
+    }
+
+    fn end_frame(&mut self, frame: SoftwareFrame) -> BackendResult<()> {
+        if frame.index != self.frame_index || !self.in_frame {
+            return Err(BackendError::InvalidHandle(format!("stale frame {}", frame.index)));
+        }
+        self.in_frame = false;
+        Ok(())
+    }
+
+    fn resize(&mut self, width: u32, height: u32) -> BackendResult<()> {
+        if self.in_frame {
+            return Err(BackendError::Backend("cannot resize during a frame".to_string()));
+        }
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0xFF00_0000; (width * height) as usize];
+        Ok(())
+    }
+}
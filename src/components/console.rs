@@ -0,0 +1,274 @@
+//! Console overlay - type commands into a `CommandRegistry`
+
+use crate::console::{CommandRegistry, LineKind};
+use crate::core::{AttributeValue, Element, Props, RenderContext, State};
+use crate::events::{Event, KeyCode};
+use crate::logging::{self, LogFilter};
+use crate::math::{Color, Rect};
+
+use super::level_color;
+
+/// Console props
+#[derive(Debug, Clone)]
+pub struct ConsoleProps {
+    pub bounds: Rect,
+    pub background: Color,
+    pub font_size: f32,
+    /// Key that shows and hides the console
+    pub toggle_key: KeyCode,
+    /// Recent log records shown above the output (0 = none)
+    pub log_lines: usize,
+}
+
+impl Default for ConsoleProps {
+    fn default() -> Self {
+        Self {
+            bounds: Rect::new(0.0, 0.0, 640.0, 280.0),
+            background: Color::rgba(0.04, 0.04, 0.06, 0.92),
+            font_size: 13.0,
+            toggle_key: KeyCode::Backquote,
+            log_lines: 4,
+        }
+    }
+}
+
+impl Props for ConsoleProps {
+    fn props_eq(&self, other: &Self) -> bool {
+        self.bounds == other.bounds
+            && self.background == other.background
+            && self.font_size == other.font_size
+            && self.toggle_key == other.toggle_key
+            && self.log_lines == other.log_lines
+    }
+}
+
+/// Console state
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleState {
+    pub visible: bool,
+    /// Line being typed
+    pub input: String,
+}
+
+impl State for ConsoleState {}
+
+fn line_color(kind: LineKind) -> Color {
+    match kind {
+        LineKind::Input => Color::from_hex(0x8AB4F8),
+        LineKind::Output => Color::from_hex(0xE0E0E0),
+        LineKind::Error => Color::from_hex(0xFF5555),
+    }
+}
+
+/// Quake-style console
+///
+/// Pass window events to `handle_event`: the toggle key shows it, typed
+/// characters edit the input line, Enter runs it, Tab completes command
+/// names and Up/Down walk the history.
+pub struct Console {
+    props: ConsoleProps,
+    state: ConsoleState,
+    registry: CommandRegistry,
+    /// The toggle key was just pressed; drop the character it types
+    skip_toggle_char: bool,
+}
+
+impl Console {
+    pub fn new(props: ConsoleProps) -> Self {
+        Self::with_registry(props, CommandRegistry::new())
+    }
+
+    /// Create a console that runs commands in `registry`
+    pub fn with_registry(props: ConsoleProps, registry: CommandRegistry) -> Self {
+        Self {
+            props,
+            state: ConsoleState::default(),
+            registry,
+            skip_toggle_char: false,
+        }
+    }
+
+    pub fn registry(&self) -> &CommandRegistry {
+        &self.registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut CommandRegistry {
+        &mut self.registry
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.state.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.state.visible = !self.state.visible;
+    }
+
+    pub fn input(&self) -> &str {
+        &self.state.input
+    }
+
+    /// Run the current input line
+    pub fn submit(&mut self) {
+        let line = std::mem::take(&mut self.state.input);
+        // Errors are shown in the output
+        let _ = self.registry.execute(&line);
+    }
+
+    /// Handle keyboard input; returns true if consumed
+    ///
+    /// While visible every key and character is consumed so the game
+    /// doesn't react to typing.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if matches!(event, Event::KeyDown(e) if e.key != self.props.toggle_key) {
+            self.skip_toggle_char = false;
+        }
+        match event {
+            Event::KeyDown(e) if e.key == self.props.toggle_key => {
+                self.toggle();
+                // The key may also arrive as a character (not for e.g. F1)
+                self.skip_toggle_char = true;
+                true
+            }
+            Event::CharInput(_) if std::mem::take(&mut self.skip_toggle_char) => true,
+            _ if !self.state.visible => false,
+            Event::KeyDown(e) => {
+                match e.key {
+                    KeyCode::Enter => self.submit(),
+                    KeyCode::Backspace => {
+                        self.state.input.pop();
+                    }
+                    KeyCode::Escape => self.state.input.clear(),
+                    KeyCode::Tab => {
+                        if let Some(completed) = self.registry.complete_line(&self.state.input) {
+                            self.state.input = completed;
+                        } else if !self.state.input.contains(' ') {
+                            let matches = self.registry.complete(&self.state.input).join("  ");
+                            if !matches.is_empty() {
+                                self.registry.push_line(LineKind::Output, matches);
+                            }
+                        }
+                    }
+                    KeyCode::Up => {
+                        if let Some(line) = self.registry.history_prev() {
+                            self.state.input = line.to_string();
+                        }
+                    }
+                    KeyCode::Down => {
+                        self.state.input = self.registry.history_next().unwrap_or_default().to_string();
+                    }
+                    _ => {}
+                }
+                true
+            }
+            Event::KeyUp(_) => true,
+            Event::CharInput(c) => {
+                if !c.is_control() {
+                    self.state.input.push(*c);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn line_height(&self) -> f32 {
+        self.props.font_size * 1.25
+    }
+
+    fn text_line(&self, text: String, row: usize, color: Color) -> Element {
+        let bounds = self.props.bounds;
+        Element::text(text, bounds.x + 6.0, bounds.y + 4.0 + row as f32 * self.line_height())
+            .fill(color)
+            .attr("font_size", AttributeValue::Number(self.props.font_size as f64))
+    }
+
+    pub fn render(&self, _ctx: &mut RenderContext) -> Element {
+        if !self.state.visible {
+            return Element::group(Vec::new());
+        }
+
+        let bounds = self.props.bounds;
+        let rows = ((bounds.height - 8.0) / self.line_height()).max(0.0) as usize;
+        // The input line takes the last row
+        let mut available = rows.saturating_sub(1);
+        let mut lines = Vec::new();
+
+        if self.props.log_lines > 0 {
+            let records = logging::records(&LogFilter::default());
+            let count = self.props.log_lines.min(records.len()).min(available);
+            for record in &records[records.len() - count..] {
+                let text = format!("[{:<5} {}] {}", record.level, record.target, record.message);
+                lines.push(self.text_line(text, lines.len(), level_color(record.level)));
+            }
+            available -= count;
+        }
+
+        let output: Vec<_> = self.registry.output().collect();
+        for line in &output[output.len().saturating_sub(available)..] {
+            lines.push(self.text_line(line.text.clone(), lines.len(), line_color(line.kind)));
+        }
+
+        lines.push(self.text_line(format!("> {}_", self.state.input), rows.saturating_sub(1), Color::WHITE));
+
+        Element::rect(bounds)
+            .fill(self.props.background)
+            .with_key("console")
+            .children(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{KeyEvent, Modifiers};
+
+    fn key(key: KeyCode) -> Event {
+        Event::KeyDown(KeyEvent { key, pressed: true, repeat: false, modifiers: Modifiers::default() })
+    }
+
+    fn type_text(console: &mut Console, text: &str) {
+        for c in text.chars() {
+            console.handle_event(&Event::CharInput(c));
+        }
+    }
+
+    #[test]
+    fn toggle_character_is_dropped_for_any_toggle_key() {
+        let mut console = Console::new(ConsoleProps { toggle_key: KeyCode::F1, ..Default::default() });
+        assert!(console.handle_event(&key(KeyCode::F1)));
+        assert!(console.is_visible());
+        // F1 types nothing, so the first character is kept
+        console.handle_event(&key(KeyCode::A));
+        type_text(&mut console, "a`");
+        assert_eq!(console.input(), "a`");
+
+        let mut console = Console::new(ConsoleProps::default());
+        console.handle_event(&key(KeyCode::Backquote));
+        type_text(&mut console, "``");
+        assert_eq!(console.input(), "`");
+    }
+
+    #[test]
+    fn hidden_console_passes_input_through() {
+        let mut console = Console::new(ConsoleProps { toggle_key: KeyCode::F1, ..Default::default() });
+        console.handle_event(&key(KeyCode::F1));
+        console.handle_event(&key(KeyCode::F1));
+        assert!(!console.is_visible());
+        assert!(!console.handle_event(&key(KeyCode::A)));
+        assert!(!console.handle_event(&Event::CharInput('a')));
+    }
+
+    #[test]
+    fn enter_runs_the_typed_line() {
+        let mut console = Console::new(ConsoleProps::default());
+        console.registry_mut().register("ping", |_| Ok("pong".to_string()));
+        console.toggle();
+        type_text(&mut console, "pi");
+        console.handle_event(&key(KeyCode::Tab));
+        assert_eq!(console.input(), "ping ");
+        console.handle_event(&key(KeyCode::Enter));
+        assert_eq!(console.input(), "");
+        assert_eq!(console.registry().output().last().unwrap().text, "pong");
+    }
+}
@@ -0,0 +1,147 @@
+//! Log console component - shows captured log records in-app
+
+use crate::core::{AttributeValue, Element, Props, RenderContext, State};
+use crate::events::{Event, KeyCode};
+use crate::logging::{self, LogFilter, LogRecord};
+use crate::math::{Color, Rect};
+use log::Level;
+
+/// Log console props
+#[derive(Debug, Clone)]
+pub struct LogConsoleProps {
+    pub bounds: Rect,
+    pub background: Color,
+    pub font_size: f32,
+    /// Key that shows and hides the console
+    pub toggle_key: KeyCode,
+}
+
+impl Default for LogConsoleProps {
+    fn default() -> Self {
+        Self {
+            bounds: Rect::new(0.0, 0.0, 640.0, 240.0),
+            background: Color::rgba(0.05, 0.05, 0.08, 0.9),
+            font_size: 13.0,
+            toggle_key: KeyCode::F12,
+        }
+    }
+}
+
+impl Props for LogConsoleProps {
+    fn props_eq(&self, other: &Self) -> bool {
+        self.bounds == other.bounds
+            && self.background == other.background
+            && self.font_size == other.font_size
+            && self.toggle_key == other.toggle_key
+    }
+}
+
+/// Log console state
+#[derive(Debug, Clone, Default)]
+pub struct LogConsoleState {
+    pub visible: bool,
+    pub filter: LogFilter,
+    /// Lines scrolled up from the newest record (0 = follow new records)
+    pub scroll: usize,
+}
+
+impl State for LogConsoleState {}
+
+/// Color used for each level
+pub fn level_color(level: Level) -> Color {
+    match level {
+        Level::Error => Color::from_hex(0xFF5555),
+        Level::Warn => Color::from_hex(0xFFC857),
+        Level::Info => Color::from_hex(0xE0E0E0),
+        Level::Debug => Color::from_hex(0x8AB4F8),
+        Level::Trace => Color::from_hex(0x9E9E9E),
+    }
+}
+
+fn format_record(record: &LogRecord) -> String {
+    format!("[{:<5} {}] {}", record.level, record.target, record.message)
+}
+
+/// Console listing the records captured by `logging::init`
+///
+/// Shows the newest records that fit and follows new ones unless scrolled
+/// up. Toggle it with `toggle_key` by passing events to `handle_event`.
+pub struct LogConsole {
+    props: LogConsoleProps,
+    state: LogConsoleState,
+}
+
+impl LogConsole {
+    pub fn new(props: LogConsoleProps) -> Self {
+        Self {
+            props,
+            state: LogConsoleState::default(),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.state.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.state.visible = !self.state.visible;
+    }
+
+    /// Set which records are shown
+    pub fn set_filter(&mut self, filter: LogFilter) {
+        self.state.filter = filter;
+        self.state.scroll = 0;
+    }
+
+    pub fn filter(&self) -> &LogFilter {
+        &self.state.filter
+    }
+
+    /// Handle the toggle key and scrolling; returns true if consumed
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::KeyDown(e) if e.key == self.props.toggle_key => {
+                self.toggle();
+                true
+            }
+            Event::MouseScroll(e) if self.state.visible && self.props.bounds.contains(e.position) => {
+                let lines = e.scroll_delta.round() as isize;
+                self.state.scroll = self.state.scroll.saturating_add_signed(lines);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The filtered records as plain text, one per line (for the clipboard)
+    pub fn copy_text(&self) -> String {
+        logging::records(&self.state.filter).iter().map(format_record).collect::<Vec<_>>().join("\n")
+    }
+
+    fn line_height(&self) -> f32 {
+        self.props.font_size * 1.25
+    }
+
+    pub fn render(&self, _ctx: &mut RenderContext) -> Element {
+        if !self.state.visible {
+            return Element::group(Vec::new());
+        }
+
+        let bounds = self.props.bounds;
+        let rows = ((bounds.height - 8.0) / self.line_height()).max(0.0) as usize;
+        let records = logging::records(&self.state.filter);
+        let end = records.len().saturating_sub(self.state.scroll.min(records.len().saturating_sub(rows)));
+        let start = end.saturating_sub(rows);
+
+        let lines = records[start..end].iter().enumerate().map(|(i, record)| {
+            Element::text(format_record(record), bounds.x + 6.0, bounds.y + 4.0 + i as f32 * self.line_height())
+                .fill(level_color(record.level))
+                .attr("font_size", AttributeValue::Number(self.props.font_size as f64))
+        });
+
+        Element::rect(bounds)
+            .fill(self.props.background)
+            .with_key("log-console")
+            .children(lines)
+    }
+}
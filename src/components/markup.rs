@@ -0,0 +1,197 @@
+//! Minimal BBCode-style markup for rich text
+
+use super::TextSpan;
+use crate::math::{Color, Rect};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    Bold,
+    Italic,
+    Underline,
+    Color,
+    Size,
+}
+
+impl Tag {
+    fn from_name(name: &str) -> Option<Tag> {
+        match name {
+            "b" => Some(Tag::Bold),
+            "i" => Some(Tag::Italic),
+            "u" => Some(Tag::Underline),
+            "color" => Some(Tag::Color),
+            "size" => Some(Tag::Size),
+            _ => None,
+        }
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    let expanded;
+    let hex = if hex.len() == 3 {
+        // #rgb shorthand
+        expanded = hex.chars().flat_map(|c| [c, c]).collect::<String>();
+        expanded.as_str()
+    } else {
+        hex
+    };
+    let bits = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some(Color::from_hex(bits)),
+        8 => Some(Color::rgba(
+            ((bits >> 24) & 0xFF) as f32 / 255.0,
+            ((bits >> 16) & 0xFF) as f32 / 255.0,
+            ((bits >> 8) & 0xFF) as f32 / 255.0,
+            (bits & 0xFF) as f32 / 255.0,
+        )),
+        _ => None,
+    }
+}
+
+/// Parse `atlas:x,y,w,h`
+fn parse_icon(value: &str) -> Option<TextSpan> {
+    let (atlas, region) = value.rsplit_once(':')?;
+    let numbers: Vec<f32> = region.split(',').map(|n| n.trim().parse().ok()).collect::<Option<_>>()?;
+    match numbers.as_slice() {
+        [x, y, w, h] if !atlas.is_empty() => Some(TextSpan::icon(atlas, Rect::new(*x, *y, *w, *h))),
+        _ => None,
+    }
+}
+
+/// Apply an opening tag to a style, or None if the tag is malformed
+fn open(tag: Tag, value: Option<&str>, style: &TextSpan) -> Option<TextSpan> {
+    let mut style = style.clone();
+    match (tag, value) {
+        (Tag::Bold, None) => style.bold = true,
+        (Tag::Italic, None) => style.italic = true,
+        (Tag::Underline, None) => style.underline = true,
+        (Tag::Color, Some(value)) => style.color = Some(parse_color(value)?),
+        (Tag::Size, Some(value)) => style.size_scale = value.parse().ok().filter(|s: &f32| *s > 0.0)?,
+        _ => return None,
+    }
+    Some(style)
+}
+
+struct Parser {
+    spans: Vec<TextSpan>,
+    /// Open tags with the style in effect inside them
+    stack: Vec<(Tag, TextSpan)>,
+    base: TextSpan,
+    text: String,
+}
+
+impl Parser {
+    fn style(&self) -> &TextSpan {
+        self.stack.last().map(|(_, style)| style).unwrap_or(&self.base)
+    }
+
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            let span = TextSpan { text: std::mem::take(&mut self.text), ..self.style().clone() };
+            self.spans.push(span);
+        }
+    }
+
+    /// Handle the contents of a `[...]`; false if it is not a valid tag
+    fn tag(&mut self, body: &str) -> bool {
+        if let Some(name) = body.strip_prefix('/') {
+            let Some(tag) = Tag::from_name(name) else { return false };
+            // Closing an outer tag also closes anything opened inside it
+            let Some(index) = self.stack.iter().rposition(|(open, _)| *open == tag) else { return false };
+            self.flush();
+            self.stack.truncate(index);
+            return true;
+        }
+
+        let (name, value) = match body.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (body, None),
+        };
+
+        if name == "icon" {
+            let Some(icon) = value.and_then(parse_icon) else { return false };
+            self.flush();
+            let style = self.style();
+            self.spans.push(TextSpan { size_scale: style.size_scale, color: style.color, ..icon });
+            return true;
+        }
+
+        let Some(tag) = Tag::from_name(name) else { return false };
+        let Some(style) = open(tag, value, self.style()) else { return false };
+        self.flush();
+        self.stack.push((tag, style));
+        true
+    }
+}
+
+/// Parse markup into spans
+///
+/// Unclosed tags run to the end of the text; unknown or malformed tags are
+/// kept as literal text.
+pub fn parse(markup: &str) -> Vec<TextSpan> {
+    let mut parser = Parser {
+        spans: Vec::new(),
+        stack: Vec::new(),
+        base: TextSpan::new(""),
+        text: String::new(),
+    };
+
+    let mut rest = markup;
+    while let Some(start) = rest.find('[') {
+        parser.text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find(']') {
+            Some(end) if parser.tag(&after[..end]) => rest = &after[end + 1..],
+            _ => {
+                parser.text.push('[');
+                rest = after;
+            }
+        }
+    }
+    parser.text.push_str(rest);
+    parser.flush();
+    parser.spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_tags_and_closing_an_outer_tag() {
+        let spans = parse("[b]a[i]b[/b]c");
+        assert_eq!(spans, [TextSpan::new("a").bold(), TextSpan::new("b").bold().italic(), TextSpan::new("c")]);
+
+        let spans = parse("[color=#f00][size=2]big[/size]red[/color]");
+        let red = parse_color("#ff0000").unwrap();
+        assert_eq!(spans, [TextSpan::new("big").with_color(red).with_scale(2.0), TextSpan::new("red").with_color(red)]);
+    }
+
+    #[test]
+    fn mismatched_close_stays_literal() {
+        assert_eq!(parse("[b]x[/i]"), [TextSpan::new("x[/i]").bold()]);
+        assert_eq!(parse("a[/b]"), [TextSpan::new("a[/b]")]);
+    }
+
+    #[test]
+    fn malformed_tags_stay_literal() {
+        assert_eq!(parse("[color=#zzz]red"), [TextSpan::new("[color=#zzz]red")]);
+        assert_eq!(parse("[color=#12345]x"), [TextSpan::new("[color=#12345]x")]);
+        assert_eq!(parse("[size=0]x"), [TextSpan::new("[size=0]x")]);
+        assert_eq!(parse("[b=1]x"), [TextSpan::new("[b=1]x")]);
+        assert_eq!(parse("[wave]x [b"), [TextSpan::new("[wave]x [b")]);
+    }
+
+    #[test]
+    fn icons_parse_with_their_region() {
+        let spans = parse("[icon=ui/buttons:1,2,16,16] A");
+        assert_eq!(spans, [TextSpan::icon("ui/buttons", Rect::new(1.0, 2.0, 16.0, 16.0)), TextSpan::new(" A")]);
+
+        // Icons take the size and color of the surrounding text
+        let spans = parse("[size=2][icon=ui:0,0,8,8][/size]");
+        assert_eq!(spans, [TextSpan::icon("ui", Rect::new(0.0, 0.0, 8.0, 8.0)).with_scale(2.0)]);
+
+        assert_eq!(parse("[icon=ui:1,2]"), [TextSpan::new("[icon=ui:1,2]")]);
+        assert_eq!(parse("[icon=:1,2,3,4]"), [TextSpan::new("[icon=:1,2,3,4]")]);
+    }
+}
@@ -7,12 +7,18 @@ mod container;
 mod text_component;
 mod image_component;
 mod canvas;
+mod markup;
+mod log_console;
+mod console;
 
 pub use button::{Button, ButtonProps, ButtonState};
 pub use container::{Container, ContainerProps, Flex, FlexDirection};
-pub use text_component::{Text, TextProps};
+pub use text_component::{Text, TextProps, TextSpan, InlineIcon, SpanLayout, layout_spans};
 pub use image_component::{Image, ImageProps};
 pub use canvas::{Canvas, CanvasProps};
+pub use log_console::{LogConsole, LogConsoleProps, LogConsoleState, level_color};
+pub use console::{Console, ConsoleProps, ConsoleState};
+pub use crate::core::ErrorBoundary;
 
 use crate::core::{Element, RenderContext};
 use crate::math::{Color, Rect};
@@ -1,7 +1,140 @@
 //! Text component
 
-use crate::core::{Element, RenderContext, Props};
-use crate::math::Color;
+use super::markup;
+use crate::core::{AttributeValue, Element, RenderContext, Props};
+use crate::math::{Color, Rect};
+
+/// Ascent as a fraction of the font size (approximate metrics until a font
+/// rasterizer provides real ones)
+const ASCENT: f32 = 0.8;
+/// Descent as a fraction of the font size
+const DESCENT: f32 = 0.2;
+/// Average glyph advance as a fraction of the font size
+const ADVANCE: f32 = 0.55;
+/// Extra advance for bold faces
+const BOLD_ADVANCE: f32 = 0.05;
+
+/// An icon drawn inline with text, taken from an atlas
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineIcon {
+    /// Atlas texture path
+    pub atlas: String,
+    /// Source region in atlas pixels
+    pub region: Rect,
+}
+
+/// A run of text with one style, or an inline icon
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    /// Color, or the paragraph color when None
+    pub color: Option<Color>,
+    /// Size relative to the paragraph font size
+    pub size_scale: f32,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    /// Draw an icon instead of text
+    pub icon: Option<InlineIcon>,
+}
+
+impl TextSpan {
+    /// Create a plain text span
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            color: None,
+            size_scale: 1.0,
+            bold: false,
+            italic: false,
+            underline: false,
+            icon: None,
+        }
+    }
+
+    /// Create an inline icon span (square, one line high)
+    pub fn icon(atlas: impl Into<String>, region: Rect) -> Self {
+        Self {
+            icon: Some(InlineIcon { atlas: atlas.into(), region }),
+            ..Self::new("")
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn with_scale(mut self, size_scale: f32) -> Self {
+        self.size_scale = size_scale;
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Font face name for this span's bold/italic combination
+    pub fn face(&self) -> &'static str {
+        match (self.bold, self.italic) {
+            (false, false) => "regular",
+            (true, false) => "bold",
+            (false, true) => "italic",
+            (true, true) => "bold_italic",
+        }
+    }
+}
+
+/// Placement of one span in a laid-out paragraph
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpanLayout {
+    /// Box of the span; text is drawn from its top-left corner
+    pub bounds: Rect,
+    /// Shared baseline of the paragraph
+    pub baseline: f32,
+    pub font_size: f32,
+}
+
+/// Lay out spans on one line with a shared baseline
+///
+/// The baseline sits below the tallest ascent, so mixed sizes line up.
+pub fn layout_spans(spans: &[TextSpan], font_size: f32, x: f32, y: f32) -> Vec<SpanLayout> {
+    let ascent = |span: &TextSpan| span.size_scale * font_size * if span.icon.is_some() { 1.0 } else { ASCENT };
+    let baseline = y + spans.iter().map(ascent).fold(0.0, f32::max);
+
+    let mut cursor = x;
+    spans
+        .iter()
+        .map(|span| {
+            let size = span.size_scale * font_size;
+            let (width, height) = if span.icon.is_some() {
+                (size, size)
+            } else {
+                let advance = size * (ADVANCE + if span.bold { BOLD_ADVANCE } else { 0.0 });
+                (span.text.chars().count() as f32 * advance, size * (ASCENT + DESCENT))
+            };
+            let top = baseline - ascent(span);
+            let layout = SpanLayout {
+                bounds: Rect::new(cursor, top, width, height),
+                baseline,
+                font_size: size,
+            };
+            cursor += width;
+            layout
+        })
+        .collect()
+}
 
 /// Text props
 #[derive(Debug, Clone)]
@@ -11,6 +144,8 @@ pub struct TextProps {
     pub y: f32,
     pub color: Color,
     pub font_size: f32,
+    /// Styled runs; when empty `content` is drawn as a single run
+    pub spans: Vec<TextSpan>,
 }
 
 impl Default for TextProps {
@@ -21,6 +156,7 @@ impl Default for TextProps {
             y: 0.0,
             color: Color::WHITE,
             font_size: 16.0,
+            spans: Vec::new(),
         }
     }
 }
@@ -31,6 +167,7 @@ impl Props for TextProps {
             && self.x == other.x
             && self.y == other.y
             && self.font_size == other.font_size
+            && self.spans == other.spans
     }
 }
 
@@ -53,7 +190,91 @@ impl Text {
         })
     }
 
+    /// Create rich text from markup
+    ///
+    /// Tags: `[b]`, `[i]`, `[u]`, `[color=#rrggbb]`, `[size=1.5]` (each closed
+    /// with `[/tag]`) and `[icon=atlas:x,y,w,h]`. Malformed tags are kept as text.
+    pub fn from_markup(markup: &str, x: f32, y: f32) -> Self {
+        let spans = markup::parse(markup);
+        Self::new(TextProps {
+            content: spans.iter().map(|s| s.text.as_str()).collect(),
+            x,
+            y,
+            spans,
+            ..Default::default()
+        })
+    }
+
     pub fn render(&self, _ctx: &mut RenderContext) -> Element {
-        Element::text(&self.props.content, self.props.x, self.props.y)
+        if self.props.spans.is_empty() {
+            return Element::text(&self.props.content, self.props.x, self.props.y);
+        }
+
+        let layout = layout_spans(&self.props.spans, self.props.font_size, self.props.x, self.props.y);
+        let mut children = Vec::with_capacity(self.props.spans.len());
+        for (span, placed) in self.props.spans.iter().zip(&layout) {
+            let color = span.color.unwrap_or(self.props.color);
+            if let Some(icon) = &span.icon {
+                children.push(
+                    Element::image(icon.atlas.clone(), placed.bounds)
+                        .attr("atlas_region", AttributeValue::Rect(icon.region)),
+                );
+                continue;
+            }
+
+            children.push(
+                Element::text(span.text.clone(), placed.bounds.x, placed.bounds.y)
+                    .fill(color)
+                    .attr("font_size", AttributeValue::Number(placed.font_size as f64))
+                    .attr("font_face", AttributeValue::String(span.face().to_string())),
+            );
+            if span.underline {
+                let thickness = (placed.font_size / 14.0).max(1.0);
+                let underline = Rect::new(placed.bounds.x, placed.baseline + thickness, placed.bounds.width, thickness);
+                children.push(Element::rect(underline).fill(color));
+            }
+        }
+        Element::group(children)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_markup_keeps_the_plain_text() {
+        let text = Text::from_markup("[b]Hello[/b], [icon=ui:0,0,8,8][color=#0f0]world[/color]", 4.0, 8.0);
+        assert_eq!(text.props.content, "Hello, world");
+        assert_eq!(text.props.spans.len(), 4);
+        assert!(text.props.spans[2].icon.is_some());
+        assert_eq!((text.props.x, text.props.y), (4.0, 8.0));
+    }
+
+    #[test]
+    fn mixed_sizes_share_one_baseline() {
+        let spans = [
+            TextSpan::new("small").with_scale(0.5),
+            TextSpan::new("big").with_scale(2.0).bold(),
+            TextSpan::icon("ui", Rect::new(0.0, 0.0, 8.0, 8.0)),
+            TextSpan::new("normal"),
+        ];
+        let layout = layout_spans(&spans, 20.0, 10.0, 100.0);
+
+        // The tallest ascent (the 2x span's) sets the baseline
+        let baseline = 100.0 + 2.0 * 20.0 * ASCENT;
+        for (span, placed) in spans.iter().zip(&layout) {
+            assert_eq!(placed.baseline, baseline);
+            let ascent = if span.icon.is_some() { placed.font_size } else { placed.font_size * ASCENT };
+            assert!((placed.bounds.y + ascent - baseline).abs() < 1e-4, "{:?}", span.text);
+        }
+        assert!((layout[1].bounds.y - 100.0).abs() < 1e-4);
+        assert_eq!(layout[2].bounds.height, 20.0);
+
+        // Spans follow each other without gaps
+        assert_eq!(layout[0].bounds.x, 10.0);
+        for pair in layout.windows(2) {
+            assert_eq!(pair[1].bounds.x, pair[0].bounds.x + pair[0].bounds.width);
+        }
     }
 }
@@ -0,0 +1,646 @@
+//! Command console - tweak framework state at runtime
+//!
+//! Commands are closures registered by name:
+//!
+//! ```ignore
+//! let mut console = CommandRegistry::new();
+//! console.register("set_fog", |args| {
+//!     let density = args.float(0)?;
+//!     Ok(format!("fog = {}", density))
+//! }).usage("set_fog <density>");
+//! console.execute("set_fog 0.2")?;
+//! ```
+//!
+//! `register_builtins` adds commands for the `RuntimeSettings` toggles;
+//! the app applies the settings atom to `Graphics` and its `IsrAnalyzer`
+//! each frame.
+//! The `components::Console` overlay feeds typed lines to a registry.
+
+use crate::core::{Atom, State};
+use crate::dx12::Dx12Result;
+use crate::events::GraphicsEvent;
+use crate::graphics::Graphics;
+use crate::isr::{IsrAnalyzer, IsrConfig};
+use std::collections::{BTreeMap, VecDeque};
+use thiserror::Error;
+
+/// Console errors
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConsoleError {
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+    #[error("{message} (usage: {usage})")]
+    Usage { usage: String, message: String },
+    #[error("Parse error: {0}")]
+    Parse(String),
+    #[error("{0}")]
+    Failed(String),
+}
+
+pub type ConsoleResult<T> = Result<T, ConsoleError>;
+
+/// Split a line into words; double quotes group words, `\"` is a quote
+pub fn tokenize(line: &str) -> ConsoleResult<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quoted => match chars.next() {
+                Some(next) => current.push(next),
+                None => return Err(ConsoleError::Parse("trailing backslash".to_string())),
+            },
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quoted {
+        return Err(ConsoleError::Parse("unterminated quote".to_string()));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+/// Parse a bool the way people type them
+pub fn parse_bool(word: &str) -> Option<bool> {
+    match word.to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// A typed argument
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl Value {
+    /// Infer the type of a word (int, then float, then bool, else string)
+    pub fn parse(word: &str) -> Self {
+        if let Ok(i) = word.parse() {
+            Value::Int(i)
+        } else if let Ok(f) = word.parse() {
+            Value::Float(f)
+        } else if let Some(b) = parse_bool(word) {
+            Value::Bool(b)
+        } else {
+            Value::Str(word.to_string())
+        }
+    }
+}
+
+/// Arguments passed to a command (the command name is not included)
+#[derive(Debug, Clone)]
+pub struct Args {
+    words: Vec<String>,
+    usage: String,
+}
+
+impl Args {
+    pub fn new(words: Vec<String>, usage: impl Into<String>) -> Self {
+        Self { words, usage: usage.into() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Raw word at `index`
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.words.get(index).map(String::as_str)
+    }
+
+    /// A usage error for this command
+    pub fn usage_error(&self, message: impl Into<String>) -> ConsoleError {
+        ConsoleError::Usage { usage: self.usage.clone(), message: message.into() }
+    }
+
+    /// Fail unless the argument count is within `min..=max`
+    pub fn expect_len(&self, min: usize, max: usize) -> ConsoleResult<()> {
+        if self.len() < min {
+            Err(self.usage_error(format!("expected at least {} argument(s)", min)))
+        } else if self.len() > max {
+            Err(self.usage_error(format!("expected at most {} argument(s)", max)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// String argument at `index`
+    pub fn string(&self, index: usize) -> ConsoleResult<&str> {
+        self.get(index).ok_or_else(|| self.usage_error(format!("missing argument {}", index + 1)))
+    }
+
+    /// Integer argument at `index`
+    pub fn int(&self, index: usize) -> ConsoleResult<i64> {
+        let word = self.string(index)?;
+        word.parse().map_err(|_| self.usage_error(format!("'{}' is not an integer", word)))
+    }
+
+    /// Float argument at `index` (integers are accepted)
+    pub fn float(&self, index: usize) -> ConsoleResult<f64> {
+        let word = self.string(index)?;
+        word.parse().map_err(|_| self.usage_error(format!("'{}' is not a number", word)))
+    }
+
+    /// Bool argument at `index` (true/false, on/off, yes/no, 1/0)
+    pub fn bool(&self, index: usize) -> ConsoleResult<bool> {
+        let word = self.string(index)?;
+        parse_bool(word).ok_or_else(|| self.usage_error(format!("'{}' is not a bool", word)))
+    }
+
+    /// Argument at `index` with its type inferred
+    pub fn value(&self, index: usize) -> ConsoleResult<Value> {
+        self.string(index).map(Value::parse)
+    }
+}
+
+type Handler = Box<dyn FnMut(&Args) -> ConsoleResult<String> + Send>;
+
+/// A registered command
+pub struct Command {
+    handler: Handler,
+    usage: String,
+    help: String,
+}
+
+impl Command {
+    /// Set the usage line shown in errors
+    pub fn usage(&mut self, usage: impl Into<String>) -> &mut Self {
+        self.usage = usage.into();
+        self
+    }
+
+    /// Set the one-line description shown by `help`
+    pub fn help(&mut self, help: impl Into<String>) -> &mut Self {
+        self.help = help.into();
+        self
+    }
+}
+
+/// Kind of a line in the console output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// An executed input line
+    Input,
+    Output,
+    Error,
+}
+
+/// A line of console output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleLine {
+    pub kind: LineKind,
+    pub text: String,
+}
+
+/// Commands handled by the registry itself
+const BUILTIN_NAMES: [&str; 2] = ["clear", "help"];
+
+/// Named commands plus input history and output
+pub struct CommandRegistry {
+    commands: BTreeMap<String, Command>,
+    history: Vec<String>,
+    /// Position while browsing history (None = editing a new line)
+    history_cursor: Option<usize>,
+    output: VecDeque<ConsoleLine>,
+    max_output: usize,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+            history: Vec::new(),
+            history_cursor: None,
+            output: VecDeque::new(),
+            max_output: 256,
+        }
+    }
+
+    /// Register (or replace) a command
+    pub fn register<F>(&mut self, name: &str, handler: F) -> &mut Command
+    where
+        F: FnMut(&Args) -> ConsoleResult<String> + Send + 'static,
+    {
+        let command = Command { handler: Box::new(handler), usage: name.to_string(), help: String::new() };
+        self.commands.insert(name.to_string(), command);
+        self.commands.get_mut(name).unwrap()
+    }
+
+    /// Remove a command; returns true if it existed
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.commands.remove(name).is_some()
+    }
+
+    /// All command names (including `help` and `clear`), sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.commands.keys().map(String::as_str).chain(BUILTIN_NAMES).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Run a command line without touching history or output
+    pub fn run(&mut self, line: &str) -> ConsoleResult<String> {
+        let mut words = tokenize(line)?;
+        if words.is_empty() {
+            return Ok(String::new());
+        }
+        let name = words.remove(0);
+
+        if let Some(command) = self.commands.get_mut(&name) {
+            let args = Args::new(words, command.usage.clone());
+            return (command.handler)(&args);
+        }
+        match name.as_str() {
+            "help" => Ok(self.help_text()),
+            "clear" => {
+                self.output.clear();
+                Ok(String::new())
+            }
+            _ => Err(ConsoleError::UnknownCommand(name)),
+        }
+    }
+
+    /// Run a line typed by the user, recording history and output
+    pub fn execute(&mut self, line: &str) -> ConsoleResult<String> {
+        let line = line.trim();
+        self.history_cursor = None;
+        if line.is_empty() {
+            return Ok(String::new());
+        }
+        if self.history.last().map(String::as_str) != Some(line) {
+            self.history.push(line.to_string());
+        }
+
+        self.push_line(LineKind::Input, format!("> {}", line));
+        let result = self.run(line);
+        match &result {
+            Ok(text) => {
+                for text in text.lines() {
+                    self.push_line(LineKind::Output, text.to_string());
+                }
+            }
+            Err(error) => self.push_line(LineKind::Error, error.to_string()),
+        }
+        result
+    }
+
+    fn help_text(&self) -> String {
+        self.names()
+            .into_iter()
+            .map(|name| match self.commands.get(name) {
+                Some(command) if !command.help.is_empty() => format!("{} - {}", command.usage, command.help),
+                Some(command) => command.usage.clone(),
+                None => name.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Command names starting with `prefix`, sorted
+    pub fn complete(&self, prefix: &str) -> Vec<&str> {
+        self.names().into_iter().filter(|name| name.starts_with(prefix)).collect()
+    }
+
+    /// Tab completion for an input line
+    ///
+    /// Completes the command name to the longest prefix shared by all
+    /// matches (plus a space when only one matches). Arguments are left
+    /// alone. Returns None when nothing can be added.
+    pub fn complete_line(&self, input: &str) -> Option<String> {
+        if input.contains(char::is_whitespace) {
+            return None;
+        }
+        let matches = self.complete(input);
+        let completed = match matches.as_slice() {
+            [] => return None,
+            [only] => format!("{} ", only),
+            [first, rest @ ..] => {
+                let shared = rest.iter().fold(first.chars().count(), |len, name| {
+                    first.chars().zip(name.chars()).take(len).take_while(|(a, b)| a == b).count()
+                });
+                first.chars().take(shared).collect()
+            }
+        };
+        (completed != input).then_some(completed)
+    }
+
+    /// Executed lines, oldest first
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Step back in history (Up arrow)
+    pub fn history_prev(&mut self) -> Option<&str> {
+        let index = match self.history_cursor {
+            Some(0) => 0,
+            Some(i) => i - 1,
+            None => self.history.len().checked_sub(1)?,
+        };
+        self.history_cursor = Some(index);
+        self.history.get(index).map(String::as_str)
+    }
+
+    /// Step forward in history (Down arrow); None when back at a new line
+    pub fn history_next(&mut self) -> Option<&str> {
+        let index = self.history_cursor? + 1;
+        if index >= self.history.len() {
+            self.history_cursor = None;
+            return None;
+        }
+        self.history_cursor = Some(index);
+        self.history.get(index).map(String::as_str)
+    }
+
+    /// Add a line to the output, dropping the oldest past the limit
+    pub fn push_line(&mut self, kind: LineKind, text: String) {
+        if self.output.len() == self.max_output {
+            self.output.pop_front();
+        }
+        self.output.push_back(ConsoleLine { kind, text });
+    }
+
+    /// Output lines, oldest first
+    pub fn output(&self) -> impl Iterator<Item = &ConsoleLine> {
+        self.output.iter()
+    }
+
+    pub fn output_len(&self) -> usize {
+        self.output.len()
+    }
+
+    /// Register commands for the `RuntimeSettings` toggles
+    ///
+    /// Each command prints the current value when called without
+    /// arguments. The commands only change the atom; call
+    /// `RuntimeSettings::apply_to_graphics` and `apply_to_isr` each frame
+    /// to put the changes into effect.
+    pub fn register_builtins(&mut self, settings: &Atom<RuntimeSettings>) {
+        let s = settings.clone();
+        self.register("vsync", move |args| {
+            args.expect_len(0, 1)?;
+            if !args.is_empty() {
+                let enabled = args.bool(0)?;
+                s.update(|settings| settings.vsync = enabled);
+            }
+            Ok(format!("vsync = {}", s.get().vsync))
+        })
+        .usage("vsync [on|off]")
+        .help("wait for vertical blank when presenting");
+
+        let s = settings.clone();
+        self.register("isr", move |args| {
+            args.expect_len(0, 2)?;
+            if args.is_empty() {
+                let isr = &s.get().isr;
+                return Ok(format!(
+                    "edge_threshold = {}\nmotion_sensitivity = {}\ntemporal_blend = {}\ndistance_start = {}\ndistance_end = {}\nfoveated = {}",
+                    isr.edge_threshold,
+                    isr.motion_sensitivity,
+                    isr.temporal_blend,
+                    isr.distance_start,
+                    isr.distance_end,
+                    isr.foveated_enabled,
+                ));
+            }
+
+            let field = args.string(0)?;
+            if args.len() == 2 {
+                if field == "foveated" {
+                    let enabled = args.bool(1)?;
+                    s.update(|settings| settings.isr.foveated_enabled = enabled);
+                } else {
+                    let value = args.float(1)? as f32;
+                    let mut isr = s.get().isr.clone();
+                    match field {
+                        "edge_threshold" => isr.edge_threshold = value.max(1e-4),
+                        "motion_sensitivity" => isr.motion_sensitivity = value,
+                        "temporal_blend" => isr.temporal_blend = value.clamp(0.0, 1.0),
+                        "distance_start" => isr.distance_start = value,
+                        "distance_end" => isr.distance_end = value,
+                        _ => return Err(args.usage_error(format!("unknown ISR setting '{}'", field))),
+                    }
+                    s.update(|settings| settings.isr = isr);
+                }
+            }
+
+            let isr = &s.get().isr;
+            let value = match field {
+                "edge_threshold" => isr.edge_threshold.to_string(),
+                "motion_sensitivity" => isr.motion_sensitivity.to_string(),
+                "temporal_blend" => isr.temporal_blend.to_string(),
+                "distance_start" => isr.distance_start.to_string(),
+                "distance_end" => isr.distance_end.to_string(),
+                "foveated" => isr.foveated_enabled.to_string(),
+                _ => return Err(args.usage_error(format!("unknown ISR setting '{}'", field))),
+            };
+            Ok(format!("{} = {}", field, value))
+        })
+        .usage("isr [setting [value]]")
+        .help("ISR thresholds (edge_threshold, motion_sensitivity, temporal_blend, distance_start, distance_end, foveated)");
+    }
+}
+
+/// Framework settings the built-in commands change
+#[derive(Debug, Clone)]
+pub struct RuntimeSettings {
+    pub vsync: bool,
+    pub isr: IsrConfig,
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self { vsync: true, isr: IsrConfig::default() }
+    }
+}
+
+impl State for RuntimeSettings {}
+
+impl RuntimeSettings {
+    /// Reconfigure the swap chain if vsync changed
+    ///
+    /// Returns the event to dispatch when something was reconfigured.
+    pub fn apply_to_graphics(&self, graphics: &mut Graphics) -> Dx12Result<Option<GraphicsEvent>> {
+        if graphics.config().vsync == self.vsync {
+            return Ok(None);
+        }
+        let mut config = graphics.backend().swap_chain().config().clone();
+        config.vsync = self.vsync;
+        graphics.reconfigure(config).map(Some)
+    }
+
+    /// Copy the ISR thresholds into an analyzer
+    pub fn apply_to_isr(&self, analyzer: &mut IsrAnalyzer) {
+        if analyzer.config() != &self.isr {
+            analyzer.set_config(self.isr.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> CommandRegistry {
+        let mut registry = CommandRegistry::new();
+        registry
+            .register("add", |args| {
+                args.expect_len(2, 2)?;
+                Ok((args.int(0)? + args.int(1)?).to_string())
+            })
+            .usage("add <a> <b>")
+            .help("add two integers");
+        registry.register("echo", |args| Ok((0..args.len()).map(|i| args.get(i).unwrap()).collect::<Vec<_>>().join("|")));
+        registry.register("set_fog", |args| Ok(format!("fog = {}", args.float(0)?)));
+        registry
+    }
+
+    #[test]
+    fn tokenize_splits_words_and_quotes() {
+        assert_eq!(tokenize("  add 1   2 ").unwrap(), ["add", "1", "2"]);
+        assert_eq!(tokenize(r#"say "hello world" x"#).unwrap(), ["say", "hello world", "x"]);
+        assert_eq!(tokenize(r#"say "a \"b\"""#).unwrap(), ["say", r#"a "b""#]);
+        assert_eq!(tokenize(r#"name """#).unwrap(), ["name", ""]);
+        assert!(tokenize("").unwrap().is_empty());
+        assert_eq!(tokenize(r#"say "open"#), Err(ConsoleError::Parse("unterminated quote".to_string())));
+        assert_eq!(tokenize(r#"say "end\"#), Err(ConsoleError::Parse("trailing backslash".to_string())));
+    }
+
+    #[test]
+    fn values_and_arguments_parse() {
+        assert_eq!(Value::parse("42"), Value::Int(42));
+        assert_eq!(Value::parse("-0.5"), Value::Float(-0.5));
+        assert_eq!(Value::parse("On"), Value::Bool(true));
+        assert_eq!(Value::parse("fog"), Value::Str("fog".to_string()));
+        assert_eq!(parse_bool("no"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+
+        let args = Args::new(vec!["3".to_string(), "x".to_string()], "cmd <n> <b>");
+        assert_eq!(args.int(0), Ok(3));
+        assert_eq!(args.float(0), Ok(3.0));
+        assert!(matches!(args.int(1), Err(ConsoleError::Usage { .. })));
+        assert!(matches!(args.bool(1), Err(ConsoleError::Usage { .. })));
+        assert!(matches!(args.string(2), Err(ConsoleError::Usage { .. })));
+        assert!(args.expect_len(2, 2).is_ok());
+        assert_eq!(
+            args.expect_len(0, 1).unwrap_err().to_string(),
+            "expected at most 1 argument(s) (usage: cmd <n> <b>)"
+        );
+    }
+
+    #[test]
+    fn execute_runs_commands_and_records_output() {
+        let mut registry = registry();
+        assert_eq!(registry.execute("add 2 3"), Ok("5".to_string()));
+        assert_eq!(registry.execute(r#"echo "a b" c"#), Ok("a b|c".to_string()));
+        assert_eq!(registry.execute("nope"), Err(ConsoleError::UnknownCommand("nope".to_string())));
+        assert!(matches!(registry.execute("add 1"), Err(ConsoleError::Usage { .. })));
+
+        let output: Vec<_> = registry.output().map(|line| (line.kind, line.text.as_str())).collect();
+        assert_eq!(output[..4], [
+            (LineKind::Input, "> add 2 3"),
+            (LineKind::Output, "5"),
+            (LineKind::Input, "> echo \"a b\" c"),
+            (LineKind::Output, "a b|c"),
+        ]);
+        assert_eq!(output[5], (LineKind::Error, "Unknown command: nope"));
+
+        // Commands can be replaced and removed
+        registry.register("add", |_| Ok("replaced".to_string()));
+        assert_eq!(registry.run("add"), Ok("replaced".to_string()));
+        assert!(registry.unregister("add"));
+        assert!(!registry.unregister("add"));
+        assert!(matches!(registry.run("add 1 2"), Err(ConsoleError::UnknownCommand(_))));
+
+        assert!(registry.execute("help").unwrap().contains("set_fog"));
+        registry.execute("clear").unwrap();
+        assert_eq!(registry.output_len(), 0);
+    }
+
+    #[test]
+    fn history_skips_repeats_and_walks_both_ways() {
+        let mut registry = registry();
+        for line in ["add 1 1", "add 1 1", "echo x", "  ", "set_fog 1"] {
+            let _ = registry.execute(line);
+        }
+        assert_eq!(registry.history(), ["add 1 1", "echo x", "set_fog 1"]);
+        assert_eq!(registry.history_prev(), Some("set_fog 1"));
+        assert_eq!(registry.history_prev(), Some("echo x"));
+        assert_eq!(registry.history_prev(), Some("add 1 1"));
+        assert_eq!(registry.history_prev(), Some("add 1 1"));
+        assert_eq!(registry.history_next(), Some("echo x"));
+        assert_eq!(registry.history_next(), Some("set_fog 1"));
+        assert_eq!(registry.history_next(), None);
+    }
+
+    #[test]
+    fn completion_extends_to_the_shared_prefix() {
+        let mut registry = registry();
+        registry.register("set_exposure", |_| Ok(String::new()));
+        assert_eq!(registry.complete("se"), ["set_exposure", "set_fog"]);
+        assert_eq!(registry.complete("h"), ["help"]);
+        assert_eq!(registry.complete_line("se"), Some("set_".to_string()));
+        assert_eq!(registry.complete_line("set_"), None);
+        assert_eq!(registry.complete_line("set_f"), Some("set_fog ".to_string()));
+        assert_eq!(registry.complete_line("cl"), Some("clear ".to_string()));
+        assert_eq!(registry.complete_line("zzz"), None);
+        // Arguments are not completed
+        assert_eq!(registry.complete_line("set_fog 0"), None);
+    }
+
+    #[test]
+    fn builtins_update_the_settings_atom() {
+        let settings = Atom::new("runtime_settings", RuntimeSettings::default());
+        let mut registry = CommandRegistry::new();
+        registry.register_builtins(&settings);
+
+        assert_eq!(registry.execute("vsync"), Ok("vsync = true".to_string()));
+        assert_eq!(registry.execute("vsync off"), Ok("vsync = false".to_string()));
+        assert!(!settings.get().vsync);
+        assert!(registry.execute("vsync sometimes").is_err());
+
+        assert_eq!(registry.execute("isr temporal_blend 2"), Ok("temporal_blend = 1".to_string()));
+        assert_eq!(registry.execute("isr foveated on"), Ok("foveated = true".to_string()));
+        assert!(settings.get().isr.foveated_enabled);
+        assert!(registry.execute("isr tile_size 4").is_err());
+
+        let mut analyzer = IsrAnalyzer::new(64, 64, IsrConfig::default());
+        settings.get().apply_to_isr(&mut analyzer);
+        assert_eq!(analyzer.config(), &settings.get().isr);
+    }
+}
@@ -1,6 +1,7 @@
 //! Component trait - React-inspired component system
 
 use crate::core::{Element, RenderContext, Props, State};
+use crate::hooks;
 use std::any::Any;
 use uuid::Uuid;
 
@@ -140,7 +141,56 @@ where
         }
     }
 
+    /// Render, with hooks kept between this component's renders
     pub fn render(&self, ctx: &mut RenderContext) -> Element {
-        (self.render_fn)(ctx)
+        let delta_time = ctx.delta_time;
+        hooks::with_hooks(self.id, delta_time, || (self.render_fn)(ctx)).0
+    }
+}
+
+impl<F> Drop for FunctionalComponent<F>
+where
+    F: Fn(&mut RenderContext) -> Element + Send + Sync + 'static,
+{
+    fn drop(&mut self) {
+        hooks::drop_hooks(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Context;
+    use crate::math::Rect;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn dropping_a_functional_component_forgets_its_hooks() {
+        let target = Arc::new(Mutex::new(0.0f32));
+        let component = {
+            let target = target.clone();
+            FunctionalComponent::new(move |_ctx| {
+                hooks::use_spring(*target.lock());
+                Element::text("spring", 0.0, 0.0)
+            })
+        };
+        let context = Context::new();
+        let mut ctx = RenderContext::new(&context, Rect::new(0.0, 0.0, 800.0, 600.0));
+        ctx.delta_time = FRAME;
+        component.render(&mut ctx);
+        *target.lock() = 10.0;
+        component.render(&mut ctx);
+
+        // The spring slot is still held and mid-flight
+        let id = component.id;
+        let (value, animating) = hooks::with_hooks(id, FRAME, || hooks::use_spring(10.0f32).value());
+        assert!(value < 10.0 && animating);
+
+        // Once dropped, the same id gets a fresh spring at rest
+        drop(component);
+        assert_eq!(hooks::with_hooks(id, FRAME, || hooks::use_spring(10.0f32).value()), (10.0, false));
     }
 }
@@ -5,11 +5,16 @@ use crate::dx12::Device;
 use parking_lot::RwLock;
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+/// Source of context versions, shared so no two contexts have the same one
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
 /// Application-wide context that can be accessed by any component
 pub struct Context {
     values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    version: u64,
 }
 
 impl Context {
@@ -17,12 +22,22 @@ impl Context {
     pub fn new() -> Self {
         Self {
             values: HashMap::new(),
+            version: NEXT_VERSION.fetch_add(1, Ordering::Relaxed),
         }
     }
 
+    /// Changes whenever a value is provided or removed
+    ///
+    /// Versions are unique across contexts, so a different context never
+    /// looks unchanged.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
     /// Provide a value to the context
     pub fn provide<T: Any + Send + Sync + 'static>(&mut self, value: T) {
         self.values.insert(TypeId::of::<T>(), Arc::new(value));
+        self.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
     }
 
     /// Get a value from the context
@@ -39,9 +54,9 @@ impl Context {
 
     /// Remove a value from the context
     pub fn remove<T: Any + Send + Sync + 'static>(&mut self) -> Option<Arc<T>> {
-        self.values
-            .remove(&TypeId::of::<T>())
-            .and_then(|v| v.downcast::<T>().ok())
+        let removed = self.values.remove(&TypeId::of::<T>())?;
+        self.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+        removed.downcast::<T>().ok()
     }
 }
 
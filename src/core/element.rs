@@ -2,6 +2,7 @@
 
 use crate::math::{Color, Rect, Transform};
 use crate::core::ComponentId;
+use crate::core::error_boundary::{self, BoundaryError, ErrorPhase};
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -285,6 +286,23 @@ impl ElementBuilder {
         self
     }
 
+    /// Build a child subtree inside an error boundary
+    ///
+    /// If `subtree` panics or returns `Err`, `fallback` is added instead and
+    /// the error is sent to the global error reporter.
+    pub fn error_boundary<S, F>(mut self, subtree: S, fallback: F) -> Self
+    where
+        S: FnOnce() -> Result<Element, String>,
+        F: FnOnce(&BoundaryError) -> Element,
+    {
+        let child = match error_boundary::catch(ErrorPhase::Render, subtree) {
+            Ok(element) => element,
+            Err(error) => fallback(&error),
+        };
+        self.element.children.push(child);
+        self
+    }
+
     pub fn build(self) -> Element {
         self.element
     }
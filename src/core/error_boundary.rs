@@ -0,0 +1,423 @@
+//! Error boundaries - contain panics and errors from a subtree
+//!
+//! A failing subtree is replaced by a fallback Element while the rest of the
+//! tree keeps rendering. Every caught error is also passed to the global
+//! reporter (see `set_error_reporter`) for logging or telemetry.
+
+use crate::core::{BoxedComponent, ComponentDyn, ComponentId, Element, RenderContext};
+use crate::crash;
+use crate::math::{Color, Rect};
+use parking_lot::{Mutex, RwLock};
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+/// Where in a component's life an error happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPhase {
+    Render,
+    Mount,
+    Unmount,
+}
+
+/// An error caught by a boundary
+#[derive(Debug, Clone)]
+pub struct BoundaryError {
+    pub phase: ErrorPhase,
+    pub message: String,
+    /// True if the error was a panic rather than an `Err` result
+    pub panicked: bool,
+}
+
+type Reporter = Arc<dyn Fn(&BoundaryError) + Send + Sync>;
+
+static REPORTER: RwLock<Option<Reporter>> = parking_lot::const_rwlock(None);
+
+/// Install a global callback that receives every caught error
+pub fn set_error_reporter<F>(reporter: F)
+where
+    F: Fn(&BoundaryError) + Send + Sync + 'static,
+{
+    *REPORTER.write() = Some(Arc::new(reporter));
+}
+
+/// Remove the global error callback
+pub fn clear_error_reporter() {
+    *REPORTER.write() = None;
+}
+
+fn report(error: &BoundaryError) {
+    log::error!("Error boundary caught {:?} error: {}", error.phase, error.message);
+    let reporter = REPORTER.read().clone();
+    if let Some(reporter) = reporter {
+        reporter(error);
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "panic with non-string payload".to_string()
+    }
+}
+
+/// Run `f`, turning a panic or `Err` into a reported `BoundaryError`
+///
+/// Panics are contained, so the crash handler files them as non-fatal.
+pub fn catch<T>(phase: ErrorPhase, f: impl FnOnce() -> Result<T, String>) -> Result<T, BoundaryError> {
+    let error = match catch_unwind(AssertUnwindSafe(|| crash::recoverable(f))) {
+        Ok(Ok(value)) => return Ok(value),
+        Ok(Err(message)) => BoundaryError { phase, message, panicked: false },
+        Err(payload) => BoundaryError { phase, message: panic_message(payload), panicked: true },
+    };
+    report(&error);
+    Err(error)
+}
+
+/// Default fallback: a dark red box, with the message in debug builds
+pub fn default_fallback(error: &BoundaryError) -> Element {
+    let mut fallback = Element::rect(Rect::new(0.0, 0.0, 200.0, 40.0))
+        .fill(Color::from_hex(0x5c1a1a))
+        .with_key("error-boundary-fallback");
+    if cfg!(debug_assertions) {
+        fallback = fallback.child(Element::text(error.message.clone(), 8.0, 12.0).fill(Color::WHITE));
+    }
+    fallback
+}
+
+type RenderFn = Box<dyn Fn(&mut RenderContext) -> Result<Element, String> + Send + Sync>;
+type FallbackFn = Box<dyn Fn(&BoundaryError) -> Element + Send + Sync>;
+
+enum Child {
+    Component(Mutex<BoxedComponent>),
+    Render(RenderFn),
+}
+
+/// Component that renders a fallback when its child fails
+///
+/// Once the child has failed the fallback is shown until `retry` is called.
+/// As a `ComponentDyn` it can sit anywhere in a component tree; lifecycle
+/// callbacks are forwarded to the child and contained the same way.
+pub struct ErrorBoundary {
+    id: ComponentId,
+    child: Child,
+    fallback: FallbackFn,
+    error: Mutex<Option<BoundaryError>>,
+}
+
+impl ErrorBoundary {
+    /// Wrap a component
+    pub fn new(child: BoxedComponent) -> Self {
+        Self {
+            id: ComponentId::new(),
+            child: Child::Component(Mutex::new(child)),
+            fallback: Box::new(default_fallback),
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Wrap a render function that may return an error
+    pub fn from_fn<F>(render: F) -> Self
+    where
+        F: Fn(&mut RenderContext) -> Result<Element, String> + Send + Sync + 'static,
+    {
+        Self {
+            id: ComponentId::new(),
+            child: Child::Render(Box::new(render)),
+            fallback: Box::new(default_fallback),
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Set the fallback rendered after an error
+    pub fn with_fallback<F>(mut self, fallback: F) -> Self
+    where
+        F: Fn(&BoundaryError) -> Element + Send + Sync + 'static,
+    {
+        self.fallback = Box::new(fallback);
+        self
+    }
+
+    /// Render the child, or the fallback if it has failed
+    pub fn render(&self, ctx: &mut RenderContext) -> Element {
+        if let Some(error) = self.error.lock().as_ref() {
+            return (self.fallback)(error);
+        }
+
+        let result = match &self.child {
+            Child::Component(component) => catch(ErrorPhase::Render, || Ok(component.lock().render(ctx))),
+            Child::Render(render) => catch(ErrorPhase::Render, || render(ctx)),
+        };
+
+        match result {
+            Ok(element) => element,
+            Err(error) => {
+                let fallback = (self.fallback)(&error);
+                *self.error.lock() = Some(error);
+                fallback
+            }
+        }
+    }
+
+    /// Run the child's mount callbacks
+    pub fn mount(&self) {
+        self.child_lifecycle(ErrorPhase::Mount, |component| {
+            component.will_mount();
+            component.did_mount();
+        });
+    }
+
+    /// Run the child's unmount callback
+    pub fn unmount(&self) {
+        self.child_lifecycle(ErrorPhase::Unmount, |component| component.will_unmount());
+    }
+
+    /// Run a lifecycle callback on a component child, containing failures
+    fn child_lifecycle(&self, phase: ErrorPhase, f: impl FnOnce(&mut BoxedComponent)) {
+        let Child::Component(component) = &self.child else { return };
+        let result = catch(phase, || {
+            f(&mut component.lock());
+            Ok(())
+        });
+        if let Err(error) = result {
+            // Errors while unmounting are reported but leave nothing to render
+            if phase != ErrorPhase::Unmount {
+                *self.error.lock() = Some(error);
+            }
+        }
+    }
+
+    /// Get the error that tripped the boundary
+    pub fn error(&self) -> Option<BoundaryError> {
+        self.error.lock().clone()
+    }
+
+    /// Check if the fallback is being shown
+    pub fn has_error(&self) -> bool {
+        self.error.lock().is_some()
+    }
+
+    /// Clear the error so the next render tries the child again
+    pub fn retry(&self) {
+        *self.error.lock() = None;
+    }
+}
+
+impl ComponentDyn for ErrorBoundary {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn render(&self, ctx: &mut RenderContext) -> Element {
+        ErrorBoundary::render(self, ctx)
+    }
+
+    fn will_mount(&mut self) {
+        self.child_lifecycle(ErrorPhase::Mount, |component| component.will_mount());
+    }
+
+    fn did_mount(&mut self) {
+        self.child_lifecycle(ErrorPhase::Mount, |component| component.did_mount());
+    }
+
+    fn will_unmount(&mut self) {
+        self.unmount();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{AttributeValue, Context};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Renders a label, or panics when told to
+    struct Widget {
+        id: ComponentId,
+        label: &'static str,
+        panic_in: Option<ErrorPhase>,
+        renders: Arc<AtomicUsize>,
+    }
+
+    impl Widget {
+        fn boxed(label: &'static str, panic_in: Option<ErrorPhase>, renders: &Arc<AtomicUsize>) -> BoxedComponent {
+            Box::new(Self { id: ComponentId::new(), label, panic_in, renders: renders.clone() })
+        }
+    }
+
+    impl ComponentDyn for Widget {
+        fn id(&self) -> ComponentId {
+            self.id
+        }
+
+        fn render(&self, _ctx: &mut RenderContext) -> Element {
+            self.renders.fetch_add(1, Ordering::Relaxed);
+            if self.panic_in == Some(ErrorPhase::Render) {
+                panic!("{} failed to render", self.label);
+            }
+            Element::text(self.label, 0.0, 0.0).with_key(self.label)
+        }
+
+        fn will_mount(&mut self) {}
+
+        fn did_mount(&mut self) {
+            if self.panic_in == Some(ErrorPhase::Mount) {
+                panic!("{} failed to mount", self.label);
+            }
+        }
+
+        fn will_unmount(&mut self) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn render_tree(tree: &[BoxedComponent]) -> Vec<Option<String>> {
+        let context = Context::new();
+        let mut ctx = RenderContext::new(&context, Rect::new(0.0, 0.0, 800.0, 600.0));
+        tree.iter().map(|component| component.render(&mut ctx).key).collect()
+    }
+
+    #[test]
+    fn panicking_child_renders_the_fallback() {
+        let renders = Arc::new(AtomicUsize::new(0));
+        let mut tree: Vec<BoxedComponent> = vec![
+            Widget::boxed("before", None, &renders),
+            Box::new(ErrorBoundary::new(Widget::boxed("broken", Some(ErrorPhase::Render), &renders))),
+            Widget::boxed("after", None, &renders),
+        ];
+        for component in &mut tree {
+            component.will_mount();
+            component.did_mount();
+        }
+
+        let fallback = Some("error-boundary-fallback".to_string());
+        let expected = vec![Some("before".to_string()), fallback, Some("after".to_string())];
+        assert_eq!(render_tree(&tree), expected);
+        assert_eq!(renders.swap(0, Ordering::Relaxed), 3);
+
+        let boundary = tree[1].as_any().downcast_ref::<ErrorBoundary>().unwrap();
+        let error = boundary.error().unwrap();
+        assert_eq!((error.phase, error.panicked), (ErrorPhase::Render, true));
+        assert_eq!(error.message, "broken failed to render");
+
+        // The failed child isn't rendered again until a retry
+        assert_eq!(render_tree(&tree), expected);
+        assert_eq!(renders.swap(0, Ordering::Relaxed), 2);
+        tree[1].as_any().downcast_ref::<ErrorBoundary>().unwrap().retry();
+        render_tree(&tree);
+        assert_eq!(renders.swap(0, Ordering::Relaxed), 3);
+
+        for component in &mut tree {
+            component.will_unmount();
+        }
+    }
+
+    #[test]
+    fn mount_failures_and_errors_show_custom_fallbacks() {
+        let renders = Arc::new(AtomicUsize::new(0));
+        let mut boundary: BoxedComponent = Box::new(
+            ErrorBoundary::new(Widget::boxed("child", Some(ErrorPhase::Mount), &renders))
+                .with_fallback(|error| Element::text(error.message.clone(), 0.0, 0.0).with_key("custom")),
+        );
+        boundary.will_mount();
+        boundary.did_mount();
+        assert_eq!(render_tree(std::slice::from_ref(&boundary)), vec![Some("custom".to_string())]);
+        assert_eq!(renders.load(Ordering::Relaxed), 0);
+
+        let failing: BoxedComponent = Box::new(ErrorBoundary::from_fn(|_| Err("no data".to_string())));
+        assert_eq!(render_tree(std::slice::from_ref(&failing)), vec![Some("error-boundary-fallback".to_string())]);
+        let error = failing.as_any().downcast_ref::<ErrorBoundary>().unwrap().error().unwrap();
+        assert_eq!((error.message.as_str(), error.panicked), ("no data", false));
+    }
+
+    /// Shows the frame number, panicking from frame `fail_on` on
+    struct Ticker {
+        id: ComponentId,
+        label: &'static str,
+        fail_on: Option<u64>,
+    }
+
+    impl Ticker {
+        fn boxed(label: &'static str, fail_on: Option<u64>) -> BoxedComponent {
+            Box::new(Self { id: ComponentId::new(), label, fail_on })
+        }
+    }
+
+    impl ComponentDyn for Ticker {
+        fn id(&self) -> ComponentId {
+            self.id
+        }
+
+        fn render(&self, ctx: &mut RenderContext) -> Element {
+            if self.fail_on.is_some_and(|frame| ctx.frame >= frame) {
+                panic!("{} failed on frame {}", self.label, ctx.frame);
+            }
+            Element::text(format!("{} {}", self.label, ctx.frame), 0.0, 0.0)
+        }
+
+        fn will_mount(&mut self) {}
+
+        fn did_mount(&mut self) {}
+
+        fn will_unmount(&mut self) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    /// Text content of a rendered Element, or its key for the fallback
+    fn output(element: &Element) -> String {
+        match element.attributes.get("content") {
+            Some(AttributeValue::String(text)) => text.clone(),
+            _ => element.key.clone().unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn siblings_keep_updating_after_a_child_fails_mid_run() {
+        let tree: Vec<BoxedComponent> = vec![
+            Ticker::boxed("left", None),
+            Box::new(ErrorBoundary::new(Ticker::boxed("middle", Some(3)))),
+            Ticker::boxed("right", None),
+        ];
+        let context = Context::new();
+        let frames: Vec<Vec<String>> = (1..=4)
+            .map(|frame| {
+                let mut ctx = RenderContext::new(&context, Rect::new(0.0, 0.0, 800.0, 600.0));
+                ctx.frame = frame;
+                tree.iter().map(|component| output(&component.render(&mut ctx))).collect()
+            })
+            .collect();
+
+        let fallback = "error-boundary-fallback";
+        assert_eq!(frames[0], ["left 1", "middle 1", "right 1"]);
+        assert_eq!(frames[1], ["left 2", "middle 2", "right 2"]);
+        assert_eq!(frames[2], ["left 3", fallback, "right 3"]);
+        assert_eq!(frames[3], ["left 4", fallback, "right 4"]);
+
+        let boundary = tree[1].as_any().downcast_ref::<ErrorBoundary>().unwrap();
+        assert_eq!(boundary.error().unwrap().message, "middle failed on frame 3");
+    }
+}
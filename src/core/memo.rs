@@ -0,0 +1,402 @@
+//! Memoized components - skip renders when nothing changed
+//!
+//! `Memo` wraps a component and keeps the Element from its last render. The
+//! next render reuses it unless it got props that differ (`Props::props_eq`)
+//! and `Component::will_update` accepted them, the component's state was touched, one of the atoms it depends on has a
+//! new version, the viewport or context changed, or one of its hooks (e.g. a
+//! `use_spring` that hasn't settled) asked for another render.
+
+use crate::core::{Atom, Component, ComponentDyn, ComponentId, Element, Props, RenderContext, State};
+use crate::hooks;
+use crate::math::Rect;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static EXECUTED: AtomicU64 = AtomicU64::new(0);
+static SKIPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Render counts across all `Memo` components
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoStats {
+    /// Renders that ran the wrapped component
+    pub executed: u64,
+    /// Renders that reused the previous Element
+    pub skipped: u64,
+}
+
+/// Get the render counts since the last reset
+pub fn memo_stats() -> MemoStats {
+    MemoStats {
+        executed: EXECUTED.load(Ordering::Relaxed),
+        skipped: SKIPPED.load(Ordering::Relaxed),
+    }
+}
+
+/// Reset the render counts
+pub fn reset_memo_stats() {
+    EXECUTED.store(0, Ordering::Relaxed);
+    SKIPPED.store(0, Ordering::Relaxed);
+}
+
+type VersionFn = Box<dyn Fn() -> u64 + Send + Sync>;
+
+/// The inputs a cached Element was rendered with, besides props and state
+#[derive(PartialEq)]
+struct CacheKey {
+    versions: Vec<u64>,
+    viewport: Rect,
+    context_version: u64,
+}
+
+struct Cached {
+    key: CacheKey,
+    element: Element,
+}
+
+/// A component that only re-renders when its inputs change
+pub struct Memo<C: Component> {
+    id: ComponentId,
+    component: C,
+    dependencies: Vec<VersionFn>,
+    cache: Mutex<Option<Cached>>,
+}
+
+impl<C: Component> Memo<C> {
+    /// Create the wrapped component from props
+    pub fn new(props: C::Props) -> Self {
+        Self::wrap(C::new(props))
+    }
+
+    /// Wrap an existing component
+    pub fn wrap(component: C) -> Self {
+        Self {
+            id: ComponentId::new(),
+            component,
+            dependencies: Vec::new(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Re-render whenever `atom` changes
+    pub fn depends_on<T: State>(mut self, atom: &Atom<T>) -> Self {
+        let atom = atom.clone();
+        self.dependencies.push(Box::new(move || atom.version()));
+        self
+    }
+
+    /// Get the wrapped component
+    pub fn component(&self) -> &C {
+        &self.component
+    }
+
+    /// Get mutable access to the wrapped component (forces the next render)
+    pub fn component_mut(&mut self) -> &mut C {
+        self.invalidate();
+        &mut self.component
+    }
+
+    /// Replace the props, keeping the component's state
+    ///
+    /// Equal props are not an update. Otherwise the next render runs if
+    /// `will_update` accepts the new props.
+    pub fn set_props(&mut self, props: C::Props) {
+        let state = self.component.state().clone();
+        if !self.component.props().props_eq(&props) && self.component.will_update(&props, &state) {
+            self.invalidate();
+        }
+        let mut next = C::new(props);
+        *next.state_mut() = state;
+        self.component = next;
+    }
+
+    /// Update the component's state (forces the next render)
+    pub fn set_state<F>(&mut self, updater: F)
+    where
+        F: FnOnce(&mut C::State),
+    {
+        self.invalidate();
+        self.component.set_state(updater);
+    }
+
+    /// Drop the cached Element so the next render runs
+    pub fn invalidate(&self) {
+        *self.cache.lock() = None;
+    }
+
+    /// Render, or reuse the previous Element if nothing changed
+    pub fn render(&self, ctx: &mut RenderContext) -> Element {
+        let key = CacheKey {
+            versions: self.dependencies.iter().map(|version| version()).collect(),
+            viewport: ctx.viewport,
+            context_version: ctx.context.version(),
+        };
+        let mut cache = self.cache.lock();
+        if let Some(cached) = cache.as_ref() {
+            if cached.key == key {
+                SKIPPED.fetch_add(1, Ordering::Relaxed);
+                return cached.element.clone();
+            }
+        }
+
+        EXECUTED.fetch_add(1, Ordering::Relaxed);
+        let delta_time = ctx.delta_time;
+        let (element, rerender) = hooks::with_hooks(self.id, delta_time, || self.component.render(ctx));
+        // A hook that is still animating needs the next render to run too
+        *cache = (!rerender).then(|| Cached { key, element: element.clone() });
+        element
+    }
+}
+
+impl<C: Component> ComponentDyn for Memo<C> {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn render(&self, ctx: &mut RenderContext) -> Element {
+        Memo::render(self, ctx)
+    }
+
+    fn will_mount(&mut self) {
+        self.component.will_mount();
+    }
+
+    fn did_mount(&mut self) {
+        self.component.did_mount();
+    }
+
+    fn will_unmount(&mut self) {
+        self.component.will_unmount();
+        hooks::drop_hooks(self.id);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Context;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone)]
+    struct ItemProps {
+        label: String,
+        renders: Arc<AtomicUsize>,
+    }
+
+    impl Props for ItemProps {
+        fn props_eq(&self, other: &Self) -> bool {
+            self.label == other.label
+        }
+    }
+
+    struct Item {
+        props: ItemProps,
+        state: u32,
+    }
+
+    impl Component for Item {
+        type Props = ItemProps;
+        type State = u32;
+
+        fn new(props: ItemProps) -> Self {
+            Self { props, state: 0 }
+        }
+
+        fn props(&self) -> &ItemProps {
+            &self.props
+        }
+
+        fn state(&self) -> &u32 {
+            &self.state
+        }
+
+        fn state_mut(&mut self) -> &mut u32 {
+            &mut self.state
+        }
+
+        fn set_state<F: FnOnce(&mut u32)>(&mut self, updater: F) {
+            updater(&mut self.state);
+        }
+
+        fn will_update(&mut self, next_props: &ItemProps, _next_state: &u32) -> bool {
+            next_props.label != "ignored"
+        }
+
+        fn render(&self, _ctx: &mut RenderContext) -> Element {
+            self.props.renders.fetch_add(1, Ordering::Relaxed);
+            Element::text(format!("{} {}", self.props.label, self.state), 0.0, 0.0)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn item(label: &str, renders: &Arc<AtomicUsize>) -> ItemProps {
+        ItemProps { label: label.to_string(), renders: renders.clone() }
+    }
+
+    fn render_all(items: &[Memo<Item>], context: &Context, viewport: Rect) {
+        let mut ctx = RenderContext::new(context, viewport);
+        for item in items {
+            item.render(&mut ctx);
+        }
+    }
+
+    #[test]
+    fn changing_one_of_1000_items_renders_only_that_item() {
+        let renders = Arc::new(AtomicUsize::new(0));
+        let context = Context::new();
+        let viewport = Rect::new(0.0, 0.0, 800.0, 600.0);
+        let mut items: Vec<Memo<Item>> =
+            (0..1000).map(|i| Memo::new(item(&format!("item {}", i), &renders))).collect();
+
+        render_all(&items, &context, viewport);
+        assert_eq!(renders.swap(0, Ordering::Relaxed), 1000);
+
+        // Equal props are not an update, and will_update can veto one
+        items[3].set_props(item("item 3", &renders));
+        items[7].set_props(item("ignored", &renders));
+        items[500].set_props(item("changed", &renders));
+        render_all(&items, &context, viewport);
+        assert_eq!(renders.swap(0, Ordering::Relaxed), 1);
+        assert_eq!(items[500].component().props().label, "changed");
+
+        items[999].set_state(|count| *count += 1);
+        render_all(&items, &context, viewport);
+        assert_eq!(renders.swap(0, Ordering::Relaxed), 1);
+
+        render_all(&items, &context, viewport);
+        assert_eq!(renders.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn viewport_context_and_atoms_invalidate() {
+        let renders = Arc::new(AtomicUsize::new(0));
+        let atom = Atom::new("memo_test", 0u32);
+        let memo = Memo::<Item>::new(item("a", &renders)).depends_on(&atom);
+        let mut context = Context::new();
+        let viewport = Rect::new(0.0, 0.0, 800.0, 600.0);
+
+        let render = |context: &Context, viewport| memo.render(&mut RenderContext::new(context, viewport));
+        render(&context, viewport);
+        render(&context, viewport);
+        assert_eq!(renders.load(Ordering::Relaxed), 1);
+
+        render(&context, Rect::new(0.0, 0.0, 1024.0, 768.0));
+        assert_eq!(renders.load(Ordering::Relaxed), 2);
+
+        context.provide(1.5f32);
+        render(&context, viewport);
+        assert_eq!(renders.load(Ordering::Relaxed), 3);
+
+        // A different context with the same values is still a change
+        render(&Context::new(), viewport);
+        assert_eq!(renders.load(Ordering::Relaxed), 4);
+
+        atom.set(1);
+        render(&context, viewport);
+        render(&context, viewport);
+        assert_eq!(renders.load(Ordering::Relaxed), 5);
+    }
+
+    #[derive(Debug, Clone)]
+    struct SliderProps {
+        renders: Arc<AtomicUsize>,
+    }
+
+    impl Props for SliderProps {
+        fn props_eq(&self, _other: &Self) -> bool {
+            true
+        }
+    }
+
+    /// Springs towards its state
+    struct Slider {
+        props: SliderProps,
+        target: f32,
+    }
+
+    impl Component for Slider {
+        type Props = SliderProps;
+        type State = f32;
+
+        fn new(props: SliderProps) -> Self {
+            Self { props, target: 0.0 }
+        }
+
+        fn props(&self) -> &SliderProps {
+            &self.props
+        }
+
+        fn state(&self) -> &f32 {
+            &self.target
+        }
+
+        fn state_mut(&mut self) -> &mut f32 {
+            &mut self.target
+        }
+
+        fn set_state<F: FnOnce(&mut f32)>(&mut self, updater: F) {
+            updater(&mut self.target);
+        }
+
+        fn render(&self, _ctx: &mut RenderContext) -> Element {
+            self.props.renders.fetch_add(1, Ordering::Relaxed);
+            let x = crate::hooks::use_spring(self.target).value();
+            Element::text("slider", x, 0.0)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn animating_hooks_keep_rendering_until_settled() {
+        let renders = Arc::new(AtomicUsize::new(0));
+        let mut slider = Memo::<Slider>::new(SliderProps { renders: renders.clone() });
+        let context = Context::new();
+        let frame = |slider: &Memo<Slider>| {
+            let mut ctx = RenderContext::new(&context, Rect::new(0.0, 0.0, 800.0, 600.0));
+            ctx.delta_time = 1.0 / 60.0;
+            slider.render(&mut ctx)
+        };
+
+        frame(&slider);
+        frame(&slider);
+        assert_eq!(renders.swap(0, Ordering::Relaxed), 1);
+
+        // Every frame renders until the spring settles, then the Element is reused
+        slider.set_state(|target| *target = 100.0);
+        let mut frames = 0;
+        loop {
+            let before = renders.load(Ordering::Relaxed);
+            frame(&slider);
+            frames += 1;
+            if renders.load(Ordering::Relaxed) == before {
+                break;
+            }
+            assert!(frames < 600, "never settled");
+        }
+        assert!(frames > 10);
+        slider.will_unmount();
+    }
+}
@@ -8,6 +8,8 @@ pub mod element;
 mod context;
 mod state;
 mod props;
+pub mod error_boundary;
+mod memo;
 
 pub use app::{App, AppBuilder};
 pub use component::{Component, ComponentId, ComponentDyn, BoxedComponent, FunctionalComponent, Lifecycle};
@@ -15,3 +17,5 @@ pub use element::{Element, ElementBuilder, ElementType, Style, AttributeValue, f
 pub use context::{Context, RenderContext, Theme};
 pub use state::{State, ReactiveState, Atom};
 pub use props::{Props, DynamicProps};
+pub use error_boundary::{ErrorBoundary, BoundaryError, ErrorPhase, set_error_reporter};
+pub use memo::{Memo, MemoStats, memo_stats, reset_memo_stats};
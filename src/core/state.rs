@@ -145,6 +145,11 @@ impl<T: State> Atom<T> {
         self.state.read()
     }
 
+    /// Get the current version (increments on each update)
+    pub fn version(&self) -> u64 {
+        self.state.version()
+    }
+
     /// Set a new value
     pub fn set(&self, value: T) {
         self.state.set(value);
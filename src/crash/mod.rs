@@ -0,0 +1,215 @@
+//! Crash reports - bundle logs and context when the app panics
+//!
+//! `install` adds a panic hook that writes `crash-<time>-<n>.zip` to the crash
+//! directory. The archive holds `summary.txt` (message, location, thread,
+//! backtrace), `log.txt` (the `logging` ring buffer, if initialized) and one
+//! file per context entry. Graphics registers its config, device features
+//! and any device-removed report as context; applications can add their
+//! own with `set_context`.
+//!
+//! Native faults (access violations and the like) get the same bundle with
+//! kind "native fault". The hook and the fault filter may run while another
+//! thread, or the crashing one, holds the context or log lock; those
+//! sections are then left out instead of waiting.
+//!
+//! Panics inside a `recoverable` scope are caught by the code that opened
+//! it (e.g. `ErrorBoundary`), so they get a "non-fatal error" bundle
+//! instead of a "panic" one.
+
+mod zip;
+
+use crate::logging::{self, LogFilter};
+use parking_lot::Mutex;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Crash handler options
+#[derive(Debug, Clone)]
+pub struct CrashOptions {
+    /// Where bundles are written
+    pub directory: PathBuf,
+    /// Shown at the top of `summary.txt`
+    pub app_name: String,
+    /// Most recent log records included (0 = no log)
+    pub log_lines: usize,
+    /// Also write bundles for native faults (access violations etc.)
+    pub native_faults: bool,
+}
+
+impl Default for CrashOptions {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("crashes"),
+            app_name: "EPICX application".to_string(),
+            log_lines: 500,
+            native_faults: true,
+        }
+    }
+}
+
+static OPTIONS: OnceLock<CrashOptions> = OnceLock::new();
+static CONTEXT: Mutex<BTreeMap<String, String>> = parking_lot::const_mutex(BTreeMap::new());
+
+/// How long a report waits for the context lock before leaving it out
+const LOCK_TIMEOUT: Duration = Duration::from_millis(100);
+
+thread_local! {
+    /// Number of `recoverable` scopes open on this thread
+    static RECOVERABLE: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Install the panic hook (the previous hook still runs afterwards)
+///
+/// With `native_faults` an unhandled exception filter is installed too.
+/// Only the first call takes effect.
+pub fn install(options: CrashOptions) {
+    let native_faults = options.native_faults;
+    if OPTIONS.set(options).is_err() {
+        return;
+    }
+    if native_faults {
+        crate::dx12::install_exception_filter(report_fault);
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "(non-string panic payload)".to_string());
+
+        // try_with: the hook can run while the thread's locals are torn down
+        let result = if RECOVERABLE.try_with(Cell::get).unwrap_or(0) > 0 {
+            let location = location.as_deref().unwrap_or("unknown location");
+            report_non_fatal(&format!("{} (panic at {})", message, location))
+        } else {
+            write_report("panic", &message, location.as_deref())
+        };
+        match result {
+            Ok(Some(path)) => eprintln!("Crash report written to {}", path.display()),
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        previous(info);
+    }));
+}
+
+/// Check if `install` has been called
+pub fn is_installed() -> bool {
+    OPTIONS.get().is_some()
+}
+
+/// Attach a named piece of context (written as `<name>.txt`) to reports
+pub fn set_context(name: impl Into<String>, value: impl Into<String>) {
+    CONTEXT.lock().insert(name.into(), value.into());
+}
+
+/// Remove a context entry
+pub fn clear_context(name: &str) {
+    CONTEXT.lock().remove(name);
+}
+
+/// Run `f` in a scope whose panics the caller catches and recovers from
+///
+/// The panic hook reports panics inside it through `report_non_fatal`
+/// rather than as a crash. Scopes nest; the caller still has to catch the
+/// unwind itself.
+pub fn recoverable<R>(f: impl FnOnce() -> R) -> R {
+    struct Scope;
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            RECOVERABLE.with(|depth| depth.set(depth.get() - 1));
+        }
+    }
+    RECOVERABLE.with(|depth| depth.set(depth.get() + 1));
+    let _scope = Scope;
+    f()
+}
+
+/// Write a report for an error the app recovered from
+///
+/// Returns the bundle path, or None if `install` was never called.
+pub fn report_non_fatal(error: &dyn Display) -> std::io::Result<Option<PathBuf>> {
+    write_report("non-fatal error", &error.to_string(), None)
+}
+
+/// Write a report for a native fault (called by the exception filter)
+fn report_fault(message: &str) {
+    match write_report("native fault", message, None) {
+        Ok(Some(path)) => eprintln!("Crash report written to {}", path.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to write crash report: {}", e),
+    }
+}
+
+/// Keep context names safe to use as file names
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn write_report(kind: &str, message: &str, location: Option<&str>) -> std::io::Result<Option<PathBuf>> {
+    let Some(options) = OPTIONS.get() else { return Ok(None) };
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let thread = std::thread::current();
+    let context = CONTEXT.try_lock_for(LOCK_TIMEOUT).map(|context| context.clone());
+
+    let mut summary = format!(
+        "{}\n\nkind: {}\nmessage: {}\nlocation: {}\nthread: {}\ntime: {} (unix seconds)\nversion: {}\n",
+        options.app_name,
+        kind,
+        message,
+        location.unwrap_or("unknown"),
+        thread.name().unwrap_or("<unnamed>"),
+        timestamp.as_secs(),
+        env!("CARGO_PKG_VERSION"),
+    );
+    match &context {
+        Some(context) if !context.is_empty() => {
+            summary.push_str("\nincluded context:\n");
+            for name in context.keys() {
+                summary.push_str(&format!("  {}.txt\n", file_name(name)));
+            }
+        }
+        Some(_) => {}
+        None => summary.push_str("\ncontext left out: its lock was held\n"),
+    }
+    summary.push_str(&format!("\nbacktrace:\n{}\n", std::backtrace::Backtrace::force_capture()));
+
+    let mut entries = vec![("summary.txt".to_string(), summary.into_bytes())];
+    if options.log_lines > 0 {
+        let log = match logging::try_records(&LogFilter::default()) {
+            Some(records) => {
+                let start = records.len().saturating_sub(options.log_lines);
+                records[start..]
+                    .iter()
+                    .map(|r| format!("[{:<5} {}] {}\n", r.level, r.target, r.message))
+                    .collect()
+            }
+            None => "(log left out: the log buffer was locked)\n".to_string(),
+        };
+        entries.push(("log.txt".to_string(), log.into_bytes()));
+    }
+    for (name, value) in context.unwrap_or_default() {
+        entries.push((format!("{}.txt", file_name(&name)), value.into_bytes()));
+    }
+
+    write_bundle(&options.directory, timestamp.as_millis(), &entries).map(Some)
+}
+
+fn write_bundle(directory: &Path, millis: u128, entries: &[(String, Vec<u8>)]) -> std::io::Result<PathBuf> {
+    static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+    std::fs::create_dir_all(directory)?;
+    // The sequence number keeps reports from the same millisecond apart
+    let path = directory.join(format!("crash-{}-{}.zip", millis, SEQUENCE.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&path, zip::write(entries))?;
+    Ok(path)
+}
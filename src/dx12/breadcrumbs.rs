@@ -0,0 +1,253 @@
+//! GPU breadcrumbs for diagnosing device hangs
+//!
+//! The command list writes incrementing markers into a small CPU-readable
+//! buffer when a pass starts (top of pipe) and when it completes (bottom of
+//! pipe). After a device-removed error the buffer still holds the last values
+//! the GPU reached, which tells which pass hung.
+//!
+//! DRED (Device Removed Extended Data) auto-breadcrumbs and page-fault
+//! reporting complement this with per-command-list history from the runtime.
+//! They must be enabled before the device is created, see `enable_dred`.
+//!
+//! Manual hang test: record a pass with `begin`/`end`, dispatch a shader with
+//! an infinite loop inside it, and the `Dx12Error::DeviceRemoved` report will
+//! show that pass as started but not completed.
+
+use super::{CommandList, Device, Dx12Error, Dx12Result};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU32, Ordering};
+use windows::core::Interface;
+use windows::Win32::Graphics::Direct3D12::*;
+use windows::Win32::Graphics::Dxgi::Common::*;
+
+/// Number of recent marker labels kept for the report
+const LABEL_HISTORY: usize = 64;
+
+/// Slot holding the last started marker
+const SLOT_STARTED: u64 = 0;
+/// Slot holding the last completed marker
+const SLOT_COMPLETED: u64 = 4;
+
+/// Breadcrumb marker buffer
+pub struct Breadcrumbs {
+    resource: ID3D12Resource,
+    gpu_address: u64,
+    next_marker: AtomicU32,
+    labels: Mutex<VecDeque<(u32, String)>>,
+}
+
+impl Breadcrumbs {
+    /// Create the marker buffer
+    pub fn new(device: &Device) -> Dx12Result<Self> {
+        unsafe {
+            // Write-back CPU pages so the values can be read after the GPU is lost
+            let heap_props = D3D12_HEAP_PROPERTIES {
+                Type: D3D12_HEAP_TYPE_CUSTOM,
+                CPUPageProperty: D3D12_CPU_PAGE_PROPERTY_WRITE_BACK,
+                MemoryPoolPreference: D3D12_MEMORY_POOL_L0,
+                CreationNodeMask: 1,
+                VisibleNodeMask: 1,
+            };
+
+            let resource_desc = D3D12_RESOURCE_DESC {
+                Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
+                Alignment: 0,
+                Width: 256,
+                Height: 1,
+                DepthOrArraySize: 1,
+                MipLevels: 1,
+                Format: DXGI_FORMAT_UNKNOWN,
+                SampleDesc: DXGI_SAMPLE_DESC { Count: 1, Quality: 0 },
+                Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+                Flags: D3D12_RESOURCE_FLAG_NONE,
+            };
+
+            let mut resource: Option<ID3D12Resource> = None;
+            device.raw().CreateCommittedResource(
+                &heap_props,
+                D3D12_HEAP_FLAG_NONE,
+                &resource_desc,
+                D3D12_RESOURCE_STATE_COMMON,
+                None,
+                &mut resource,
+            )?;
+
+            let resource = resource.ok_or_else(|| {
+                Dx12Error::BufferCreation("Failed to create breadcrumb buffer".to_string())
+            })?;
+            let gpu_address = resource.GetGPUVirtualAddress();
+
+            Ok(Self {
+                resource,
+                gpu_address,
+                next_marker: AtomicU32::new(1),
+                labels: Mutex::new(VecDeque::with_capacity(LABEL_HISTORY)),
+            })
+        }
+    }
+
+    /// Record the start of a pass and return its marker
+    pub fn begin(&self, cmd_list: &CommandList, label: &str) -> u32 {
+        let marker = self.next_marker.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut labels = self.labels.lock();
+            if labels.len() == LABEL_HISTORY {
+                labels.pop_front();
+            }
+            labels.push_back((marker, label.to_string()));
+        }
+        self.write(cmd_list, SLOT_STARTED, marker, D3D12_WRITEBUFFERIMMEDIATE_MODE_MARKER_IN);
+        marker
+    }
+
+    /// Record the completion of a pass started with `begin`
+    pub fn end(&self, cmd_list: &CommandList, marker: u32) {
+        self.write(cmd_list, SLOT_COMPLETED, marker, D3D12_WRITEBUFFERIMMEDIATE_MODE_MARKER_OUT);
+    }
+
+    fn write(&self, cmd_list: &CommandList, offset: u64, value: u32, mode: D3D12_WRITEBUFFERIMMEDIATE_MODE) {
+        // WriteBufferImmediate needs ID3D12GraphicsCommandList2 (Windows 10 1709+)
+        let Ok(list) = cmd_list.raw().cast::<ID3D12GraphicsCommandList2>() else {
+            return;
+        };
+        let param = D3D12_WRITEBUFFERIMMEDIATE_PARAMETER {
+            Dest: self.gpu_address + offset,
+            Value: value,
+        };
+        unsafe {
+            list.WriteBufferImmediate(1, &param, Some(&mode));
+        }
+    }
+
+    /// Read the last started and last completed markers
+    pub fn read(&self) -> Dx12Result<(u32, u32)> {
+        unsafe {
+            let mut data: *mut std::ffi::c_void = std::ptr::null_mut();
+            self.resource.Map(0, None, Some(&mut data))?;
+            let words = data as *const u32;
+            let started = std::ptr::read_volatile(words.add((SLOT_STARTED / 4) as usize));
+            let completed = std::ptr::read_volatile(words.add((SLOT_COMPLETED / 4) as usize));
+            self.resource.Unmap(0, None);
+            Ok((started, completed))
+        }
+    }
+
+    /// Format the marker state for a crash report
+    pub fn report(&self) -> String {
+        let (started, completed) = match self.read() {
+            Ok(values) => values,
+            Err(e) => return format!("breadcrumbs unreadable: {}", e),
+        };
+
+        let labels = self.labels.lock();
+        let label = |marker: u32| {
+            labels
+                .iter()
+                .find(|(m, _)| *m == marker)
+                .map(|(_, l)| l.as_str())
+                .unwrap_or("?")
+        };
+
+        let mut report = format!(
+            "last started #{} \"{}\", last completed #{} \"{}\"",
+            started,
+            label(started),
+            completed,
+            label(completed),
+        );
+        if started != completed {
+            let _ = write!(report, " (hang likely in \"{}\")", label(started));
+        }
+        report
+    }
+}
+
+impl std::fmt::Debug for Breadcrumbs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Breadcrumbs")
+            .field("next_marker", &self.next_marker.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Enable DRED auto-breadcrumbs and page-fault reporting
+///
+/// Must be called before the device is created. Returns false if the
+/// runtime doesn't support DRED.
+pub fn enable_dred() -> bool {
+    unsafe {
+        let mut settings: Option<ID3D12DeviceRemovedExtendedDataSettings> = None;
+        if D3D12GetDebugInterface(&mut settings).is_err() {
+            return false;
+        }
+        match settings {
+            Some(settings) => {
+                settings.SetAutoBreadcrumbsEnablement(D3D12_DRED_ENABLEMENT_FORCED_ON);
+                settings.SetPageFaultEnablement(D3D12_DRED_ENABLEMENT_FORCED_ON);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Format the DRED output of a removed device
+///
+/// Returns None if DRED was not enabled or has nothing to report.
+pub fn dred_report(device: &Device) -> Option<String> {
+    unsafe {
+        let dred = device.raw().cast::<ID3D12DeviceRemovedExtendedData>().ok()?;
+        let mut report = String::new();
+
+        if let Ok(output) = dred.GetAutoBreadcrumbsOutput() {
+            let mut node = output.pHeadAutoBreadcrumbNode;
+            while let Some(n) = node.as_ref() {
+                let completed = n.pLastBreadcrumbValue.as_ref().copied().unwrap_or(0);
+                if completed < n.BreadcrumbCount {
+                    let name = c_str(n.pCommandListDebugNameA).unwrap_or("<unnamed>");
+                    let op = *n.pCommandHistory.add(completed as usize);
+                    let _ = writeln!(
+                        report,
+                        "command list {}: {}/{} ops completed, next op {:?}",
+                        name, completed, n.BreadcrumbCount, op,
+                    );
+                }
+                node = n.pNext;
+            }
+        }
+
+        if let Ok(output) = dred.GetPageFaultAllocationOutput() {
+            if output.PageFaultVA != 0 {
+                let _ = writeln!(report, "page fault at 0x{:016x}", output.PageFaultVA);
+                let mut node = output.pHeadExistingAllocationNode;
+                while let Some(n) = node.as_ref() {
+                    let name = c_str(n.ObjectNameA).unwrap_or("<unnamed>");
+                    let _ = writeln!(report, "  existing allocation: {} ({:?})", name, n.AllocationType);
+                    node = n.pNext;
+                }
+                let mut node = output.pHeadRecentFreedAllocationNode;
+                while let Some(n) = node.as_ref() {
+                    let name = c_str(n.ObjectNameA).unwrap_or("<unnamed>");
+                    let _ = writeln!(report, "  recently freed: {} ({:?})", name, n.AllocationType);
+                    node = n.pNext;
+                }
+            }
+        }
+
+        if report.is_empty() {
+            None
+        } else {
+            Some(report.trim_end().to_string())
+        }
+    }
+}
+
+/// Read a NUL-terminated ANSI string from DRED output
+unsafe fn c_str<'a>(ptr: *const u8) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    std::ffi::CStr::from_ptr(ptr as *const std::ffi::c_char).to_str().ok()
+}
@@ -0,0 +1,167 @@
+//! Programmatic GPU capture (RenderDoc / PIX)
+//!
+//! RenderDoc is used when it has been injected into the process (launched
+//! from the RenderDoc UI). PIX is used when WinPixGpuCapturer.dll can be
+//! loaded; that has to happen before the D3D12 device is created.
+
+use windows::core::{s, PCSTR};
+use windows::Win32::Foundation::HMODULE;
+use windows::Win32::Graphics::Dxgi::{DXGIGetDebugInterface1, IDXGraphicsAnalysis};
+use windows::Win32::System::LibraryLoader::{GetModuleHandleA, GetProcAddress, LoadLibraryA};
+
+/// RenderDoc in-application API version 1.1.2
+const RENDERDOC_API_VERSION: i32 = 10102;
+
+type RenderDocGetApi = unsafe extern "C" fn(version: i32, out: *mut *mut RenderDocApi) -> i32;
+type DeviceWindowFn = unsafe extern "C" fn(device: *mut std::ffi::c_void, window: *mut std::ffi::c_void);
+type DeviceWindowResultFn = unsafe extern "C" fn(device: *mut std::ffi::c_void, window: *mut std::ffi::c_void) -> u32;
+
+/// RENDERDOC_API_1_1_2 function table (only the entries used here are typed)
+#[repr(C)]
+struct RenderDocApi {
+    _unused: [usize; 19],
+    start_frame_capture: Option<DeviceWindowFn>,
+    _is_frame_capturing: usize,
+    end_frame_capture: Option<DeviceWindowResultFn>,
+}
+
+/// Which capture tool is attached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureTool {
+    RenderDoc,
+    Pix,
+}
+
+enum Backend {
+    RenderDoc(&'static RenderDocApi),
+    Pix(IDXGraphicsAnalysis),
+}
+
+/// Handle to an attached graphics debugger
+pub struct GpuCapture {
+    backend: Backend,
+    capturing: bool,
+}
+
+impl GpuCapture {
+    /// Try to load the PIX GPU capturer
+    ///
+    /// Call before creating the device. Returns false if it isn't installed.
+    pub fn load_pix_capturer() -> bool {
+        unsafe {
+            if GetModuleHandleA(s!("WinPixGpuCapturer.dll")).is_ok() {
+                return true;
+            }
+            if LoadLibraryA(s!("WinPixGpuCapturer.dll")).is_ok() {
+                return true;
+            }
+            match newest_pix_capturer() {
+                Some(path) => {
+                    let path = format!("{}\0", path);
+                    LoadLibraryA(PCSTR(path.as_ptr())).is_ok()
+                }
+                None => false,
+            }
+        }
+    }
+
+    /// Detect an attached capture tool
+    pub fn detect() -> Option<Self> {
+        unsafe {
+            if let Ok(module) = GetModuleHandleA(s!("renderdoc.dll")) {
+                if let Some(api) = renderdoc_api(module) {
+                    return Some(Self { backend: Backend::RenderDoc(api), capturing: false });
+                }
+            }
+
+            if let Ok(analysis) = DXGIGetDebugInterface1::<IDXGraphicsAnalysis>(0) {
+                return Some(Self { backend: Backend::Pix(analysis), capturing: false });
+            }
+        }
+        None
+    }
+
+    /// Get the attached tool
+    pub fn tool(&self) -> CaptureTool {
+        match self.backend {
+            Backend::RenderDoc(_) => CaptureTool::RenderDoc,
+            Backend::Pix(_) => CaptureTool::Pix,
+        }
+    }
+
+    /// Check if a capture is in progress
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    /// Start capturing (all devices and windows)
+    pub fn start(&mut self) {
+        if self.capturing {
+            return;
+        }
+        unsafe {
+            match &self.backend {
+                Backend::RenderDoc(api) => {
+                    if let Some(start) = api.start_frame_capture {
+                        start(std::ptr::null_mut(), std::ptr::null_mut());
+                    }
+                }
+                Backend::Pix(analysis) => analysis.BeginCapture(),
+            }
+        }
+        self.capturing = true;
+    }
+
+    /// Stop the capture started with `start`
+    pub fn end(&mut self) {
+        if !self.capturing {
+            return;
+        }
+        unsafe {
+            match &self.backend {
+                Backend::RenderDoc(api) => {
+                    if let Some(end) = api.end_frame_capture {
+                        end(std::ptr::null_mut(), std::ptr::null_mut());
+                    }
+                }
+                Backend::Pix(analysis) => analysis.EndCapture(),
+            }
+        }
+        self.capturing = false;
+    }
+}
+
+impl std::fmt::Debug for GpuCapture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuCapture")
+            .field("tool", &self.tool())
+            .field("capturing", &self.capturing)
+            .finish()
+    }
+}
+
+/// Fetch the RenderDoc API table from the injected module
+unsafe fn renderdoc_api(module: HMODULE) -> Option<&'static RenderDocApi> {
+    let get_api = GetProcAddress(module, s!("RENDERDOC_GetAPI"))?;
+    let get_api: RenderDocGetApi = std::mem::transmute(get_api);
+
+    let mut api: *mut RenderDocApi = std::ptr::null_mut();
+    if get_api(RENDERDOC_API_VERSION, &mut api) != 1 {
+        return None;
+    }
+    // The table lives for the lifetime of the process
+    api.as_ref()
+}
+
+/// Find WinPixGpuCapturer.dll in the newest PIX install
+fn newest_pix_capturer() -> Option<String> {
+    let root = std::path::Path::new("C:\\Program Files\\Microsoft PIX");
+    let mut versions: Vec<_> = std::fs::read_dir(root)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().join("WinPixGpuCapturer.dll"))
+        .filter(|path| path.exists())
+        .collect();
+    versions.sort();
+    versions.pop().map(|path| path.to_string_lossy().into_owned())
+}
@@ -1,7 +1,9 @@
 //! Command Queue and Command List wrappers
 
-use super::{Device, Dx12Result, Fence};
+use super::{Device, Dx12Result, Fence, PipelineState, RootSignature};
+use crate::math::Rect;
 use windows::core::Interface;
+use windows::Win32::Foundation::RECT;
 use windows::Win32::Graphics::Direct3D12::*;
 
 /// Command queue wrapper
@@ -95,6 +97,11 @@ impl CommandAllocator {
         Ok(Self { allocator, list_type })
     }
 
+    /// Create an allocator for direct (graphics) command lists
+    pub fn graphics(device: &Device) -> Dx12Result<Self> {
+        Self::new(device, D3D12_COMMAND_LIST_TYPE_DIRECT)
+    }
+
     /// Get the raw allocator handle
     pub fn raw(&self) -> &ID3D12CommandAllocator {
         &self.allocator
@@ -190,6 +197,20 @@ impl CommandList {
         }
     }
 
+    /// Set the root signature for draws
+    pub fn set_root_signature(&self, root_signature: &RootSignature) {
+        unsafe {
+            self.list.SetGraphicsRootSignature(root_signature.raw());
+        }
+    }
+
+    /// Set the pipeline state for draws
+    pub fn set_pipeline_state(&self, state: &PipelineState) {
+        unsafe {
+            self.list.SetPipelineState(state.raw());
+        }
+    }
+
     /// Set render targets
     pub fn set_render_targets(
         &self,
@@ -217,6 +238,28 @@ impl CommandList {
         }
     }
 
+    /// Clear parts of a render target (the whole target if `rects` is empty)
+    pub fn clear_render_target_rects(
+        &self,
+        rtv_handle: D3D12_CPU_DESCRIPTOR_HANDLE,
+        color: [f32; 4],
+        rects: &[Rect],
+    ) {
+        let rects: Vec<RECT> = rects
+            .iter()
+            .map(|r| RECT {
+                left: r.x as i32,
+                top: r.y as i32,
+                right: (r.x + r.width) as i32,
+                bottom: (r.y + r.height) as i32,
+            })
+            .collect();
+        unsafe {
+            let rects = if rects.is_empty() { None } else { Some(rects.as_slice()) };
+            self.list.ClearRenderTargetView(rtv_handle, &color, rects);
+        }
+    }
+
     /// Clear a depth stencil
     pub fn clear_depth_stencil(
         &self,
@@ -1,6 +1,6 @@
 //! DirectX12 Device wrapper
 
-use super::{Dx12Error, Dx12Result};
+use super::{DeviceFeatures, Dx12Error, Dx12Result};
 use windows::{
     Win32::Graphics::{
         Direct3D::D3D_FEATURE_LEVEL_12_0,
@@ -14,47 +14,95 @@ pub struct Device {
     device: ID3D12Device,
     adapter: IDXGIAdapter1,
     factory: IDXGIFactory4,
+    features: DeviceFeatures,
     debug_enabled: bool,
 }
 
 impl Device {
     /// Create a new DirectX12 device
+    ///
+    /// Without the Graphics Tools optional feature installed the debug
+    /// layer is skipped with a warning; see `is_debug_enabled`.
     pub fn new(debug: bool) -> Dx12Result<Self> {
         unsafe {
-            // Enable debug layer if requested
-            if debug {
-                let mut debug_controller: Option<ID3D12Debug> = None;
-                if D3D12GetDebugInterface(&mut debug_controller).is_ok() {
-                    if let Some(debug) = debug_controller {
-                        debug.EnableDebugLayer();
-                    }
-                }
-            }
-
-            // Create DXGI factory
-            let factory_flags = if debug { DXGI_CREATE_FACTORY_DEBUG.0 } else { 0 };
-            let factory: IDXGIFactory4 = CreateDXGIFactory2(DXGI_CREATE_FACTORY_FLAGS(factory_flags))?;
+            let (factory, debug) = Self::create_factory(debug)?;
 
             // Find a suitable adapter
             let adapter = Self::find_adapter(&factory)?;
 
-            // Create the device
-            let mut device: Option<ID3D12Device> = None;
-            D3D12CreateDevice(&adapter, D3D_FEATURE_LEVEL_12_0, &mut device)?;
+            Self::from_adapter(factory, adapter, debug)
+        }
+    }
 
-            let device = device.ok_or_else(|| {
-                Dx12Error::DeviceCreation("Failed to create D3D12 device".to_string())
+    /// Create a device on the WARP software adapter
+    ///
+    /// WARP is available on every Windows 10+ machine, which makes it the
+    /// adapter of choice for headless tools and GPU-less test runs.
+    pub fn new_warp(debug: bool) -> Dx12Result<Self> {
+        unsafe {
+            let (factory, debug) = Self::create_factory(debug)?;
+            let adapter: IDXGIAdapter1 = factory.EnumWarpAdapter().map_err(|e| {
+                Dx12Error::DeviceCreation(format!("WARP adapter unavailable: {}", e))
             })?;
 
-            Ok(Self {
-                device,
-                adapter,
-                factory,
-                debug_enabled: debug,
-            })
+            Self::from_adapter(factory, adapter, debug)
         }
     }
 
+    /// Enable the debug layer (if requested and installed) and create the
+    /// DXGI factory
+    ///
+    /// Returns the factory and whether the D3D12 debug layer is on. The
+    /// layer stays on even if only the non-debug DXGI factory could be
+    /// created; DXGI validation is what gets lost then.
+    unsafe fn create_factory(debug: bool) -> Dx12Result<(IDXGIFactory4, bool)> {
+        let mut layer_enabled = false;
+        if debug {
+            let mut debug_controller: Option<ID3D12Debug> = None;
+            match D3D12GetDebugInterface(&mut debug_controller) {
+                Ok(()) => {
+                    if let Some(controller) = debug_controller {
+                        controller.EnableDebugLayer();
+                        layer_enabled = true;
+                    }
+                    // The debug factory also needs the SDK layers
+                    match CreateDXGIFactory2(DXGI_CREATE_FACTORY_DEBUG) {
+                        Ok(factory) => return Ok((factory, layer_enabled)),
+                        Err(e) => log::warn!("DXGI debug layer unavailable ({}); continuing without it", e),
+                    }
+                }
+                Err(e) => log::warn!("D3D12 debug layer unavailable ({}); continuing without it", e),
+            }
+        }
+
+        let factory: IDXGIFactory4 = CreateDXGIFactory2(DXGI_CREATE_FACTORY_FLAGS(0))?;
+        Ok((factory, layer_enabled))
+    }
+
+    /// Create the D3D12 device on a specific adapter
+    unsafe fn from_adapter(
+        factory: IDXGIFactory4,
+        adapter: IDXGIAdapter1,
+        debug: bool,
+    ) -> Dx12Result<Self> {
+        let mut device: Option<ID3D12Device> = None;
+        D3D12CreateDevice(&adapter, D3D_FEATURE_LEVEL_12_0, &mut device)?;
+
+        let device = device.ok_or_else(|| {
+            Dx12Error::DeviceCreation("Failed to create D3D12 device".to_string())
+        })?;
+
+        let features = DeviceFeatures::query(&device);
+
+        Ok(Self {
+            device,
+            adapter,
+            factory,
+            features,
+            debug_enabled: debug,
+        })
+    }
+
     /// Find a suitable GPU adapter
     unsafe fn find_adapter(factory: &IDXGIFactory4) -> Dx12Result<IDXGIAdapter1> {
         let mut adapter_index = 0;
@@ -107,7 +155,17 @@ impl Device {
         &self.adapter
     }
 
-    /// Check if debug mode is enabled
+    /// Get the feature support queried at creation
+    pub fn features(&self) -> &DeviceFeatures {
+        &self.features
+    }
+
+    /// Get the reason the device was removed, if it was
+    pub fn removed_reason(&self) -> Option<windows::core::Error> {
+        unsafe { self.device.GetDeviceRemovedReason().err() }
+    }
+
+    /// Check if the D3D12 debug layer is enabled
     pub fn is_debug_enabled(&self) -> bool {
         self.debug_enabled
     }
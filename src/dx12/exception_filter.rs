@@ -0,0 +1,82 @@
+//! Unhandled exception filter for native faults (access violations and
+//! other SEH exceptions)
+//!
+//! Panics never get here; this catches the faults that end the process
+//! without unwinding, e.g. a bad pointer from FFI or a driver. The handler
+//! runs on the faulting thread with whatever state is left, so anything it
+//! does is best effort. The previous filter runs afterwards, so Windows
+//! Error Reporting and debuggers still see the fault. `crash::install`
+//! uses this to write native fault reports.
+
+use std::sync::OnceLock;
+use windows::Win32::Foundation::{
+    EXCEPTION_ACCESS_VIOLATION, EXCEPTION_ARRAY_BOUNDS_EXCEEDED, EXCEPTION_DATATYPE_MISALIGNMENT,
+    EXCEPTION_ILLEGAL_INSTRUCTION, EXCEPTION_INT_DIVIDE_BY_ZERO, EXCEPTION_IN_PAGE_ERROR,
+    EXCEPTION_PRIV_INSTRUCTION, EXCEPTION_STACK_OVERFLOW,
+};
+use windows::Win32::System::Diagnostics::Debug::{
+    SetUnhandledExceptionFilter, EXCEPTION_CONTINUE_SEARCH, EXCEPTION_POINTERS, EXCEPTION_RECORD,
+    LPTOP_LEVEL_EXCEPTION_FILTER,
+};
+
+/// The filter that was installed before ours
+static PREVIOUS: OnceLock<LPTOP_LEVEL_EXCEPTION_FILTER> = OnceLock::new();
+
+/// Called with a description of each fault
+static HANDLER: OnceLock<fn(&str)> = OnceLock::new();
+
+/// Install the unhandled exception filter
+///
+/// `handler` gets a one-line description of the fault. Only the first call
+/// takes effect.
+pub(crate) fn install_exception_filter(handler: fn(&str)) {
+    if HANDLER.set(handler).is_err() {
+        return;
+    }
+    // SAFETY: `on_fault` matches the filter signature and lives forever
+    let previous = unsafe { SetUnhandledExceptionFilter(Some(on_fault)) };
+    let _ = PREVIOUS.set(previous);
+}
+
+unsafe extern "system" fn on_fault(info: *const EXCEPTION_POINTERS) -> i32 {
+    // SAFETY: Windows passes valid pointers (or null) for the faulting thread
+    let record = unsafe { info.as_ref().and_then(|info| info.ExceptionRecord.as_ref()) };
+    // Writing a report needs stack that an overflowed thread doesn't have
+    if let Some(record) = record.filter(|record| record.ExceptionCode != EXCEPTION_STACK_OVERFLOW) {
+        if let Some(handler) = HANDLER.get() {
+            handler(&describe(record));
+        }
+    }
+    match PREVIOUS.get().copied().flatten() {
+        // SAFETY: the previous filter expects the same arguments
+        Some(previous) => unsafe { previous(info) },
+        None => EXCEPTION_CONTINUE_SEARCH,
+    }
+}
+
+/// One-line description, e.g. "access violation writing 0x0 at 0x7FF6A1B2"
+fn describe(record: &EXCEPTION_RECORD) -> String {
+    let code = record.ExceptionCode;
+    let name = match code {
+        EXCEPTION_ACCESS_VIOLATION => "access violation",
+        EXCEPTION_IN_PAGE_ERROR => "in-page error",
+        EXCEPTION_ILLEGAL_INSTRUCTION => "illegal instruction",
+        EXCEPTION_PRIV_INSTRUCTION => "privileged instruction",
+        EXCEPTION_INT_DIVIDE_BY_ZERO => "integer divide by zero",
+        EXCEPTION_ARRAY_BOUNDS_EXCEEDED => "array bounds exceeded",
+        EXCEPTION_DATATYPE_MISALIGNMENT => "misaligned access",
+        _ => "unhandled exception",
+    };
+    let mut text = format!("{} (0x{:08X})", name, code.0 as u32);
+    // Access violations carry the kind of access and the address touched
+    if (code == EXCEPTION_ACCESS_VIOLATION || code == EXCEPTION_IN_PAGE_ERROR) && record.NumberParameters >= 2 {
+        let access = match record.ExceptionInformation[0] {
+            0 => "reading",
+            1 => "writing",
+            _ => "executing",
+        };
+        text.push_str(&format!(" {} 0x{:X}", access, record.ExceptionInformation[1]));
+    }
+    text.push_str(&format!(" at 0x{:X}", record.ExceptionAddress as usize));
+    text
+}
@@ -0,0 +1,191 @@
+//! Device feature support queries
+//!
+//! Queried once at device creation so callers can branch on capabilities
+//! instead of trying an operation and handling the failure.
+
+use std::fmt;
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// Shader model version (e.g. 6.6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ShaderModel {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl ShaderModel {
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    fn from_raw(raw: D3D_SHADER_MODEL) -> Self {
+        Self {
+            major: ((raw.0 >> 4) & 0xF) as u8,
+            minor: (raw.0 & 0xF) as u8,
+        }
+    }
+}
+
+impl fmt::Display for ShaderModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Optional hardware features reported by the device
+///
+/// Tiers use the numeric values of the corresponding D3D12 enums, where `0`
+/// means "not supported" (raytracing and mesh shader tier 1.0 are `10`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceFeatures {
+    /// Highest supported shader model
+    pub shader_model: ShaderModel,
+    /// Resource binding tier (1-3)
+    pub resource_binding_tier: u32,
+    /// Variable rate shading tier (0-2)
+    pub vrs_tier: u32,
+    /// Raytracing tier (0, 10 = 1.0, 11 = 1.1)
+    pub raytracing_tier: u32,
+    /// Mesh shader tier (0, 10 = 1.0)
+    pub mesh_shader_tier: u32,
+    /// Typed UAV loads for additional formats
+    pub typed_uav_load_additional_formats: bool,
+    /// Conservative rasterization tier (0-3)
+    pub conservative_raster_tier: u32,
+    /// Wave intrinsics in shaders
+    pub wave_ops: bool,
+}
+
+impl Default for DeviceFeatures {
+    fn default() -> Self {
+        Self {
+            shader_model: ShaderModel::new(5, 1),
+            resource_binding_tier: 1,
+            vrs_tier: 0,
+            raytracing_tier: 0,
+            mesh_shader_tier: 0,
+            typed_uav_load_additional_formats: false,
+            conservative_raster_tier: 0,
+            wave_ops: false,
+        }
+    }
+}
+
+impl DeviceFeatures {
+    /// Query feature support from a device
+    ///
+    /// Queries the runtime does not know about (older Windows builds) are
+    /// treated as "not supported" rather than an error.
+    pub fn query(device: &ID3D12Device) -> Self {
+        let mut features = Self::default();
+
+        if let Some(sm) = query_shader_model(device) {
+            features.shader_model = sm;
+        }
+
+        if let Some(opts) = check::<D3D12_FEATURE_DATA_D3D12_OPTIONS>(device, D3D12_FEATURE_D3D12_OPTIONS) {
+            features.resource_binding_tier = opts.ResourceBindingTier.0 as u32;
+            features.typed_uav_load_additional_formats = opts.TypedUAVLoadAdditionalFormats.as_bool();
+            features.conservative_raster_tier = opts.ConservativeRasterizationTier.0 as u32;
+        }
+
+        if let Some(opts) = check::<D3D12_FEATURE_DATA_D3D12_OPTIONS1>(device, D3D12_FEATURE_D3D12_OPTIONS1) {
+            features.wave_ops = opts.WaveOps.as_bool();
+        }
+
+        if let Some(opts) = check::<D3D12_FEATURE_DATA_D3D12_OPTIONS5>(device, D3D12_FEATURE_D3D12_OPTIONS5) {
+            features.raytracing_tier = opts.RaytracingTier.0 as u32;
+        }
+
+        if let Some(opts) = check::<D3D12_FEATURE_DATA_D3D12_OPTIONS6>(device, D3D12_FEATURE_D3D12_OPTIONS6) {
+            features.vrs_tier = opts.VariableShadingRateTier.0 as u32;
+        }
+
+        if let Some(opts) = check::<D3D12_FEATURE_DATA_D3D12_OPTIONS7>(device, D3D12_FEATURE_D3D12_OPTIONS7) {
+            features.mesh_shader_tier = opts.MeshShaderTier.0 as u32;
+        }
+
+        features
+    }
+
+    /// Check if variable rate shading is available
+    pub fn supports_vrs(&self) -> bool {
+        self.vrs_tier >= 1
+    }
+
+    /// Check if DXR raytracing is available
+    pub fn supports_raytracing(&self) -> bool {
+        self.raytracing_tier >= D3D12_RAYTRACING_TIER_1_0.0 as u32
+    }
+
+    /// Check if mesh shaders are available
+    pub fn supports_mesh_shaders(&self) -> bool {
+        self.mesh_shader_tier >= D3D12_MESH_SHADER_TIER_1.0 as u32
+    }
+}
+
+impl fmt::Display for DeviceFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SM {}, binding tier {}, VRS tier {}, raytracing tier {}, mesh shader tier {}, \
+             typed UAV loads {}, conservative raster tier {}, wave ops {}",
+            self.shader_model,
+            self.resource_binding_tier,
+            self.vrs_tier,
+            self.raytracing_tier,
+            self.mesh_shader_tier,
+            self.typed_uav_load_additional_formats,
+            self.conservative_raster_tier,
+            self.wave_ops,
+        )
+    }
+}
+
+/// Run a single CheckFeatureSupport query
+fn check<T: Default>(device: &ID3D12Device, feature: D3D12_FEATURE) -> Option<T> {
+    let mut data = T::default();
+    unsafe {
+        device
+            .CheckFeatureSupport(
+                feature,
+                &mut data as *mut T as *mut _,
+                std::mem::size_of::<T>() as u32,
+            )
+            .ok()?;
+    }
+    Some(data)
+}
+
+/// Find the highest shader model the runtime and driver agree on
+///
+/// The query fails with E_INVALIDARG if the requested model is newer than the
+/// runtime knows, so step down until it succeeds.
+fn query_shader_model(device: &ID3D12Device) -> Option<ShaderModel> {
+    const CANDIDATES: [D3D_SHADER_MODEL; 10] = [
+        D3D_SHADER_MODEL_6_9,
+        D3D_SHADER_MODEL_6_8,
+        D3D_SHADER_MODEL_6_7,
+        D3D_SHADER_MODEL_6_6,
+        D3D_SHADER_MODEL_6_5,
+        D3D_SHADER_MODEL_6_4,
+        D3D_SHADER_MODEL_6_3,
+        D3D_SHADER_MODEL_6_2,
+        D3D_SHADER_MODEL_6_1,
+        D3D_SHADER_MODEL_6_0,
+    ];
+
+    CANDIDATES.iter().find_map(|&highest| {
+        let mut data = D3D12_FEATURE_DATA_SHADER_MODEL { HighestShaderModel: highest };
+        unsafe {
+            device
+                .CheckFeatureSupport(
+                    D3D12_FEATURE_SHADER_MODEL,
+                    &mut data as *mut _ as *mut _,
+                    std::mem::size_of::<D3D12_FEATURE_DATA_SHADER_MODEL>() as u32,
+                )
+                .ok()?;
+        }
+        Some(ShaderModel::from_raw(data.HighestShaderModel))
+    })
+}
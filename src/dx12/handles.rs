@@ -0,0 +1,112 @@
+//! Opaque handles for the levels above dx12
+//!
+//! Level B and C name windows, GPU resources and resource states through
+//! these instead of windows-rs types. Level A code converts to and from the
+//! raw types with `From` and `raw`.
+
+use super::{Buffer, Texture};
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// A native window to present to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowHandle(HWND);
+
+impl WindowHandle {
+    /// Get the handle of a window (None if it isn't a Win32 window)
+    pub fn from_window(window: &impl HasWindowHandle) -> Option<Self> {
+        match window.window_handle().ok()?.as_raw() {
+            RawWindowHandle::Win32(handle) => Some(Self(HWND(handle.hwnd.get() as *mut std::ffi::c_void))),
+            _ => None,
+        }
+    }
+
+    /// Get the raw window handle
+    pub fn raw(&self) -> HWND {
+        self.0
+    }
+}
+
+impl From<HWND> for WindowHandle {
+    fn from(hwnd: HWND) -> Self {
+        Self(hwnd)
+    }
+}
+
+/// A GPU resource whose state can be tracked
+#[derive(Clone, PartialEq, Eq)]
+pub struct GpuResource(ID3D12Resource);
+
+impl GpuResource {
+    /// Get the raw resource
+    pub fn raw(&self) -> &ID3D12Resource {
+        &self.0
+    }
+}
+
+impl From<ID3D12Resource> for GpuResource {
+    fn from(resource: ID3D12Resource) -> Self {
+        Self(resource)
+    }
+}
+
+impl From<&Texture> for GpuResource {
+    fn from(texture: &Texture) -> Self {
+        Self(texture.raw().clone())
+    }
+}
+
+impl From<&Buffer> for GpuResource {
+    fn from(buffer: &Buffer) -> Self {
+        Self(buffer.raw().clone())
+    }
+}
+
+impl std::fmt::Debug for GpuResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use windows::core::Interface;
+        write!(f, "GpuResource({:p})", self.0.as_raw())
+    }
+}
+
+/// How a resource is being used, which decides the barriers around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceState {
+    Common,
+    Present,
+    RenderTarget,
+    DepthWrite,
+    DepthRead,
+    /// Read by any shader stage
+    ShaderResource,
+    UnorderedAccess,
+    CopySource,
+    CopyDest,
+    VertexAndConstantBuffer,
+    IndexBuffer,
+    /// The state upload heap resources stay in
+    GenericRead,
+}
+
+impl ResourceState {
+    /// Get the D3D12 state
+    pub fn raw(&self) -> D3D12_RESOURCE_STATES {
+        match self {
+            ResourceState::Common => D3D12_RESOURCE_STATE_COMMON,
+            ResourceState::Present => D3D12_RESOURCE_STATE_PRESENT,
+            ResourceState::RenderTarget => D3D12_RESOURCE_STATE_RENDER_TARGET,
+            ResourceState::DepthWrite => D3D12_RESOURCE_STATE_DEPTH_WRITE,
+            ResourceState::DepthRead => D3D12_RESOURCE_STATE_DEPTH_READ,
+            ResourceState::ShaderResource => {
+                D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE | D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE
+            }
+            ResourceState::UnorderedAccess => D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+            ResourceState::CopySource => D3D12_RESOURCE_STATE_COPY_SOURCE,
+            ResourceState::CopyDest => D3D12_RESOURCE_STATE_COPY_DEST,
+            ResourceState::VertexAndConstantBuffer => D3D12_RESOURCE_STATE_VERTEX_AND_CONSTANT_BUFFER,
+            ResourceState::IndexBuffer => D3D12_RESOURCE_STATE_INDEX_BUFFER,
+            ResourceState::GenericRead => D3D12_RESOURCE_STATE_GENERIC_READ,
+        }
+    }
+}
@@ -11,18 +11,30 @@ mod texture;
 mod descriptor_heap;
 mod fence;
 mod shader;
+mod features;
+pub mod breadcrumbs;
+mod state_tracker;
+mod capture;
+mod handles;
+mod exception_filter;
 pub mod gpu_info;
 
 pub use device::Device;
 pub use command_queue::{CommandQueue, CommandList, CommandAllocator};
-pub use swap_chain::{SwapChain, SwapChainConfig};
+pub use swap_chain::{SwapChain, SwapChainConfig, SwapChainFormat};
 pub use gpu_info::{GpuDetector, GpuInfo, GpuVendor, detect_gpu};
-pub use pipeline::{Pipeline, PipelineState, RootSignature};
+pub use pipeline::{DepthMode, Pipeline, PipelineOptions, PipelineState, RootSignature};
 pub use buffer::{Buffer, BufferDesc, BufferUsage, VertexBuffer, IndexBuffer, ConstantBuffer};
 pub use texture::{Texture, TextureDesc, RenderTarget, DepthStencil};
 pub use descriptor_heap::{DescriptorHeap, DescriptorHandle};
 pub use fence::Fence;
-pub use shader::{Shader, ShaderType, ShaderCompiler};
+pub use shader::{builtin, Shader, ShaderType, ShaderCompiler};
+pub use features::{DeviceFeatures, ShaderModel};
+pub use breadcrumbs::Breadcrumbs;
+pub use state_tracker::{BarrierRecorder, ResourceStateTracker, StateChanges};
+pub use capture::{CaptureTool, GpuCapture};
+pub use handles::{GpuResource, ResourceState, WindowHandle};
+pub(crate) use exception_filter::install_exception_filter;
 
 use thiserror::Error;
 
@@ -45,8 +57,38 @@ pub enum Dx12Error {
     ShaderCompilation(String),
     #[error("Resource not found: {0}")]
     ResourceNotFound(String),
+    #[error("A frame is already in progress; call end_frame first")]
+    FrameInProgress,
+    #[error("Frame is stale or belongs to another graphics instance")]
+    StaleFrame,
+    #[error("Graphics is suspended; call resume first")]
+    Suspended,
+    #[error("Invalid swap chain configuration: {0}")]
+    InvalidSwapChainConfig(String),
+    #[error("Swap chain has no back buffers: {0}")]
+    SwapChainLost(String),
+    #[error("Render backend error: {0}")]
+    Backend(String),
+    #[error("GPU device removed: {0}")]
+    DeviceRemoved(String),
     #[error("Windows API error: {0}")]
     WindowsApi(#[from] windows::core::Error),
 }
 
 pub type Dx12Result<T> = Result<T, Dx12Error>;
+
+/// Create a device for tests and headless tools
+///
+/// Uses the WARP software adapter so it works on machines without a GPU.
+/// Debug builds ask for the debug layer and fall back to running without
+/// it when it isn't installed. Returns `None` (and logs why) when even
+/// WARP is unavailable, so callers can skip instead of failing.
+pub fn test_device() -> Option<Device> {
+    match Device::new_warp(cfg!(debug_assertions)) {
+        Ok(device) => Some(device),
+        Err(e) => {
+            log::warn!("Skipping: no WARP device available ({})", e);
+            None
+        }
+    }
+}
@@ -65,6 +65,49 @@ impl PipelineState {
     }
 }
 
+/// Depth test used by a pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DepthMode {
+    /// No depth buffer
+    #[default]
+    Disabled,
+    /// Near is 0, far is 1; closer fragments have smaller depth
+    Standard,
+    /// Near is 1, far is 0; far more precision at distance with D32_FLOAT
+    Reversed,
+}
+
+impl DepthMode {
+    /// Value to clear the depth buffer to (the far plane)
+    pub fn clear_value(&self) -> f32 {
+        match self {
+            DepthMode::Reversed => 0.0,
+            DepthMode::Standard | DepthMode::Disabled => 1.0,
+        }
+    }
+
+    fn comparison(&self) -> D3D12_COMPARISON_FUNC {
+        match self {
+            DepthMode::Reversed => D3D12_COMPARISON_FUNC_GREATER,
+            DepthMode::Standard | DepthMode::Disabled => D3D12_COMPARISON_FUNC_LESS,
+        }
+    }
+}
+
+/// Fixed-function state that varies between pipeline variants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PipelineOptions {
+    /// Blend the output over the target by its alpha
+    pub alpha_blend: bool,
+    /// Turn output alpha into sample coverage (smooths cutout edges when
+    /// the target is multisampled)
+    pub alpha_to_coverage: bool,
+    /// Depth test against a D32_FLOAT depth buffer
+    pub depth: DepthMode,
+    /// Format of the render target view drawn to (None = R8G8B8A8_UNORM)
+    pub render_target_format: Option<DXGI_FORMAT>,
+}
+
 /// Graphics pipeline builder
 pub struct Pipeline {
     root_signature: RootSignature,
@@ -98,6 +141,25 @@ impl Pipeline {
         vertex_shader: &[u8],
         pixel_shader: &[u8],
         input_layout: &[D3D12_INPUT_ELEMENT_DESC],
+    ) -> Dx12Result<PipelineState> {
+        Self::create_graphics_pipeline_with(
+            device,
+            root_signature,
+            vertex_shader,
+            pixel_shader,
+            input_layout,
+            PipelineOptions::default(),
+        )
+    }
+
+    /// Create a graphics pipeline with non-default fixed-function state
+    pub fn create_graphics_pipeline_with(
+        device: &Device,
+        root_signature: &RootSignature,
+        vertex_shader: &[u8],
+        pixel_shader: &[u8],
+        input_layout: &[D3D12_INPUT_ELEMENT_DESC],
+        options: PipelineOptions,
     ) -> Dx12Result<PipelineState> {
         unsafe {
             let desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
@@ -111,14 +173,18 @@ impl Pipeline {
                     BytecodeLength: pixel_shader.len(),
                 },
                 BlendState: D3D12_BLEND_DESC {
-                    AlphaToCoverageEnable: false.into(),
+                    AlphaToCoverageEnable: options.alpha_to_coverage.into(),
                     IndependentBlendEnable: false.into(),
                     RenderTarget: [
                         D3D12_RENDER_TARGET_BLEND_DESC {
-                            BlendEnable: false.into(),
+                            BlendEnable: options.alpha_blend.into(),
                             LogicOpEnable: false.into(),
-                            SrcBlend: D3D12_BLEND_ONE,
-                            DestBlend: D3D12_BLEND_ZERO,
+                            SrcBlend: if options.alpha_blend { D3D12_BLEND_SRC_ALPHA } else { D3D12_BLEND_ONE },
+                            DestBlend: if options.alpha_blend {
+                                D3D12_BLEND_INV_SRC_ALPHA
+                            } else {
+                                D3D12_BLEND_ZERO
+                            },
                             BlendOp: D3D12_BLEND_OP_ADD,
                             SrcBlendAlpha: D3D12_BLEND_ONE,
                             DestBlendAlpha: D3D12_BLEND_ZERO,
@@ -150,9 +216,9 @@ impl Pipeline {
                     ConservativeRaster: D3D12_CONSERVATIVE_RASTERIZATION_MODE_OFF,
                 },
                 DepthStencilState: D3D12_DEPTH_STENCIL_DESC {
-                    DepthEnable: false.into(),
+                    DepthEnable: (options.depth != DepthMode::Disabled).into(),
                     DepthWriteMask: D3D12_DEPTH_WRITE_MASK_ALL,
-                    DepthFunc: D3D12_COMPARISON_FUNC_LESS,
+                    DepthFunc: options.depth.comparison(),
                     StencilEnable: false.into(),
                     StencilReadMask: 0xFF,
                     StencilWriteMask: 0xFF,
@@ -166,7 +232,7 @@ impl Pipeline {
                 PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
                 NumRenderTargets: 1,
                 RTVFormats: [
-                    DXGI_FORMAT_R8G8B8A8_UNORM,
+                    options.render_target_format.unwrap_or(DXGI_FORMAT_R8G8B8A8_UNORM),
                     DXGI_FORMAT_UNKNOWN,
                     DXGI_FORMAT_UNKNOWN,
                     DXGI_FORMAT_UNKNOWN,
@@ -175,7 +241,7 @@ impl Pipeline {
                     DXGI_FORMAT_UNKNOWN,
                     DXGI_FORMAT_UNKNOWN,
                 ],
-                DSVFormat: DXGI_FORMAT_UNKNOWN,
+                DSVFormat: if options.depth == DepthMode::Disabled { DXGI_FORMAT_UNKNOWN } else { DXGI_FORMAT_D32_FLOAT },
                 SampleDesc: DXGI_SAMPLE_DESC {
                     Count: 1,
                     Quality: 0,
@@ -1,6 +1,13 @@
 //! Shader compilation and management
 
 use super::{Dx12Error, Dx12Result};
+use std::ffi::CString;
+use windows::core::PCSTR;
+use windows::Win32::Graphics::Direct3D::{Fxc::*, ID3DBlob};
+
+fn blob_bytes(blob: &ID3DBlob) -> Vec<u8> {
+    unsafe { std::slice::from_raw_parts(blob.GetBufferPointer() as *const u8, blob.GetBufferSize()).to_vec() }
+}
 
 /// Shader types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -53,7 +60,7 @@ impl Shader {
     }
 }
 
-/// Shader compiler (placeholder - actual compilation requires D3DCompiler)
+/// HLSL shader compiler (FXC, shader model 5.1)
 pub struct ShaderCompiler;
 
 impl ShaderCompiler {
@@ -63,20 +70,52 @@ impl ShaderCompiler {
     }
 
     /// Compile HLSL source code
-    /// Note: This is a placeholder. Real implementation would use D3DCompiler.
+    ///
+    /// Compile errors are returned as `Dx12Error::ShaderCompilation` with
+    /// the compiler's messages (file, line and column included).
     pub fn compile(
         &self,
-        _source: &str,
-        _entry_point: &str,
+        source: &str,
+        entry_point: &str,
         shader_type: ShaderType,
     ) -> Dx12Result<Shader> {
-        // Placeholder - in a real implementation, this would:
-        // 1. Call D3DCompile or use dxc (DirectX Shader Compiler)
-        // 2. Return the compiled bytecode
-        
-        Err(Dx12Error::ShaderCompilation(
-            "Runtime shader compilation not implemented. Use pre-compiled shaders.".to_string(),
-        ))
+        let entry = CString::new(entry_point)
+            .map_err(|e| Dx12Error::ShaderCompilation(format!("invalid entry point: {}", e)))?;
+        let target = CString::new(shader_type.target()).expect("targets have no NUL");
+
+        let mut code: Option<ID3DBlob> = None;
+        let mut errors: Option<ID3DBlob> = None;
+        let result = unsafe {
+            D3DCompile(
+                source.as_ptr() as *const _,
+                source.len(),
+                PCSTR(c"shader.hlsl".as_ptr() as *const u8),
+                None,
+                None,
+                PCSTR(entry.as_ptr() as *const u8),
+                PCSTR(target.as_ptr() as *const u8),
+                D3DCOMPILE_OPTIMIZATION_LEVEL3,
+                0,
+                &mut code,
+                Some(&mut errors),
+            )
+        };
+
+        let messages = errors
+            .map(|blob| String::from_utf8_lossy(&blob_bytes(&blob)).trim_end().to_string())
+            .filter(|m| !m.is_empty());
+        match (result, code) {
+            (Ok(()), Some(code)) => {
+                if let Some(warnings) = messages {
+                    log::warn!("Shader '{}' compiled with warnings:\n{}", entry_point, warnings);
+                }
+                Ok(Shader::from_bytecode(blob_bytes(&code), shader_type))
+            }
+            (result, _) => Err(Dx12Error::ShaderCompilation(messages.unwrap_or_else(|| match result {
+                Err(e) => e.to_string(),
+                Ok(()) => "compiler returned no bytecode".to_string(),
+            }))),
+        }
     }
 
     /// Load a pre-compiled shader from a file
@@ -0,0 +1,328 @@
+//! Automatic resource state tracking
+//!
+//! Records the last known state of each tracked resource so callers ask for
+//! the state they need instead of writing transition barriers by hand.
+//! Transitions are queued and emitted as a single ResourceBarrier call the
+//! next time the barriers are flushed; transitions to the current state are
+//! dropped.
+//!
+//! A command list that may never be submitted records into `StateChanges`,
+//! which layers the resources it touched over the committed tracker; only a
+//! submitted list's changes are applied back.
+
+use super::CommandList;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use windows::core::Interface;
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// Something that can record resource barriers (a command list, or a fake)
+pub trait BarrierRecorder {
+    fn record_barriers(&self, barriers: &[D3D12_RESOURCE_BARRIER]);
+}
+
+impl BarrierRecorder for CommandList {
+    fn record_barriers(&self, barriers: &[D3D12_RESOURCE_BARRIER]) {
+        self.resource_barrier(barriers);
+    }
+}
+
+impl BarrierRecorder for ID3D12GraphicsCommandList {
+    fn record_barriers(&self, barriers: &[D3D12_RESOURCE_BARRIER]) {
+        unsafe {
+            self.ResourceBarrier(barriers);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct TrackedResource {
+    resource: ID3D12Resource,
+    state: D3D12_RESOURCE_STATES,
+    expected_final: Option<D3D12_RESOURCE_STATES>,
+}
+
+#[derive(Clone)]
+struct PendingTransition {
+    resource: ID3D12Resource,
+    before: D3D12_RESOURCE_STATES,
+    after: D3D12_RESOURCE_STATES,
+}
+
+/// Tracks resource states and batches the barriers between them
+///
+/// States are whole-resource (all subresources share one state).
+#[derive(Clone, Default)]
+pub struct ResourceStateTracker {
+    resources: HashMap<usize, TrackedResource>,
+    pending: Vec<PendingTransition>,
+}
+
+fn key(resource: &ID3D12Resource) -> usize {
+    resource.as_raw() as usize
+}
+
+impl ResourceStateTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a resource that is currently in `state`
+    ///
+    /// The tracker keeps a reference to the resource until `untrack` is
+    /// called, so untrack swap chain buffers before resizing.
+    pub fn track(&mut self, resource: &ID3D12Resource, state: D3D12_RESOURCE_STATES) {
+        self.resources.insert(
+            key(resource),
+            TrackedResource {
+                resource: resource.clone(),
+                state,
+                expected_final: None,
+            },
+        );
+    }
+
+    /// Stop tracking a resource
+    pub fn untrack(&mut self, resource: &ID3D12Resource) {
+        let k = key(resource);
+        self.resources.remove(&k);
+        self.pending.retain(|p| key(&p.resource) != k);
+    }
+
+    /// Check if a resource is tracked
+    pub fn is_tracked(&self, resource: &ID3D12Resource) -> bool {
+        self.resources.contains_key(&key(resource))
+    }
+
+    /// Number of resources being tracked
+    pub fn tracked_count(&self) -> usize {
+        self.resources.len()
+    }
+
+    /// Get the last known state of a resource
+    pub fn state(&self, resource: &ID3D12Resource) -> Option<D3D12_RESOURCE_STATES> {
+        self.resources.get(&key(resource)).map(|r| r.state)
+    }
+
+    /// Request that a resource be in `target` state
+    ///
+    /// Queues a barrier if the state changes and returns whether one was
+    /// queued. Untracked resources are ignored (with a warning).
+    pub fn transition(&mut self, resource: &ID3D12Resource, target: D3D12_RESOURCE_STATES) -> bool {
+        let Some(tracked) = self.resources.get_mut(&key(resource)) else {
+            log::warn!("transition requested for an untracked resource; call track() first");
+            return false;
+        };
+
+        if tracked.state == target {
+            return false;
+        }
+
+        self.pending.push(PendingTransition {
+            resource: tracked.resource.clone(),
+            before: tracked.state,
+            after: target,
+        });
+        tracked.state = target;
+        true
+    }
+
+    /// Number of barriers waiting to be flushed
+    pub fn pending_barriers(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Emit all queued barriers in one call
+    pub fn flush(&mut self, recorder: &impl BarrierRecorder) {
+        record(&self.pending, recorder);
+        self.pending.clear();
+    }
+
+    /// Declare the state a resource must be in when the command list closes
+    pub fn expect_final(&mut self, resource: &ID3D12Resource, state: D3D12_RESOURCE_STATES) {
+        if let Some(tracked) = self.resources.get_mut(&key(resource)) {
+            tracked.expected_final = Some(state);
+        }
+    }
+
+    /// Check declared final states, returning a message per mismatch
+    ///
+    /// Also reports barriers that were queued but never flushed.
+    pub fn validate(&self) -> Vec<String> {
+        validate(self.resources.values(), &self.pending)
+    }
+
+    /// Publish the changes of a submitted command list
+    ///
+    /// Declared final states are not carried over.
+    pub fn apply(&mut self, changes: StateChanges) {
+        for (k, mut tracked) in changes.resources {
+            tracked.expected_final = None;
+            self.resources.insert(k, tracked);
+        }
+    }
+
+    /// Clear declared final states (after a successful validation)
+    pub fn clear_expectations(&mut self) {
+        for r in self.resources.values_mut() {
+            r.expected_final = None;
+        }
+    }
+}
+
+/// One command list's state changes, recorded against a committed tracker
+///
+/// Only the resources the list tracks or transitions are stored, so starting
+/// a list costs nothing however many resources are committed. Pass the same
+/// committed tracker to every call, then hand the changes to
+/// `ResourceStateTracker::apply` once the list is submitted; dropping them
+/// leaves the committed states as they were.
+#[derive(Default)]
+pub struct StateChanges {
+    resources: HashMap<usize, TrackedResource>,
+    pending: Vec<PendingTransition>,
+}
+
+impl StateChanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a resource that is currently in `state`
+    pub fn track(&mut self, resource: &ID3D12Resource, state: D3D12_RESOURCE_STATES) {
+        let tracked = TrackedResource { resource: resource.clone(), state, expected_final: None };
+        self.resources.insert(key(resource), tracked);
+    }
+
+    /// The resource's state as this list leaves it
+    pub fn state(&self, committed: &ResourceStateTracker, resource: &ID3D12Resource) -> Option<D3D12_RESOURCE_STATES> {
+        self.resources.get(&key(resource)).map(|r| r.state).or_else(|| committed.state(resource))
+    }
+
+    /// Request that a resource be in `target` state
+    ///
+    /// Same as `ResourceStateTracker::transition`, starting from the state
+    /// this list left the resource in, or else the committed one.
+    pub fn transition(
+        &mut self,
+        committed: &ResourceStateTracker,
+        resource: &ID3D12Resource,
+        target: D3D12_RESOURCE_STATES,
+    ) -> bool {
+        match self.state(committed, resource) {
+            None => {
+                log::warn!("transition requested for an untracked resource; call track() first");
+                return false;
+            }
+            // Checked before copying, so no-ops don't touch the resource
+            Some(state) if state == target => return false,
+            Some(_) => {}
+        }
+
+        let tracked = self.touch(committed, resource).expect("resource is tracked");
+        let before = std::mem::replace(&mut tracked.state, target);
+        let resource = tracked.resource.clone();
+        self.pending.push(PendingTransition { resource, before, after: target });
+        true
+    }
+
+    /// Declare the state a resource must be in when the command list closes
+    pub fn expect_final(
+        &mut self,
+        committed: &ResourceStateTracker,
+        resource: &ID3D12Resource,
+        state: D3D12_RESOURCE_STATES,
+    ) {
+        if let Some(tracked) = self.touch(committed, resource) {
+            tracked.expected_final = Some(state);
+        }
+    }
+
+    /// This list's entry for a resource, copied from the committed states on first use
+    fn touch(&mut self, committed: &ResourceStateTracker, resource: &ID3D12Resource) -> Option<&mut TrackedResource> {
+        let k = key(resource);
+        match self.resources.entry(k) {
+            Entry::Occupied(entry) => Some(entry.into_mut()),
+            Entry::Vacant(entry) => Some(entry.insert(committed.resources.get(&k)?.clone())),
+        }
+    }
+
+    /// Number of resources this list changed or started tracking
+    pub fn touched_count(&self) -> usize {
+        self.resources.len()
+    }
+
+    /// Number of barriers waiting to be flushed
+    pub fn pending_barriers(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Emit all queued barriers in one call
+    pub fn flush(&mut self, recorder: &impl BarrierRecorder) {
+        record(&self.pending, recorder);
+        self.pending.clear();
+    }
+
+    /// Check declared final states and unflushed barriers, as
+    /// `ResourceStateTracker::validate` does
+    pub fn validate(&self) -> Vec<String> {
+        validate(self.resources.values(), &self.pending)
+    }
+}
+
+/// Record transitions as one barrier batch (nothing if there are none)
+fn record(pending: &[PendingTransition], recorder: &impl BarrierRecorder) {
+    if pending.is_empty() {
+        return;
+    }
+
+    let barriers: Vec<D3D12_RESOURCE_BARRIER> = pending
+        .iter()
+        .map(|p| D3D12_RESOURCE_BARRIER {
+            Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
+            Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+            Anonymous: D3D12_RESOURCE_BARRIER_0 {
+                Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
+                    // Borrowed: `pending` keeps the resource alive until after recording
+                    pResource: unsafe { std::mem::transmute_copy(&p.resource) },
+                    Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+                    StateBefore: p.before,
+                    StateAfter: p.after,
+                }),
+            },
+        })
+        .collect();
+
+    recorder.record_barriers(&barriers);
+}
+
+fn validate<'a>(resources: impl Iterator<Item = &'a TrackedResource>, pending: &[PendingTransition]) -> Vec<String> {
+    let mut errors: Vec<String> = resources
+        .filter_map(|r| {
+            let expected = r.expected_final?;
+            (expected != r.state).then(|| {
+                format!(
+                    "resource {:p} ends in state 0x{:x}, expected 0x{:x}",
+                    r.resource.as_raw(),
+                    r.state.0,
+                    expected.0
+                )
+            })
+        })
+        .collect();
+
+    if !pending.is_empty() {
+        errors.push(format!("{} barrier(s) queued but never flushed", pending.len()));
+    }
+    errors
+}
+
+impl std::fmt::Debug for ResourceStateTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceStateTracker")
+            .field("tracked", &self.resources.len())
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
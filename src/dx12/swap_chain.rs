@@ -3,13 +3,50 @@
 use super::{Device, Dx12Error, Dx12Result, CommandQueue};
 use windows::core::Interface;
 use windows::Win32::{
-    Foundation::HWND,
+    Foundation::{DXGI_STATUS_OCCLUDED, HWND},
     Graphics::{
         Direct3D12::*,
         Dxgi::{Common::*, *},
     },
 };
 
+/// Output format of the swap chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SwapChainFormat {
+    /// 8-bit SDR output (sRGB display curve)
+    #[default]
+    Sdr,
+    /// 16-bit float scRGB output (linear, 1.0 = 80 nits)
+    Rgba16Float,
+    /// 10-bit HDR10 output (ST.2084 PQ, Rec.2020 primaries)
+    Hdr10,
+}
+
+impl SwapChainFormat {
+    /// Get the back buffer format
+    pub fn dxgi_format(&self) -> DXGI_FORMAT {
+        match self {
+            SwapChainFormat::Sdr => DXGI_FORMAT_R8G8B8A8_UNORM,
+            SwapChainFormat::Rgba16Float => DXGI_FORMAT_R16G16B16A16_FLOAT,
+            SwapChainFormat::Hdr10 => DXGI_FORMAT_R10G10B10A2_UNORM,
+        }
+    }
+
+    /// Get the color space presented to the display
+    pub fn color_space(&self) -> DXGI_COLOR_SPACE_TYPE {
+        match self {
+            SwapChainFormat::Sdr => DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+            SwapChainFormat::Rgba16Float => DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709,
+            SwapChainFormat::Hdr10 => DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+        }
+    }
+
+    /// Check if this is an HDR format
+    pub fn is_hdr(&self) -> bool {
+        *self != SwapChainFormat::Sdr
+    }
+}
+
 /// Swap chain configuration
 #[derive(Debug, Clone)]
 pub struct SwapChainConfig {
@@ -18,6 +55,9 @@ pub struct SwapChainConfig {
     pub buffer_count: u32,
     pub format: DXGI_FORMAT,
     pub vsync: bool,
+    /// Create sRGB render target views so blending happens in linear space
+    /// and output is encoded once on write (8-bit formats only)
+    pub srgb_views: bool,
 }
 
 impl Default for SwapChainConfig {
@@ -28,6 +68,44 @@ impl Default for SwapChainConfig {
             buffer_count: 2,
             format: DXGI_FORMAT_R8G8B8A8_UNORM,
             vsync: true,
+            srgb_views: false,
+        }
+    }
+}
+
+/// Get the sRGB view format for an 8-bit back buffer format
+fn srgb_view_format(format: DXGI_FORMAT) -> Option<DXGI_FORMAT> {
+    match format {
+        DXGI_FORMAT_R8G8B8A8_UNORM => Some(DXGI_FORMAT_R8G8B8A8_UNORM_SRGB),
+        DXGI_FORMAT_B8G8R8A8_UNORM => Some(DXGI_FORMAT_B8G8R8A8_UNORM_SRGB),
+        _ => None,
+    }
+}
+
+/// Create the render target view for a back buffer
+///
+/// Flip-model swap chains can't use sRGB buffer formats, but their views can.
+fn create_rtv(
+    device: &Device,
+    buffer: &ID3D12Resource,
+    config: &SwapChainConfig,
+    handle: D3D12_CPU_DESCRIPTOR_HANDLE,
+) {
+    let view_format = if config.srgb_views { srgb_view_format(config.format) } else { None };
+
+    unsafe {
+        match view_format {
+            Some(format) => {
+                let desc = D3D12_RENDER_TARGET_VIEW_DESC {
+                    Format: format,
+                    ViewDimension: D3D12_RTV_DIMENSION_TEXTURE2D,
+                    Anonymous: D3D12_RENDER_TARGET_VIEW_DESC_0 {
+                        Texture2D: D3D12_TEX2D_RTV { MipSlice: 0, PlaneSlice: 0 },
+                    },
+                };
+                device.raw().CreateRenderTargetView(buffer, Some(&desc), handle);
+            }
+            None => device.raw().CreateRenderTargetView(buffer, None, handle),
         }
     }
 }
@@ -40,6 +118,8 @@ pub struct SwapChain {
     rtv_heap: ID3D12DescriptorHeap,
     rtv_descriptor_size: u32,
     current_back_buffer: u32,
+    output_format: SwapChainFormat,
+    occluded: bool,
 }
 
 impl SwapChain {
@@ -94,7 +174,7 @@ impl SwapChain {
                     ptr: rtv_handle.ptr + (i * rtv_descriptor_size) as usize,
                 };
 
-                device.raw().CreateRenderTargetView(&buffer, None, handle);
+                create_rtv(device, &buffer, &config, handle);
                 back_buffers.push(buffer);
             }
 
@@ -107,6 +187,8 @@ impl SwapChain {
                 rtv_heap,
                 rtv_descriptor_size,
                 current_back_buffer,
+                output_format: SwapChainFormat::Sdr,
+                occluded: false,
             })
         }
     }
@@ -121,6 +203,11 @@ impl SwapChain {
         &self.back_buffers[self.current_back_buffer as usize]
     }
 
+    /// Get all back buffer resources
+    pub fn back_buffers(&self) -> &[ID3D12Resource] {
+        &self.back_buffers
+    }
+
     /// Get the RTV handle for the current back buffer
     pub fn current_rtv(&self) -> D3D12_CPU_DESCRIPTOR_HANDLE {
         unsafe {
@@ -135,47 +222,235 @@ impl SwapChain {
     pub fn present(&mut self) -> Dx12Result<()> {
         unsafe {
             let sync_interval = if self.config.vsync { 1 } else { 0 };
-            self.swap_chain.Present(sync_interval, DXGI_PRESENT(0)).ok()?;
+            let result = self.swap_chain.Present(sync_interval, DXGI_PRESENT(0));
+            result.ok()?;
+            self.occluded = result == DXGI_STATUS_OCCLUDED;
             self.current_back_buffer = self.swap_chain.GetCurrentBackBufferIndex();
             Ok(())
         }
     }
 
+    /// Check if the window was hidden (minimized, covered, locked) at the
+    /// last present
+    ///
+    /// While occluded, re-checks with a test present so callers can skip
+    /// rendering until the window is visible again.
+    pub fn is_occluded(&mut self) -> bool {
+        if self.occluded {
+            let result = unsafe { self.swap_chain.Present(0, DXGI_PRESENT_TEST) };
+            self.occluded = result == DXGI_STATUS_OCCLUDED;
+        }
+        self.occluded
+    }
+
+    /// Drop the references to the back buffers (e.g. before the system
+    /// suspends); `recreate_buffers` gets them back
+    pub fn release_buffers(&mut self) {
+        self.back_buffers.clear();
+    }
+
+    /// Check if `release_buffers` was called without a recreate since
+    pub fn buffers_released(&self) -> bool {
+        self.back_buffers.is_empty()
+    }
+
+    /// Recreate the back buffers and views at the current size
+    pub fn recreate_buffers(&mut self, device: &Device) -> Dx12Result<()> {
+        self.resize(device, self.config.width, self.config.height)
+    }
+
     /// Resize the swap chain
+    ///
+    /// If ResizeBuffers fails the previous buffers are taken back, so the
+    /// swap chain stays usable at its old size. `Dx12Error::SwapChainLost`
+    /// means it has no buffers left; `recreate_buffers` can retry.
     pub fn resize(&mut self, device: &Device, width: u32, height: u32) -> Dx12Result<()> {
-        unsafe {
-            // Release back buffers
-            self.back_buffers.clear();
+        // ResizeBuffers fails while anything still references the buffers
+        self.back_buffers.clear();
 
-            // Resize buffers
+        let resized = unsafe {
             self.swap_chain.ResizeBuffers(
                 self.config.buffer_count,
                 width,
                 height,
                 self.config.format,
                 DXGI_SWAP_CHAIN_FLAG_ALLOW_MODE_SWITCH,
-            )?;
+            )
+        };
+        if let Err(e) = resized {
+            return match self.acquire_buffers(device) {
+                Ok(()) => Err(e.into()),
+                Err(lost) => Err(Dx12Error::SwapChainLost(format!("{}; taking the buffers back failed: {}", e, lost))),
+            };
+        }
 
-            // Recreate back buffers and RTVs
+        self.acquire_buffers(device).map_err(|e| Dx12Error::SwapChainLost(e.to_string()))?;
+        self.config.width = width;
+        self.config.height = height;
+        Ok(())
+    }
+
+    /// Get the swap chain's buffers and create their views
+    fn acquire_buffers(&mut self, device: &Device) -> Dx12Result<()> {
+        self.back_buffers.clear();
+        unsafe {
+            // After a failed ResizeBuffers this is the old count, not the config's
+            let count = self.swap_chain.GetDesc1()?.BufferCount;
+
+            // A buffer count change needs a heap with room for every RTV
+            if self.rtv_heap.GetDesc().NumDescriptors < count {
+                self.rtv_heap = device.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_RTV, count, false)?;
+            }
             let rtv_handle = self.rtv_heap.GetCPUDescriptorHandleForHeapStart();
 
-            for i in 0..self.config.buffer_count {
+            let mut buffers = Vec::with_capacity(count as usize);
+            for i in 0..count {
                 let buffer: ID3D12Resource = self.swap_chain.GetBuffer(i)?;
-
                 let handle = D3D12_CPU_DESCRIPTOR_HANDLE {
                     ptr: rtv_handle.ptr + (i * self.rtv_descriptor_size) as usize,
                 };
+                create_rtv(device, &buffer, &self.config, handle);
+                buffers.push(buffer);
+            }
+            // Only a complete set counts, so a failure leaves the swap chain without buffers
+            self.back_buffers = buffers;
+            self.current_back_buffer = self.swap_chain.GetCurrentBackBufferIndex();
+        }
+        Ok(())
+    }
 
-                device.raw().CreateRenderTargetView(&buffer, None, handle);
-                self.back_buffers.push(buffer);
+    /// Apply a new buffer count, format, vsync or view setting in place
+    ///
+    /// The caller must make sure the GPU is idle and that nothing else
+    /// holds the back buffers. If the new settings can't be applied the
+    /// previous ones are restored and the error returned. If restoring fails
+    /// too and leaves no back buffers, the error is `Dx12Error::SwapChainLost`.
+    pub fn reconfigure(&mut self, device: &Device, config: SwapChainConfig) -> Dx12Result<()> {
+        if !(2..=DXGI_MAX_SWAP_CHAIN_BUFFERS).contains(&config.buffer_count) {
+            return Err(Dx12Error::InvalidSwapChainConfig(format!(
+                "buffer_count must be 2-{}, got {}",
+                DXGI_MAX_SWAP_CHAIN_BUFFERS, config.buffer_count
+            )));
+        }
+        let output_format = match config.format {
+            DXGI_FORMAT_R8G8B8A8_UNORM | DXGI_FORMAT_B8G8R8A8_UNORM => SwapChainFormat::Sdr,
+            DXGI_FORMAT_R16G16B16A16_FLOAT => SwapChainFormat::Rgba16Float,
+            DXGI_FORMAT_R10G10B10A2_UNORM => SwapChainFormat::Hdr10,
+            other => {
+                return Err(Dx12Error::InvalidSwapChainConfig(format!(
+                    "unsupported back buffer format {:?}",
+                    other
+                )))
             }
+        };
 
-            self.config.width = width;
-            self.config.height = height;
-            self.current_back_buffer = self.swap_chain.GetCurrentBackBufferIndex();
+        let previous = (self.config.clone(), self.output_format);
+        match self.apply_config(device, config, output_format) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                log::error!("Swap chain reconfiguration failed ({}); restoring previous settings", e);
+                match self.apply_config(device, previous.0, previous.1) {
+                    Ok(()) => Err(e),
+                    // A failed ResizeBuffers leaves the previous buffers, which were taken back
+                    Err(restore) if !self.buffers_released() => {
+                        log::error!("Restoring the swap chain settings failed: {}", restore);
+                        Err(e)
+                    }
+                    Err(restore) => Err(Dx12Error::SwapChainLost(format!(
+                        "{}; restoring the previous settings failed: {}",
+                        e, restore
+                    ))),
+                }
+            }
+        }
+    }
 
-            Ok(())
+    fn apply_config(
+        &mut self,
+        device: &Device,
+        config: SwapChainConfig,
+        output_format: SwapChainFormat,
+    ) -> Dx12Result<()> {
+        self.config = config;
+        self.resize(device, self.config.width, self.config.height)?;
+        if output_format != self.output_format {
+            unsafe {
+                self.swap_chain.SetColorSpace1(output_format.color_space())?;
+            }
+            self.output_format = output_format;
+        }
+        Ok(())
+    }
+
+    /// Check if the display the window is on is in HDR mode
+    pub fn display_supports_hdr(&self) -> bool {
+        unsafe {
+            let Ok(output) = self.swap_chain.GetContainingOutput() else {
+                return false;
+            };
+            let Ok(output6) = output.cast::<IDXGIOutput6>() else {
+                return false;
+            };
+            match output6.GetDesc1() {
+                Ok(desc) => desc.ColorSpace == DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+                Err(_) => false,
+            }
+        }
+    }
+
+    /// Switch the output format, falling back to SDR if the display can't show it
+    ///
+    /// Returns the format actually in use. SDR output is left untouched.
+    pub fn set_output_format(
+        &mut self,
+        device: &Device,
+        format: SwapChainFormat,
+    ) -> Dx12Result<SwapChainFormat> {
+        let format = if format.is_hdr() && !self.display_supports_hdr() {
+            log::info!("Display is not in HDR mode; using SDR output");
+            SwapChainFormat::Sdr
+        } else {
+            format
+        };
+
+        if format == self.output_format {
+            return Ok(format);
+        }
+
+        unsafe {
+            let support = self.swap_chain.CheckColorSpaceSupport(format.color_space())?;
+            if support & DXGI_SWAP_CHAIN_COLOR_SPACE_SUPPORT_FLAG_PRESENT.0 as u32 == 0 {
+                log::warn!("Swap chain cannot present {:?}; using SDR output", format);
+                return Ok(self.output_format);
+            }
         }
+
+        self.config.format = format.dxgi_format();
+        self.resize(device, self.config.width, self.config.height)?;
+        unsafe {
+            self.swap_chain.SetColorSpace1(format.color_space())?;
+        }
+        self.output_format = format;
+        Ok(format)
+    }
+
+    /// Check if the current views encode to sRGB on write
+    pub fn is_srgb_view(&self) -> bool {
+        self.config.srgb_views && srgb_view_format(self.config.format).is_some()
+    }
+
+    /// Get the format of the back buffer views (what pipelines must target)
+    pub fn rtv_format(&self) -> DXGI_FORMAT {
+        if self.config.srgb_views {
+            srgb_view_format(self.config.format).unwrap_or(self.config.format)
+        } else {
+            self.config.format
+        }
+    }
+
+    /// Get the output format in use
+    pub fn output_format(&self) -> SwapChainFormat {
+        self.output_format
     }
 
     /// Get the swap chain configuration
@@ -1,6 +1,6 @@
 //! Texture resources for DirectX12
 
-use super::{Device, Dx12Error, Dx12Result};
+use super::{DepthMode, Device, Dx12Error, Dx12Result};
 use windows::Win32::Graphics::{Direct3D12::*, Dxgi::Common::*};
 
 /// Texture description
@@ -202,14 +202,19 @@ impl RenderTarget {
 pub struct DepthStencil {
     texture: Texture,
     dsv_handle: D3D12_CPU_DESCRIPTOR_HANDLE,
+    depth: DepthMode,
 }
 
 impl DepthStencil {
     /// Create a new depth stencil
+    ///
+    /// `depth` picks the optimized clear value, so it has to match the
+    /// pipelines that test against this buffer.
     pub fn new(
         device: &Device,
         width: u32,
         height: u32,
+        depth: DepthMode,
         dsv_heap: &ID3D12DescriptorHeap,
         heap_index: u32,
     ) -> Dx12Result<Self> {
@@ -244,7 +249,7 @@ impl DepthStencil {
                 Format: format,
                 Anonymous: D3D12_CLEAR_VALUE_0 {
                     DepthStencil: D3D12_DEPTH_STENCIL_VALUE {
-                        Depth: 1.0,
+                        Depth: depth.clear_value(),
                         Stencil: 0,
                     },
                 },
@@ -284,6 +289,7 @@ impl DepthStencil {
             Ok(Self {
                 texture: Texture { resource, desc },
                 dsv_handle,
+                depth,
             })
         }
     }
@@ -293,6 +299,16 @@ impl DepthStencil {
         self.dsv_handle
     }
 
+    /// Depth test this buffer was created for
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth
+    }
+
+    /// Value to clear to before drawing (matches the optimized clear value)
+    pub fn clear_value(&self) -> f32 {
+        self.depth.clear_value()
+    }
+
     /// Get the underlying texture
     pub fn texture(&self) -> &Texture {
         &self.texture
@@ -0,0 +1,320 @@
+//! Draw order sorting and batch statistics for `DrawContext`
+//!
+//! Consecutive commands with the same `BatchKey` can be merged into one
+//! draw. Auto-sort moves a command earlier to join the current batch only
+//! when it doesn't overlap any command it would jump over, so the image is
+//! unchanged. Bounds are padded by a pixel to cover edge rounding; commands
+//! without known bounds (text, clears) are never moved past. Commands are
+//! only moved within windows of `SORT_WINDOW`, so sorting stays linear.
+
+use super::DrawCommand;
+use crate::math::{Color, Rect};
+use std::collections::BTreeSet;
+
+/// What a command is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Material {
+    Shape,
+    Text,
+    Image,
+}
+
+/// Commands with equal keys can share a draw call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BatchKey<'a> {
+    texture: Option<&'a str>,
+    material: Material,
+    blend: bool,
+}
+
+fn translucent(color: &Color) -> bool {
+    color.a < 1.0
+}
+
+fn batch_key(command: &DrawCommand) -> Option<BatchKey<'_>> {
+    let (texture, material, blend) = match command {
+        DrawCommand::Clear(_) => return None,
+        DrawCommand::Rect { color, .. }
+        | DrawCommand::FilledRect { color, .. }
+        | DrawCommand::Circle { color, .. }
+        | DrawCommand::FilledCircle { color, .. }
+        | DrawCommand::Line { color, .. } => (None, Material::Shape, translucent(color)),
+        DrawCommand::Text { color, .. } => (None, Material::Text, translucent(color)),
+        DrawCommand::Image { path, .. } => (Some(path.as_str()), Material::Image, false),
+    };
+    Some(BatchKey { texture, material, blend })
+}
+
+/// Rect from two corners in any order (None if not finite)
+fn span(x1: f32, y1: f32, x2: f32, y2: f32) -> Option<Rect> {
+    if ![x1, y1, x2, y2].iter().all(|v| v.is_finite()) {
+        return None;
+    }
+    let (x, y) = (x1.min(x2), y1.min(y2));
+    Some(Rect::new(x, y, x1.max(x2) - x, y1.max(y2) - y))
+}
+
+/// Conservative screen bounds (None = unknown, treated as covering everything)
+fn bounds(command: &DrawCommand) -> Option<Rect> {
+    let rect = match *command {
+        DrawCommand::Rect { x, y, width, height, .. }
+        | DrawCommand::FilledRect { x, y, width, height, .. }
+        | DrawCommand::Image { x, y, width, height, .. } => span(x, y, x + width, y + height)?,
+        DrawCommand::Circle { x, y, radius, .. } | DrawCommand::FilledCircle { x, y, radius, .. } => {
+            span(x - radius, y - radius, x + radius, y + radius)?
+        }
+        DrawCommand::Line { x1, y1, x2, y2, thickness, .. } => {
+            span(x1, y1, x2, y2)?.expand(thickness.abs() * 0.5)
+        }
+        DrawCommand::Clear(_) | DrawCommand::Text { .. } => return None,
+    };
+    Some(rect.expand(1.0))
+}
+
+fn overlaps(a: Option<Rect>, b: Option<Rect>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.intersects(&b),
+        _ => true,
+    }
+}
+
+/// Commands a command can be moved earlier past, at most
+///
+/// Sorting works on windows of this many commands, which keeps the overlap
+/// checks linear in the command count; batches still continue from one
+/// window into the next.
+const SORT_WINDOW: usize = 128;
+
+/// Reorder commands to group batch keys without changing the image
+pub(super) fn sort_commands(commands: &[DrawCommand]) -> Vec<DrawCommand> {
+    let keys: Vec<_> = commands.iter().map(batch_key).collect();
+    let rects: Vec<_> = commands.iter().map(bounds).collect();
+    let mut order = Vec::with_capacity(commands.len());
+    let mut last_key = None;
+    for start in (0..commands.len()).step_by(SORT_WINDOW) {
+        let end = (start + SORT_WINDOW).min(commands.len());
+        sort_window(&keys[start..end], &rects[start..end], start, &mut last_key, &mut order);
+    }
+    order.into_iter().map(|i| commands[i].clone()).collect()
+}
+
+/// Append one window's commands (indices offset by `start`) to `order`
+fn sort_window<'a>(
+    keys: &[Option<BatchKey<'a>>],
+    rects: &[Option<Rect>],
+    start: usize,
+    last_key: &mut Option<BatchKey<'a>>,
+    order: &mut Vec<usize>,
+) {
+    // Command j has to follow every earlier command it overlaps
+    let mut waiting_on = vec![0usize; keys.len()];
+    let mut followers = vec![Vec::new(); keys.len()];
+    for j in 0..keys.len() {
+        for i in 0..j {
+            if overlaps(rects[i], rects[j]) {
+                waiting_on[j] += 1;
+                followers[i].push(j);
+            }
+        }
+    }
+
+    let mut ready: BTreeSet<usize> = (0..keys.len()).filter(|&i| waiting_on[i] == 0).collect();
+    while !ready.is_empty() {
+        // Continue the current batch if possible, otherwise keep the original order
+        let next = ready
+            .iter()
+            .copied()
+            .find(|&i| last_key.is_some() && keys[i] == *last_key)
+            .or_else(|| ready.first().copied())
+            .unwrap();
+        ready.remove(&next);
+        *last_key = keys[next];
+        order.push(start + next);
+        for &j in &followers[next] {
+            waiting_on[j] -= 1;
+            if waiting_on[j] == 0 {
+                ready.insert(j);
+            }
+        }
+    }
+}
+
+/// Why a new batch had to start
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchBreaks {
+    /// Different texture (image)
+    pub texture: usize,
+    /// Same texture, different material (shape, text, image)
+    pub material: usize,
+    /// Same texture and material, opaque vs translucent
+    pub blend: usize,
+}
+
+impl BatchBreaks {
+    pub fn total(&self) -> usize {
+        self.texture + self.material + self.blend
+    }
+}
+
+/// Batching summary for one frame's commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchStats {
+    /// Draw commands (clears excluded)
+    pub commands: usize,
+    /// Draw calls after merging consecutive commands
+    pub batches: usize,
+    /// Batches started after a key change, by reason
+    pub breaks: BatchBreaks,
+}
+
+pub(super) fn batch_stats(commands: &[DrawCommand]) -> BatchStats {
+    let mut stats = BatchStats::default();
+    let mut previous: Option<BatchKey> = None;
+    for command in commands {
+        // A clear ends the batch without being a break
+        let Some(key) = batch_key(command) else {
+            previous = None;
+            continue;
+        };
+        stats.commands += 1;
+        match previous {
+            Some(prev) if prev == key => continue,
+            Some(prev) if prev.texture != key.texture => stats.breaks.texture += 1,
+            Some(prev) if prev.material != key.material => stats.breaks.material += 1,
+            Some(_) => stats.breaks.blend += 1,
+            None => {}
+        }
+        stats.batches += 1;
+        previous = Some(key);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::xxh64;
+    use crate::math::Rng;
+
+    const SIZE: usize = 64;
+
+    /// Fill the pixels under `rect`, blending translucent colors
+    fn fill(pixels: &mut [[f32; 4]], rect: Rect, color: [f32; 4]) {
+        let clamp = |v: f32| (v.round().max(0.0) as usize).min(SIZE);
+        let (x0, x1) = (clamp(rect.x), clamp(rect.x + rect.width));
+        let (y0, y1) = (clamp(rect.y), clamp(rect.y + rect.height));
+        for y in y0..y1 {
+            for pixel in &mut pixels[y * SIZE + x0..y * SIZE + x1] {
+                for (channel, value) in pixel.iter_mut().zip(color) {
+                    *channel = value * color[3] + *channel * (1.0 - color[3]);
+                }
+            }
+        }
+    }
+
+    /// Hash of a reference rendering: every command fills its unpadded bounds
+    ///
+    /// Text covers `size` per character; each image path gets its own color.
+    fn render_hash(commands: &[DrawCommand]) -> u64 {
+        let mut pixels = vec![[0.0f32; 4]; SIZE * SIZE];
+        for command in commands {
+            let rgba = |c: &Color| [c.r, c.g, c.b, c.a];
+            match command {
+                DrawCommand::Clear(color) => pixels.fill(rgba(color)),
+                DrawCommand::Text { text, x, y, color, size } => {
+                    fill(&mut pixels, Rect::new(*x, *y, size * text.len() as f32, *size), rgba(color))
+                }
+                DrawCommand::Image { path, .. } => {
+                    let shade = path.len() as f32 / 4.0;
+                    fill(&mut pixels, bounds(command).unwrap().expand(-1.0), [shade, 1.0 - shade, 0.5, 1.0])
+                }
+                DrawCommand::Rect { color, .. }
+                | DrawCommand::FilledRect { color, .. }
+                | DrawCommand::Circle { color, .. }
+                | DrawCommand::FilledCircle { color, .. }
+                | DrawCommand::Line { color, .. } => {
+                    if let Some(rect) = bounds(command) {
+                        fill(&mut pixels, rect.expand(-1.0), rgba(color));
+                    }
+                }
+            }
+        }
+        let bytes: Vec<u8> = pixels.iter().flatten().flat_map(|v| v.to_le_bytes()).collect();
+        xxh64(&bytes, 0)
+    }
+
+    fn random_command(rng: &mut Rng) -> DrawCommand {
+        let opaque = rng.range_u32(0, 3) > 0;
+        let color = Color::new(rng.next_f32(), rng.next_f32(), rng.next_f32(), if opaque { 1.0 } else { 0.5 });
+        let (x, y) = (rng.range_f32(-3.0, 67.0), rng.range_f32(-3.0, 67.0));
+        let (width, height) = (rng.range_f32(-1.0, 12.0), rng.range_f32(0.0, 12.0));
+        match rng.range_u32(0, 9) {
+            0 => DrawCommand::Rect { x, y, width, height, color },
+            1 | 2 => DrawCommand::FilledRect { x, y, width, height, color },
+            3 => DrawCommand::FilledCircle { x, y, radius: rng.range_f32(0.0, 6.0), color },
+            4 => DrawCommand::Circle { x, y, radius: 3.0, color },
+            5 => DrawCommand::Line {
+                x1: x,
+                y1: y,
+                x2: x + rng.range_f32(-5.0, 5.0),
+                y2: y + rng.range_f32(0.0, 10.0),
+                color,
+                thickness: rng.range_f32(0.0, 3.0),
+            },
+            6 => DrawCommand::Text { text: "ab".to_string(), x, y, color, size: 5.0 },
+            7 if rng.range_u32(0, 10) == 0 => DrawCommand::Clear(color),
+            _ => {
+                let path = ["a", "bb", "ccc"][rng.range_u32(0, 3) as usize].to_string();
+                DrawCommand::Image { path, x, y, width, height }
+            }
+        }
+    }
+
+    fn random_scene(rng: &mut Rng, len: u32) -> Vec<DrawCommand> {
+        (0..len).map(|_| random_command(rng)).collect()
+    }
+
+    #[test]
+    fn sorting_random_scenes_keeps_the_image() {
+        let mut rng = Rng::new(0x5EED);
+        let mut fewer_batches = 0;
+        for _ in 0..1000 {
+            let len = rng.range_u32(1, 40);
+            let commands = random_scene(&mut rng, len);
+            let sorted = sort_commands(&commands);
+            assert_eq!(sorted.len(), commands.len());
+            assert_eq!(render_hash(&sorted), render_hash(&commands), "{:?}\nsorted: {:?}", commands, sorted);
+
+            let (before, after) = (batch_stats(&commands), batch_stats(&sorted));
+            assert_eq!(before.commands, after.commands);
+            assert!(after.batches <= before.batches);
+            if after.batches < before.batches {
+                fewer_batches += 1;
+            }
+        }
+        assert!(fewer_batches > 100, "sorting only helped {} scenes", fewer_batches);
+    }
+
+    #[test]
+    fn sorting_spans_windows() {
+        let mut rng = Rng::new(7);
+        let commands = random_scene(&mut rng, SORT_WINDOW as u32 * 5 + 17);
+        let sorted = sort_commands(&commands);
+        assert_eq!(render_hash(&sorted), render_hash(&commands));
+        assert!(batch_stats(&sorted).batches < batch_stats(&commands).batches);
+    }
+
+    #[test]
+    fn commands_move_past_non_overlapping_commands_only() {
+        let image = |x: f32| DrawCommand::Image { path: "a".to_string(), x, y: 0.0, width: 4.0, height: 4.0 };
+        let rect = |x: f32| DrawCommand::FilledRect { x, y: 0.0, width: 4.0, height: 4.0, color: Color::new(1.0, 0.0, 0.0, 1.0) };
+
+        let apart = [image(0.0), rect(10.0), image(20.0)];
+        assert_eq!(batch_stats(&apart).batches, 3);
+        assert_eq!(batch_stats(&sort_commands(&apart)).batches, 2);
+
+        // The second image would cover the rect if it moved first
+        let covering = [image(0.0), rect(10.0), image(12.0)];
+        assert_eq!(batch_stats(&sort_commands(&covering)).batches, 3);
+    }
+}
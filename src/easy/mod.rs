@@ -23,9 +23,20 @@
 //! }
 //! ```
 
-use crate::graphics::{Graphics, GraphicsConfig};
-use crate::math::{Color, Rect, Vec2};
+mod batching;
+
+pub use batching::{BatchBreaks, BatchStats};
+
+use crate::backend::{DrawBatch, RenderBackend, SoftwareBackend};
+use crate::events::InputState;
+use crate::graphics::{Graphics, GraphicsConfig, WindowHandle};
+use crate::math::{Color, Rect, Rng, Vec2};
 use crate::dx12::Dx12Result;
+use crate::testing::{hash_frame, RecordedInput};
+use std::borrow::Cow;
+
+/// Timestep used by `EasyApp::run_deterministic` (60 Hz)
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
 
 /// Simple 2D drawing context
 pub struct DrawContext {
@@ -33,6 +44,7 @@ pub struct DrawContext {
     height: f32,
     clear_color: Color,
     commands: Vec<DrawCommand>,
+    auto_sort: bool,
 }
 
 /// Drawing commands
@@ -56,6 +68,7 @@ impl DrawContext {
             height,
             clear_color: Color::BLACK,
             commands: Vec::new(),
+            auto_sort: false,
         }
     }
 
@@ -159,17 +172,78 @@ impl DrawContext {
         });
     }
 
-    /// Get all draw commands
+    /// Get all draw commands, in the order they were issued
     pub fn commands(&self) -> &[DrawCommand] {
         &self.commands
     }
 
+    /// Group commands by texture and material when drawing (off by default)
+    ///
+    /// Only commands whose bounds don't overlap are reordered, so the
+    /// output is the same as drawing in issue order.
+    pub fn set_auto_sort(&mut self, enabled: bool) {
+        self.auto_sort = enabled;
+    }
+
+    /// Check if auto-sort is enabled
+    pub fn auto_sort(&self) -> bool {
+        self.auto_sort
+    }
+
+    /// Commands in the order they will be drawn
+    pub fn draw_order(&self) -> Cow<'_, [DrawCommand]> {
+        if self.auto_sort {
+            Cow::Owned(batching::sort_commands(&self.commands))
+        } else {
+            Cow::Borrowed(&self.commands)
+        }
+    }
+
+    /// How many draw calls this frame needs and why batches were split
+    pub fn batch_stats(&self) -> BatchStats {
+        batching::batch_stats(&self.draw_order())
+    }
+
+    /// Convert the commands a backend can draw into a `DrawBatch`
+    ///
+    /// Only clears and filled rectangles are included so far.
+    pub fn to_batch(&self) -> DrawBatch {
+        let mut batch = DrawBatch::new();
+        for command in self.draw_order().iter() {
+            match command {
+                DrawCommand::Clear(color) => {
+                    batch.clear = Some(*color);
+                    batch.quads.clear();
+                }
+                DrawCommand::FilledRect { x, y, width, height, color } => {
+                    batch.quad(Rect::new(*x, *y, *width, *height), *color);
+                }
+                _ => {}
+            }
+        }
+        batch
+    }
+
     /// Clear all commands
     pub fn reset(&mut self) {
         self.commands.clear();
     }
 }
 
+/// Per-frame state passed to deterministic runs
+pub struct FrameInfo {
+    /// Frame number, starting at 0
+    pub frame: u64,
+    /// Seconds since the previous frame (always `FIXED_TIMESTEP`)
+    pub delta: f32,
+    /// Simulated seconds since the start
+    pub time: f32,
+    /// Seeded random numbers; use this instead of any other source
+    pub rng: Rng,
+    /// Input with this frame's recorded events applied
+    pub input: InputState,
+}
+
 /// Easy application - the simplest way to create a graphics app
 pub struct EasyApp {
     title: String,
@@ -193,8 +267,8 @@ impl EasyApp {
         }
     }
 
-    /// Initialize the graphics system (requires HWND from window)
-    pub fn init_with_hwnd(&mut self, hwnd: windows::Win32::Foundation::HWND) -> Dx12Result<()> {
+    /// Initialize the graphics system for a window
+    pub fn init_with_hwnd(&mut self, window: WindowHandle) -> Dx12Result<()> {
         let config = GraphicsConfig {
             width: self.width,
             height: self.height,
@@ -202,7 +276,7 @@ impl EasyApp {
             ..Default::default()
         };
 
-        self.graphics = Some(Graphics::new(hwnd, config)?);
+        self.graphics = Some(Graphics::new(window, config)?);
         self.running = true;
         Ok(())
     }
@@ -239,6 +313,49 @@ impl EasyApp {
         }
     }
     
+    /// Render `frames` frames off-screen and return a hash of each
+    ///
+    /// Runs on the software backend with a fixed timestep, an `Rng` seeded
+    /// with `seed` and, optionally, replayed input. Nothing depends on wall
+    /// clock time or threads, so the same callback, seed and input always
+    /// produce the same hashes.
+    pub fn run_deterministic<F>(&mut self, frames: u32, seed: u64, input: Option<RecordedInput>, mut draw_fn: F) -> Vec<u64>
+    where
+        F: FnMut(&mut DrawContext, &mut FrameInfo),
+    {
+        let mut backend = SoftwareBackend::new(self.width, self.height);
+        let recorded = input.unwrap_or_default();
+        let mut info = FrameInfo {
+            frame: 0,
+            delta: FIXED_TIMESTEP,
+            time: 0.0,
+            rng: Rng::new(seed),
+            input: InputState::new(),
+        };
+
+        let mut hashes = Vec::with_capacity(frames as usize);
+        for frame in 0..frames as u64 {
+            info.frame = frame;
+            info.time = frame as f32 * FIXED_TIMESTEP;
+            for event in recorded.events(frame) {
+                info.input.handle_event(event);
+            }
+
+            let mut ctx = DrawContext::new(self.width as f32, self.height as f32);
+            draw_fn(&mut ctx, &mut info);
+
+            // The software backend only fails on misuse, which can't happen here
+            let mut target = backend.begin_frame().expect("software frame");
+            backend.submit(&mut target, &ctx.to_batch()).expect("software submit");
+            backend.end_frame(target).expect("software end frame");
+
+            hashes.push(hash_frame(backend.pixels()));
+            info.input.end_frame();
+            self.frame_count += 1;
+        }
+        hashes
+    }
+
     /// Get mutable reference to graphics (if initialized)
     pub fn graphics_mut(&mut self) -> Option<&mut Graphics> {
         self.graphics.as_mut()
@@ -0,0 +1,288 @@
+//! Polled input state built from events
+
+use super::{Event, KeyCode, MouseButton};
+use crate::math::{Letterbox, Vec2};
+use std::collections::HashSet;
+
+/// How mouse positions outside the letterboxed image are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutsideContent {
+    /// Report no position
+    #[default]
+    None,
+    /// Clamp to the nearest edge of the image
+    Clamp,
+}
+
+/// Current keyboard and mouse state
+///
+/// Feed every event to `handle_event` and call `end_frame` once per frame to
+/// reset the per-frame deltas.
+///
+/// In relative mode (`set_relative_mode`) `mouse_delta` comes from raw
+/// motion events instead of cursor positions, so mouse-look keeps working
+/// when the cursor would hit the screen edge; `mouse_position` still
+/// follows the cursor. Relative mode is suspended while the window is
+/// unfocused or a UI has captured the mouse. `WinitInput` produces the raw
+/// motion events and hides and confines the cursor.
+#[derive(Debug, Clone)]
+pub struct InputState {
+    mouse_position: Vec2,
+    mouse_delta: Vec2,
+    scroll_delta: f32,
+    buttons: HashSet<MouseButton>,
+    keys: HashSet<KeyCode>,
+    letterbox: Option<Letterbox>,
+    outside: OutsideContent,
+    relative_mode: bool,
+    ui_captured: bool,
+    focused: bool,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self {
+            mouse_position: Vec2::ZERO,
+            mouse_delta: Vec2::ZERO,
+            scroll_delta: 0.0,
+            buttons: HashSet::new(),
+            keys: HashSet::new(),
+            letterbox: None,
+            outside: OutsideContent::default(),
+            relative_mode: false,
+            ui_captured: false,
+            focused: true,
+        }
+    }
+}
+
+impl InputState {
+    /// Create an empty input state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the state from an event
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::MouseMove(e) => {
+                if !self.is_relative_active() {
+                    self.mouse_delta += e.position - self.mouse_position;
+                }
+                self.mouse_position = e.position;
+            }
+            Event::RawMouseMotion(delta) if self.is_relative_active() => {
+                self.mouse_delta += *delta;
+            }
+            Event::MouseDown(e) => {
+                self.mouse_position = e.position;
+                if let Some(button) = e.button {
+                    self.buttons.insert(button);
+                }
+            }
+            Event::MouseUp(e) => {
+                self.mouse_position = e.position;
+                if let Some(button) = e.button {
+                    self.buttons.remove(&button);
+                }
+            }
+            Event::MouseScroll(e) => self.scroll_delta += e.scroll_delta,
+            Event::KeyDown(e) => {
+                self.keys.insert(e.key);
+            }
+            Event::KeyUp(e) => {
+                self.keys.remove(&e.key);
+            }
+            Event::WindowFocus(focused) => {
+                if !focused {
+                    // Releases are lost while unfocused
+                    self.keys.clear();
+                    self.buttons.clear();
+                }
+                self.set_focused(*focused);
+            }
+            _ => {}
+        }
+    }
+
+    /// Reset per-frame deltas
+    pub fn end_frame(&mut self) {
+        self.mouse_delta = Vec2::ZERO;
+        self.scroll_delta = 0.0;
+    }
+
+    /// Mouse position in window pixels
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_position
+    }
+
+    /// Mouse movement since the last `end_frame`
+    ///
+    /// Window pixels, or raw device units in relative mode.
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+
+    /// Scroll movement since the last `end_frame`
+    pub fn scroll_delta(&self) -> f32 {
+        self.scroll_delta
+    }
+
+    /// Check if a key is held
+    pub fn is_key_down(&self, key: KeyCode) -> bool {
+        self.keys.contains(&key)
+    }
+
+    /// Check if a mouse button is held
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    /// Enable or disable relative mouse mode
+    ///
+    /// The window layer should hide and lock the cursor while
+    /// `is_cursor_locked` is true and forward `Event::RawMouseMotion`;
+    /// `WinitInput::sync_cursor` and `WinitInput::device_event` do both.
+    pub fn set_relative_mode(&mut self, enabled: bool) {
+        self.update_relative(|state| state.relative_mode = enabled);
+    }
+
+    /// Check if relative mode was requested (it may be suspended)
+    pub fn relative_mode(&self) -> bool {
+        self.relative_mode
+    }
+
+    /// Tell the input state that a UI (debug overlay, modal) has the mouse
+    ///
+    /// Relative mode is suspended while captured and resumes afterwards.
+    pub fn set_ui_captured(&mut self, captured: bool) {
+        self.update_relative(|state| state.ui_captured = captured);
+    }
+
+    /// Check if relative mode is currently in effect
+    pub fn is_relative_active(&self) -> bool {
+        self.relative_mode && !self.ui_captured && self.focused
+    }
+
+    /// Check if the cursor should be hidden and locked
+    pub fn is_cursor_locked(&self) -> bool {
+        self.is_relative_active()
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.update_relative(|state| state.focused = focused);
+    }
+
+    /// Apply a change, dropping the pending delta when relative mode toggles
+    /// so the switch doesn't show up as a jump
+    fn update_relative(&mut self, change: impl FnOnce(&mut Self)) {
+        let was_active = self.is_relative_active();
+        change(self);
+        if was_active != self.is_relative_active() {
+            self.mouse_delta = Vec2::ZERO;
+        }
+    }
+
+    /// Set the letterbox used to map into internal-resolution space
+    ///
+    /// Update it whenever the window is resized.
+    pub fn set_letterbox(&mut self, letterbox: Option<Letterbox>) {
+        self.letterbox = letterbox;
+    }
+
+    /// Set how positions outside the image are reported
+    pub fn set_outside_content(&mut self, outside: OutsideContent) {
+        self.outside = outside;
+    }
+
+    /// Mouse position in internal-resolution pixels
+    ///
+    /// Same as `mouse_position` when no letterbox is set.
+    pub fn internal_mouse_position(&self) -> Option<Vec2> {
+        let Some(letterbox) = &self.letterbox else {
+            return Some(self.mouse_position);
+        };
+        match self.outside {
+            OutsideContent::None => letterbox.window_to_internal(self.mouse_position),
+            OutsideContent::Clamp => Some(letterbox.window_to_internal_clamped(self.mouse_position)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::MouseEvent;
+    use crate::math::ScalingMode;
+
+    fn move_to(input: &mut InputState, x: f32, y: f32) {
+        input.handle_event(&Event::MouseMove(MouseEvent { position: Vec2::new(x, y), ..Default::default() }));
+    }
+
+    #[test]
+    fn absolute_mode_deltas_follow_the_cursor() {
+        let mut input = InputState::new();
+        move_to(&mut input, 10.0, 10.0);
+        input.end_frame();
+        move_to(&mut input, 15.0, 12.0);
+        move_to(&mut input, 20.0, 8.0);
+        input.handle_event(&Event::RawMouseMotion(Vec2::new(100.0, 100.0)));
+        assert_eq!(input.mouse_delta(), Vec2::new(10.0, -2.0));
+        assert_eq!(input.mouse_position(), Vec2::new(20.0, 8.0));
+    }
+
+    #[test]
+    fn relative_mode_takes_deltas_from_raw_motion_and_keeps_the_position() {
+        let mut input = InputState::new();
+        move_to(&mut input, 10.0, 10.0);
+        input.set_relative_mode(true);
+        assert!(input.is_cursor_locked());
+
+        move_to(&mut input, 400.0, 300.0);
+        input.handle_event(&Event::RawMouseMotion(Vec2::new(3.0, -1.0)));
+        input.handle_event(&Event::RawMouseMotion(Vec2::new(2.0, 0.5)));
+        assert_eq!(input.mouse_delta(), Vec2::new(5.0, -0.5));
+        assert_eq!(input.mouse_position(), Vec2::new(400.0, 300.0));
+
+        // Back in absolute mode there is no jump from the stale position
+        input.set_relative_mode(false);
+        assert_eq!(input.mouse_delta(), Vec2::ZERO);
+        move_to(&mut input, 401.0, 300.0);
+        assert_eq!(input.mouse_delta(), Vec2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn relative_mode_is_suspended_by_focus_loss_and_ui_capture() {
+        let mut input = InputState::new();
+        input.set_relative_mode(true);
+        input.handle_event(&Event::WindowFocus(false));
+        assert!(input.relative_mode());
+        assert!(!input.is_cursor_locked());
+        input.handle_event(&Event::RawMouseMotion(Vec2::new(3.0, 3.0)));
+        assert_eq!(input.mouse_delta(), Vec2::ZERO);
+
+        input.handle_event(&Event::WindowFocus(true));
+        assert!(input.is_cursor_locked());
+        input.set_ui_captured(true);
+        assert!(!input.is_cursor_locked());
+        input.set_ui_captured(false);
+        assert!(input.is_cursor_locked());
+    }
+
+    #[test]
+    fn internal_mouse_position_follows_the_outside_mode() {
+        let mut input = InputState::new();
+        move_to(&mut input, 10.0, 300.0);
+        assert_eq!(input.internal_mouse_position(), Some(Vec2::new(10.0, 300.0)));
+
+        input.set_letterbox(Some(Letterbox::new((320, 180), (1000, 600), ScalingMode::IntegerNearest)));
+        assert_eq!(input.internal_mouse_position(), None);
+        input.set_outside_content(OutsideContent::Clamp);
+        assert_eq!(input.internal_mouse_position(), Some(Vec2::new(0.0, 90.0)));
+
+        move_to(&mut input, 500.0, 300.0);
+        assert_eq!(input.internal_mouse_position(), Some(Vec2::new(160.0, 90.0)));
+        input.set_outside_content(OutsideContent::None);
+        assert_eq!(input.internal_mouse_position(), Some(Vec2::new(160.0, 90.0)));
+    }
+}
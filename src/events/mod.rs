@@ -1,5 +1,11 @@
 //! Event system for EPICX
 
+mod input;
+mod winit_input;
+
+pub use input::{InputState, OutsideContent};
+pub use winit_input::WinitInput;
+
 use crate::math::Vec2;
 use std::collections::VecDeque;
 
@@ -26,6 +32,7 @@ pub enum KeyCode {
     Escape, Tab, CapsLock, Shift, Control, Alt, Space,
     Enter, Backspace, Delete, Insert, Home, End, PageUp, PageDown,
     Left, Right, Up, Down,
+    Backquote,
     // Other
     Unknown,
 }
@@ -68,6 +75,27 @@ pub struct Modifiers {
     pub logo: bool,
 }
 
+/// Application lifecycle changes reported by the OS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppLifecycleEvent {
+    /// The system is about to sleep or the session lost its display
+    Suspending,
+    /// The system woke up or the session got a display again
+    Resumed,
+}
+
+/// Changes to the GPU surfaces owned by `Graphics`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsEvent {
+    /// Swap chain buffers were released; don't render until recreated
+    SurfacesReleased,
+    /// Swap chain buffers were recreated; re-upload anything that was lost
+    SurfacesRecreated,
+    /// Buffer count, format or vsync changed; rebuild anything that
+    /// depends on the back buffer format
+    SwapChainReconfigured,
+}
+
 /// Event types
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -75,12 +103,18 @@ pub enum Event {
     WindowClose,
     WindowResize { width: u32, height: u32 },
     WindowFocus(bool),
+
+    // Lifecycle events
+    Lifecycle(AppLifecycleEvent),
+    Graphics(GraphicsEvent),
     
     // Mouse events
     MouseMove(MouseEvent),
     MouseDown(MouseEvent),
     MouseUp(MouseEvent),
     MouseScroll(MouseEvent),
+    /// Unaccelerated device motion (not clamped at the screen edge)
+    RawMouseMotion(Vec2),
     MouseEnter,
     MouseLeave,
     
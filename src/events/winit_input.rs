@@ -0,0 +1,120 @@
+//! winit glue for `InputState`
+//!
+//! Converts the mouse and focus events `InputState` tracks, forwards raw
+//! device motion for relative mode, and hides and confines the cursor
+//! while relative mode is active. Keyboard input is left to the app.
+
+use super::{Event, InputState, MouseButton, MouseEvent};
+use crate::math::Vec2;
+use winit::event::{DeviceEvent, ElementState, MouseScrollDelta, WindowEvent};
+use winit::window::{CursorGrabMode, Window};
+
+/// Pixels per scroll line for touchpads that report pixel deltas
+const PIXELS_PER_LINE: f32 = 40.0;
+
+/// Tracks what winit doesn't repeat in every event
+#[derive(Debug, Default)]
+pub struct WinitInput {
+    cursor: Vec2,
+    cursor_locked: bool,
+}
+
+impl WinitInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Convert a window event (None for events `InputState` doesn't use)
+    pub fn window_event(&mut self, event: &WindowEvent) -> Option<Event> {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Vec2::new(position.x as f32, position.y as f32);
+                Some(Event::MouseMove(MouseEvent { position: self.cursor, ..Default::default() }))
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let mouse = MouseEvent { position: self.cursor, button: Some(mouse_button(*button)), ..Default::default() };
+                Some(match state {
+                    ElementState::Pressed => Event::MouseDown(mouse),
+                    ElementState::Released => Event::MouseUp(mouse),
+                })
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+                };
+                Some(Event::MouseScroll(MouseEvent { position: self.cursor, scroll_delta: lines, ..Default::default() }))
+            }
+            WindowEvent::Focused(focused) => Some(Event::WindowFocus(*focused)),
+            WindowEvent::CursorEntered { .. } => Some(Event::MouseEnter),
+            WindowEvent::CursorLeft { .. } => Some(Event::MouseLeave),
+            _ => None,
+        }
+    }
+
+    /// Convert a device event; raw mouse motion becomes `Event::RawMouseMotion`
+    pub fn device_event(&self, event: &DeviceEvent) -> Option<Event> {
+        match event {
+            DeviceEvent::MouseMotion { delta: (x, y) } => Some(Event::RawMouseMotion(Vec2::new(*x as f32, *y as f32))),
+            _ => None,
+        }
+    }
+
+    /// Hide and confine the cursor while `input` is in relative mode
+    ///
+    /// Call after handling each batch of events; the window is only touched
+    /// when the lock state changes.
+    pub fn sync_cursor(&mut self, window: &Window, input: &InputState) {
+        let locked = input.is_cursor_locked();
+        if locked == self.cursor_locked {
+            return;
+        }
+        self.cursor_locked = locked;
+
+        let grab = if locked {
+            // Windows can only confine; other platforms can also lock in place
+            window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)
+        };
+        if let Err(e) = grab {
+            log::warn!("Failed to change the cursor grab: {}", e);
+        }
+        window.set_cursor_visible(!locked);
+    }
+
+    /// Check if `sync_cursor` last hid and confined the cursor
+    pub fn is_cursor_locked(&self) -> bool {
+        self.cursor_locked
+    }
+}
+
+fn mouse_button(button: winit::event::MouseButton) -> MouseButton {
+    match button {
+        winit::event::MouseButton::Left => MouseButton::Left,
+        winit::event::MouseButton::Right => MouseButton::Right,
+        winit::event::MouseButton::Middle => MouseButton::Middle,
+        winit::event::MouseButton::Back => MouseButton::Other(3),
+        winit::event::MouseButton::Forward => MouseButton::Other(4),
+        winit::event::MouseButton::Other(n) => MouseButton::Other(n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_motion_reaches_relative_input() {
+        let winit_input = WinitInput::new();
+        let mut input = InputState::new();
+        input.set_relative_mode(true);
+
+        let event = winit_input.device_event(&DeviceEvent::MouseMotion { delta: (4.0, -2.0) }).unwrap();
+        input.handle_event(&event);
+        assert_eq!(input.mouse_delta(), Vec2::new(4.0, -2.0));
+        assert!(winit_input.device_event(&DeviceEvent::Added).is_none());
+    }
+}
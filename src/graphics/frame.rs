@@ -11,10 +11,7 @@ pub struct FrameResources {
 impl FrameResources {
     /// Create new frame resources
     pub fn new(device: &Device) -> Dx12Result<Self> {
-        let command_allocator = CommandAllocator::new(
-            device,
-            windows::Win32::Graphics::Direct3D12::D3D12_COMMAND_LIST_TYPE_DIRECT,
-        )?;
+        let command_allocator = CommandAllocator::graphics(device)?;
 
         Ok(Self {
             command_allocator,
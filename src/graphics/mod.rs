@@ -12,16 +12,34 @@ mod context;
 mod frame;
 mod resources;
 pub mod renderer3d;
+pub mod tonemap;
+pub mod terrain;
 
 pub use context::GraphicsContext;
 pub use frame::{Frame, FrameResources};
-pub use resources::{GpuBuffer, GpuTexture, GpuMesh, Material};
-pub use renderer3d::{Vertex3D, Mesh3D, Camera3D, Transform3D, Object3D, TransformConstants};
+pub use resources::{GpuBuffer, GpuTexture, GpuMesh, Material, AlphaMode, RenderQueue};
+pub use renderer3d::{Vertex3D, Mesh3D, Camera3D, Transform3D, Object3D, TransformConstants, ClipPlane, NO_CLIP_PLANE, splitscreen_layout};
+pub use tonemap::{ToneMapOperator, ToneMapSettings};
+pub use terrain::{Terrain, TerrainChunk, TerrainConfig};
 
-use crate::dx12::{Device, CommandQueue, SwapChain, SwapChainConfig, CommandAllocator, CommandList, Dx12Result};
-use crate::math::Color;
-use windows::Win32::Foundation::HWND;
-use windows::Win32::Graphics::Direct3D12::*;
+pub use crate::dx12::{GpuResource, ResourceState, WindowHandle};
+
+use crate::backend::{Dx12Backend, Dx12Frame, DrawBatch, RenderBackend};
+use crate::dx12::{Device, CommandQueue, SwapChainConfig, CommandList, Dx12Result, Dx12Error, DeviceFeatures, SwapChainFormat, Breadcrumbs, ResourceStateTracker, StateChanges, GpuCapture};
+use crate::dx12::breadcrumbs;
+use crate::events::{AppLifecycleEvent, GraphicsEvent};
+use crate::math::{Color, Frustum, Letterbox, Rect, ScalingMode, Vec2};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Source of frame tokens, shared by all Graphics instances so a frame
+/// from one instance can never be mistaken for a frame of another.
+static NEXT_FRAME_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+/// Sentinel stored in the active-frame slot when no frame is in progress
+const NO_ACTIVE_FRAME: u64 = 0;
 
 /// Graphics configuration
 #[derive(Debug, Clone)]
@@ -32,6 +50,30 @@ pub struct GraphicsConfig {
     pub debug: bool,
     pub buffer_count: u32,
     pub clear_color: Color,
+    /// Requested output format; HDR falls back to SDR on SDR displays
+    pub output_format: SwapChainFormat,
+    /// Exposure, paper white and SDR tone mapping curve
+    pub tone_mapping: ToneMapSettings,
+    /// Blend in linear space: back buffers get sRGB views, clear colors,
+    /// quads and `RenderFrame::vertex_color` are decoded on input and output
+    /// is encoded once by the hardware
+    pub linear_blending: bool,
+    /// Record GPU breadcrumbs and enable DRED so device-removed errors
+    /// report which pass hung (small per-frame cost)
+    pub gpu_crash_diagnostics: bool,
+    /// Load the PIX GPU capturer (if installed) so `trigger_gpu_capture`
+    /// works without launching from PIX; RenderDoc is always detected
+    pub gpu_capture: bool,
+    /// Capture a frame after a frame-time spike (`EPICX_CAPTURE_ON_SPIKE=1`)
+    pub capture_on_spike: bool,
+    /// Fixed internal resolution, scaled to the window with bars
+    pub internal_resolution: Option<(u32, u32)>,
+    /// How the internal resolution is scaled to the window
+    pub scaling_mode: ScalingMode,
+    /// Color of the bars around the image with an internal resolution
+    pub letterbox_color: Color,
+    /// Render on the WARP software adapter (tests, machines without a GPU)
+    pub warp: bool,
 }
 
 impl Default for GraphicsConfig {
@@ -43,63 +85,136 @@ impl Default for GraphicsConfig {
             debug: cfg!(debug_assertions),
             buffer_count: 2,
             clear_color: Color::from_hex(0x1a1a2e),
+            output_format: SwapChainFormat::Sdr,
+            tone_mapping: ToneMapSettings::default(),
+            linear_blending: false,
+            gpu_crash_diagnostics: false,
+            gpu_capture: false,
+            capture_on_spike: std::env::var("EPICX_CAPTURE_ON_SPIKE").is_ok_and(|v| v == "1"),
+            internal_resolution: None,
+            scaling_mode: ScalingMode::IntegerNearest,
+            letterbox_color: Color::BLACK,
+            warp: false,
         }
     }
 }
 
 /// Main graphics system - Level B abstraction
 /// 
-/// Encapsulates ALL DirectX12 resources and provides a cleaner API.
-/// This is the main entry point for graphics operations.
+/// Renders through a `Dx12Backend`, adding tone mapping, resource state
+/// tracking and diagnostics. This is the main entry point for graphics
+/// operations.
 pub struct Graphics {
-    device: Device,
-    command_queue: CommandQueue,
-    swap_chain: SwapChain,
-    allocator: CommandAllocator,
+    backend: Dx12Backend,
     config: GraphicsConfig,
     frame_index: u64,
+    active_frame: Arc<AtomicU64>,
+    breadcrumbs: Option<Arc<Breadcrumbs>>,
+    /// Committed states, shared read-only with the frame being recorded
+    state_tracker: Arc<ResourceStateTracker>,
+    capture: Option<GpuCapture>,
+    capture_frames_remaining: u32,
+    /// Spikes before this frame don't trigger a capture
+    spike_cooldown_until: u64,
+    last_frame_end: Option<Instant>,
+    avg_frame_time: f32,
+    suspended: bool,
 }
 
 impl Graphics {
     /// Create a new graphics system with a window
-    pub fn new(hwnd: HWND, config: GraphicsConfig) -> Dx12Result<Self> {
-        let device = Device::new(config.debug)?;
-        let command_queue = CommandQueue::graphics(&device)?;
-        
+    pub fn new(window: WindowHandle, config: GraphicsConfig) -> Dx12Result<Self> {
+        if config.gpu_crash_diagnostics && !breadcrumbs::enable_dred() {
+            log::warn!("DRED is not available; device-removed reports will be limited");
+        }
+        if (config.gpu_capture || config.capture_on_spike) && !GpuCapture::load_pix_capturer() {
+            log::info!("PIX GPU capturer not found");
+        }
+        let device = if config.warp { Device::new_warp(config.debug)? } else { Device::new(config.debug)? };
+        let capture = GpuCapture::detect();
+        if let Some(capture) = &capture {
+            log::info!("GPU capture available via {:?}", capture.tool());
+        }
+        log::info!("Device features: {}", device.features());
+        crate::crash::set_context("graphics", format!("{:#?}\n\nDevice features: {}", config, device.features()));
+
         let swap_config = SwapChainConfig {
             width: config.width,
             height: config.height,
             buffer_count: config.buffer_count,
             vsync: config.vsync,
+            srgb_views: config.linear_blending,
             ..Default::default()
         };
         
-        let swap_chain = SwapChain::new(&device, &command_queue, hwnd, swap_config)?;
-        let allocator = CommandAllocator::new(&device, D3D12_COMMAND_LIST_TYPE_DIRECT)?;
-
-        Ok(Self {
-            device,
-            command_queue,
-            swap_chain,
-            allocator,
+        let breadcrumbs = if config.gpu_crash_diagnostics {
+            Some(Arc::new(Breadcrumbs::new(&device)?))
+        } else {
+            None
+        };
+        let mut backend = Dx12Backend::new(device, window, swap_config)?;
+        if config.output_format.is_hdr() {
+            let format = backend.set_output_format(config.output_format)?;
+            log::info!("Output format: {:?}", format);
+        }
+
+        let mut graphics = Self {
+            backend,
             config,
             frame_index: 0,
-        })
+            active_frame: Arc::new(AtomicU64::new(NO_ACTIVE_FRAME)),
+            breadcrumbs,
+            state_tracker: Arc::new(ResourceStateTracker::new()),
+            capture,
+            capture_frames_remaining: 0,
+            spike_cooldown_until: 0,
+            last_frame_end: None,
+            avg_frame_time: 0.0,
+            suspended: false,
+        };
+        graphics.present_startup_frames()?;
+        Ok(graphics)
+    }
+
+    /// Clear every back buffer to the clear color and present it
+    ///
+    /// Runs before the application draws anything, so the window never
+    /// shows uninitialized buffer contents. Frame counters are reset after.
+    fn present_startup_frames(&mut self) -> Dx12Result<()> {
+        for _ in 0..self.config.buffer_count {
+            let frame = self.begin_frame()?;
+            frame.clear(self.config.clear_color);
+            self.end_frame(frame)?;
+        }
+        self.frame_index = 0;
+        self.last_frame_end = None;
+        self.avg_frame_time = 0.0;
+        Ok(())
     }
 
     /// Get the device
     pub fn device(&self) -> &Device {
-        &self.device
+        self.backend.device()
+    }
+
+    /// Get the device feature support
+    pub fn features(&self) -> &DeviceFeatures {
+        self.backend.device().features()
     }
 
     /// Get the command queue
     pub fn command_queue(&self) -> &CommandQueue {
-        &self.command_queue
+        self.backend.command_queue()
     }
 
     /// Get mutable command queue
     pub fn command_queue_mut(&mut self) -> &mut CommandQueue {
-        &mut self.command_queue
+        self.backend.command_queue_mut()
+    }
+
+    /// Get the backend frames are rendered through
+    pub fn backend(&self) -> &Dx12Backend {
+        &self.backend
     }
 
     /// Get the configuration
@@ -107,6 +222,41 @@ impl Graphics {
         &self.config
     }
 
+    /// Get the swap chain settings in use (a starting point for `reconfigure`)
+    pub fn swap_chain_config(&self) -> &SwapChainConfig {
+        self.backend.swap_chain().config()
+    }
+
+    /// Get the output format actually in use
+    pub fn output_format(&self) -> SwapChainFormat {
+        self.backend.swap_chain().output_format()
+    }
+
+    /// Get the tone mapping settings
+    pub fn tone_mapping(&self) -> &ToneMapSettings {
+        &self.config.tone_mapping
+    }
+
+    /// Set the scene exposure multiplier
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.config.tone_mapping.exposure = exposure.max(0.0);
+    }
+
+    /// Get the committed resource states (as of the last submitted frame)
+    pub fn state_tracker(&self) -> &ResourceStateTracker {
+        &self.state_tracker
+    }
+
+    /// Register a long-lived resource (texture, render target) for state tracking
+    pub fn track_resource(&mut self, resource: &GpuResource, state: ResourceState) {
+        Arc::make_mut(&mut self.state_tracker).track(resource.raw(), state.raw());
+    }
+
+    /// Stop tracking a resource (before releasing it)
+    pub fn untrack_resource(&mut self, resource: &GpuResource) {
+        Arc::make_mut(&mut self.state_tracker).untrack(resource.raw());
+    }
+
     /// Get current frame index
     pub fn frame_index(&self) -> u64 {
         self.frame_index
@@ -122,84 +272,346 @@ impl Graphics {
         self.config.height
     }
 
+    /// Get the placement of the internal resolution in the window
+    ///
+    /// None when `internal_resolution` is not set. Pass it to
+    /// `InputState::set_letterbox` to map mouse coordinates.
+    pub fn letterbox(&self) -> Option<Letterbox> {
+        self.config.internal_resolution.map(|internal| {
+            Letterbox::new(internal, (self.config.width, self.config.height), self.config.scaling_mode)
+        })
+    }
+
+    /// Check if a frame has been begun but not yet ended (or dropped)
+    pub fn is_frame_in_progress(&self) -> bool {
+        self.active_frame.load(Ordering::Acquire) != NO_ACTIVE_FRAME
+    }
+
     /// Begin a new frame - returns a RenderFrame for drawing
+    ///
+    /// Returns `Dx12Error::FrameInProgress` if the previous frame has not been
+    /// passed to `end_frame` (or dropped) yet.
     pub fn begin_frame(&mut self) -> Dx12Result<RenderFrame> {
-        self.frame_index += 1;
-        self.allocator.reset()?;
-        
-        let cmd_list = CommandList::new(&self.device, &self.allocator, None)?;
-        let back_buffer = self.swap_chain.current_back_buffer();
-        let rtv = self.swap_chain.current_rtv();
-        
-        // Transition to render target
-        unsafe {
-            let barrier = D3D12_RESOURCE_BARRIER {
-                Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-                Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-                Anonymous: D3D12_RESOURCE_BARRIER_0 {
-                    Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                        pResource: std::mem::transmute_copy(back_buffer),
-                        Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-                        StateBefore: D3D12_RESOURCE_STATE_PRESENT,
-                        StateAfter: D3D12_RESOURCE_STATE_RENDER_TARGET,
-                    }),
-                },
-            };
-            cmd_list.raw().ResourceBarrier(&[barrier]);
+        if self.is_frame_in_progress() {
+            return Err(Dx12Error::FrameInProgress);
+        }
+        if self.suspended {
+            return Err(Dx12Error::Suspended);
         }
+
+        if self.capture_frames_remaining > 0 {
+            if let Some(capture) = &mut self.capture {
+                capture.start();
+            }
+        }
+
+        // The backend owns the back buffer and its PRESENT/RENDER_TARGET barriers
+        let gpu = RenderBackend::begin_frame(&mut self.backend)?;
+
+        // Record changes against the committed states; end_frame applies
+        // them once the list is submitted, an aborted frame just discards them
+        let committed = Arc::clone(&self.state_tracker);
+
+        let frame_marker = self.breadcrumbs.as_ref().map(|b| {
+            b.begin(gpu.command_list(), &format!("frame {}", self.frame_index + 1))
+        });
+
+        let token = FrameToken(NEXT_FRAME_TOKEN.fetch_add(1, Ordering::Relaxed));
+        self.active_frame.store(token.0, Ordering::Release);
+        self.frame_index += 1;
         
         Ok(RenderFrame {
-            cmd_list,
-            rtv,
-            back_buffer: std::mem::ManuallyDrop::new(Some(back_buffer.clone())),
+            srgb_view: gpu.is_srgb_view(),
+            gpu: Some(gpu),
+            committed,
+            states: RefCell::new(StateChanges::new()),
+            token,
+            active_frame: Arc::clone(&self.active_frame),
+            breadcrumbs: self.breadcrumbs.clone(),
+            frame_marker,
+            output_format: self.backend.swap_chain().output_format(),
+            tone_mapping: self.config.tone_mapping,
+            letterbox: self.letterbox(),
+            letterbox_color: self.config.letterbox_color,
             width: self.config.width,
             height: self.config.height,
         })
     }
 
     /// End the current frame and present
-    pub fn end_frame(&mut self, frame: RenderFrame) -> Dx12Result<()> {
-        // Transition back to present
-        unsafe {
-            let barrier = D3D12_RESOURCE_BARRIER {
-                Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-                Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-                Anonymous: D3D12_RESOURCE_BARRIER_0 {
-                    Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                        pResource: std::mem::transmute_copy(&frame.back_buffer),
-                        Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-                        StateBefore: D3D12_RESOURCE_STATE_RENDER_TARGET,
-                        StateAfter: D3D12_RESOURCE_STATE_PRESENT,
-                    }),
-                },
-            };
-            frame.cmd_list.raw().ResourceBarrier(&[barrier]);
+    ///
+    /// Returns `Dx12Error::StaleFrame` if `frame` is not the frame returned by
+    /// the last `begin_frame` of this instance; the frame is then aborted.
+    pub fn end_frame(&mut self, mut frame: RenderFrame) -> Dx12Result<()> {
+        if self.active_frame.load(Ordering::Acquire) != frame.token.0 {
+            return Err(Dx12Error::StaleFrame);
         }
-        
-        frame.cmd_list.close()?;
-        self.command_queue.execute(&[&frame.cmd_list]);
-        self.swap_chain.present()?;
-        self.command_queue.flush()?;
+
+        frame.flush_barriers();
+        frame.end_marker(frame.frame_marker);
+
+        let changes = frame.states.take();
+        if cfg!(debug_assertions) {
+            for error in changes.validate() {
+                log::error!("Resource state validation: {}", error);
+            }
+        }
+
+        let gpu = frame.gpu.take().expect("frame not ended yet");
+        // Releases the frame slot and the frame's reference to the committed
+        // states, so applying the changes below doesn't copy them
+        drop(frame);
+        let result = RenderBackend::end_frame(&mut self.backend, gpu);
+        // Only a frame that ended cleanly is known to have run its
+        // transitions; after an error the committed states stay as they were
+        if result.is_ok() {
+            Arc::make_mut(&mut self.state_tracker).apply(changes);
+        }
+        result.map_err(|e| self.check_device_removed(e.into()))?;
+
+        let captured = self.finish_capture_frame();
+        self.watch_frame_time(captured);
         
         Ok(())
     }
 
+    /// Capture the next `frames` frames with RenderDoc or PIX
+    ///
+    /// A logged no-op when no capture tool is attached. Bind it to a hotkey
+    /// (the cube_window example uses F12).
+    pub fn trigger_gpu_capture(&mut self, frames: u32) {
+        if self.capture.is_none() {
+            log::info!("GPU capture requested but no RenderDoc/PIX is attached");
+            return;
+        }
+        if self.capture_frames_remaining == 0 {
+            log::info!("Capturing {} frame(s)", frames);
+            self.capture_frames_remaining = frames;
+        }
+    }
+
+    /// Check if a capture tool is attached
+    pub fn is_gpu_capture_available(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// End the capture after its last frame; returns true if this frame was captured
+    fn finish_capture_frame(&mut self) -> bool {
+        let Some(capture) = &mut self.capture else { return false };
+        if !capture.is_capturing() {
+            return false;
+        }
+        self.capture_frames_remaining = self.capture_frames_remaining.saturating_sub(1);
+        if self.capture_frames_remaining == 0 {
+            capture.end();
+            log::info!("GPU capture finished");
+        }
+        true
+    }
+
+    /// Track frame times and trigger a capture after a spike
+    ///
+    /// Captures can't start retroactively, so the frame following the spike
+    /// is captured; repeated hitches are usually caught on the next one.
+    /// Captured frames are slow by nature, so they are left out of the
+    /// average and start a cooldown instead of triggering more captures.
+    fn watch_frame_time(&mut self, captured: bool) {
+        const WARMUP_FRAMES: u64 = 60;
+        const SPIKE_FACTOR: f32 = 2.5;
+        const COOLDOWN_FRAMES: u64 = 600;
+
+        let now = Instant::now();
+        let Some(last) = self.last_frame_end.replace(now) else { return };
+        if captured {
+            self.spike_cooldown_until = self.frame_index + COOLDOWN_FRAMES;
+            return;
+        }
+        let dt = now.duration_since(last).as_secs_f32();
+
+        if self.config.capture_on_spike
+            && self.frame_index > WARMUP_FRAMES
+            && self.frame_index >= self.spike_cooldown_until
+            && self.capture_frames_remaining == 0
+            && dt > self.avg_frame_time * SPIKE_FACTOR
+        {
+            log::warn!(
+                "Frame spike: {:.1} ms (average {:.1} ms)",
+                dt * 1000.0,
+                self.avg_frame_time * 1000.0
+            );
+            self.trigger_gpu_capture(1);
+        }
+
+        self.avg_frame_time = if self.avg_frame_time == 0.0 {
+            dt
+        } else {
+            self.avg_frame_time * 0.95 + dt * 0.05
+        };
+    }
+
+    /// Record a batch of clears and quads into a frame
+    ///
+    /// Colors are display values; translucent quads blend over the target.
+    pub fn submit(&mut self, frame: &mut RenderFrame, batch: &DrawBatch) -> Dx12Result<()> {
+        frame.flush_barriers();
+        let gpu = frame.gpu.as_mut().expect("frame not ended yet");
+        Ok(self.backend.submit(gpu, batch)?)
+    }
+
+    /// Replace an error with a `DeviceRemoved` report if the device is gone
+    fn check_device_removed(&self, error: Dx12Error) -> Dx12Error {
+        let Some(reason) = self.backend.device().removed_reason() else {
+            return error;
+        };
+
+        let mut report = format!("{}", reason);
+        if let Some(b) = &self.breadcrumbs {
+            report.push_str("\nbreadcrumbs: ");
+            report.push_str(&b.report());
+        }
+        if let Some(dred) = breadcrumbs::dred_report(self.backend.device()) {
+            report.push_str("\nDRED:\n");
+            report.push_str(&dred);
+        }
+        log::error!("GPU device removed: {}", report);
+        crate::crash::set_context("device_removed", report.clone());
+        Dx12Error::DeviceRemoved(report)
+    }
+
+    /// Suspend if a failed swap chain change left it without back buffers
+    ///
+    /// `begin_frame` would have nothing to render to; `resume` retries.
+    fn check_swap_chain_lost(&mut self, error: Dx12Error) -> Dx12Error {
+        let error = self.check_device_removed(error);
+        if self.backend.swap_chain().buffers_released() {
+            self.suspended = true;
+            log::error!("Swap chain lost its back buffers ({}); suspended until resume", error);
+        }
+        error
+    }
+
     /// Flush all GPU work
     pub fn flush(&mut self) -> Dx12Result<()> {
-        self.command_queue.flush()
+        self.backend.flush()
     }
 
     /// Resize the graphics system
+    ///
+    /// The swap chain buffers cannot be recreated while a frame still holds
+    /// one, so this returns `Dx12Error::FrameInProgress` in that case. A
+    /// failed resize keeps the previous size; if it cost the swap chain its
+    /// buffers the error is `Dx12Error::SwapChainLost` and Graphics is
+    /// suspended until `resume` succeeds.
     pub fn resize(&mut self, width: u32, height: u32) -> Dx12Result<()> {
         if width == 0 || height == 0 {
             return Ok(());
         }
-        self.flush()?;
-        self.swap_chain.resize(&self.device, width, height)?;
+        if self.is_frame_in_progress() {
+            return Err(Dx12Error::FrameInProgress);
+        }
+        if self.suspended {
+            // Applied by resume
+            self.config.width = width;
+            self.config.height = height;
+            return Ok(());
+        }
+        RenderBackend::resize(&mut self.backend, width, height)
+            .map_err(|e| self.check_swap_chain_lost(e.into()))?;
+        // Only a resize that happened changes the size frames are laid out for
         self.config.width = width;
         self.config.height = height;
         Ok(())
     }
+
+    /// Check if `suspend` was called without a `resume` since
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Check if the window was hidden at the last present
+    ///
+    /// Rendering can be skipped while this is true; it clears once the
+    /// window is visible again.
+    pub fn is_occluded(&mut self) -> bool {
+        self.backend.swap_chain_mut().is_occluded()
+    }
+
+    /// Flush the GPU and release the swap chain buffers
+    ///
+    /// Call before the system sleeps or the session changes adapters.
+    /// `begin_frame` returns `Dx12Error::Suspended` until `resume`.
+    pub fn suspend(&mut self) -> Dx12Result<GraphicsEvent> {
+        if self.is_frame_in_progress() {
+            return Err(Dx12Error::FrameInProgress);
+        }
+        if !self.suspended {
+            self.flush().map_err(|e| self.check_device_removed(e))?;
+            self.backend.swap_chain_mut().release_buffers();
+            self.suspended = true;
+            log::info!("Graphics suspended");
+        }
+        Ok(GraphicsEvent::SurfacesReleased)
+    }
+
+    /// Recreate the swap chain buffers released by `suspend`
+    ///
+    /// Applies any resize that arrived while suspended. Frame timing starts
+    /// over so the pause doesn't register as a spike.
+    pub fn resume(&mut self) -> Dx12Result<GraphicsEvent> {
+        if self.suspended {
+            RenderBackend::resize(&mut self.backend, self.config.width, self.config.height)
+                .map_err(|e| self.check_device_removed(e.into()))?;
+            self.suspended = false;
+            self.last_frame_end = None;
+            log::info!("Graphics resumed");
+        }
+        Ok(GraphicsEvent::SurfacesRecreated)
+    }
+
+    /// Change the buffer count, format, vsync or sRGB views without
+    /// recreating Graphics
+    ///
+    /// Width and height are taken from `config` too. On failure the
+    /// previous settings stay in effect; if they couldn't be restored the
+    /// error is `Dx12Error::SwapChainLost` and Graphics is suspended until
+    /// `resume` succeeds. Dispatch the returned event so
+    /// format-dependent pipelines can be rebuilt; tone mapping and the
+    /// letterbox pick up the change on the next `begin_frame`.
+    pub fn reconfigure(&mut self, config: SwapChainConfig) -> Dx12Result<GraphicsEvent> {
+        if self.is_frame_in_progress() {
+            return Err(Dx12Error::FrameInProgress);
+        }
+        if self.suspended {
+            return Err(Dx12Error::Suspended);
+        }
+        self.backend.reconfigure(config).map_err(|e| self.check_swap_chain_lost(e))?;
+
+        let applied = self.backend.swap_chain().config();
+        self.config.width = applied.width;
+        self.config.height = applied.height;
+        self.config.buffer_count = applied.buffer_count;
+        self.config.vsync = applied.vsync;
+        self.config.linear_blending = applied.srgb_views;
+        self.config.output_format = self.backend.swap_chain().output_format();
+        log::info!(
+            "Swap chain reconfigured: {} buffers, {:?}, vsync {}",
+            applied.buffer_count,
+            self.config.output_format,
+            applied.vsync
+        );
+        Ok(GraphicsEvent::SwapChainReconfigured)
+    }
+
+    /// Suspend or resume for an OS lifecycle event
+    ///
+    /// Dispatch the returned event (as `Event::Graphics`) to user code so it
+    /// can re-upload anything it keeps in swap-chain-sized resources.
+    pub fn handle_lifecycle(&mut self, event: AppLifecycleEvent) -> Dx12Result<GraphicsEvent> {
+        match event {
+            AppLifecycleEvent::Suspending => self.suspend(),
+            AppLifecycleEvent::Resumed => self.resume(),
+        }
+    }
 }
 
 impl Drop for Graphics {
@@ -208,68 +620,258 @@ impl Drop for Graphics {
     }
 }
 
+/// Identifies a single begin_frame/end_frame pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameToken(u64);
+
+/// One viewport of a split-screen frame
+#[derive(Clone, Debug)]
+pub struct SplitView {
+    /// Position in the `views` slice
+    pub index: usize,
+    /// Viewport in window pixels
+    pub viewport: Rect,
+    /// The view's camera with its aspect set from `viewport`
+    pub camera: Camera3D,
+}
+
+impl SplitView {
+    /// Frustum for culling this view
+    pub fn frustum(&self) -> Frustum {
+        self.camera.frustum()
+    }
+
+    /// Viewport size in whole pixels (e.g. for `ViewportAnalyzers::get`)
+    pub fn size(&self) -> (u32, u32) {
+        (self.viewport.width.max(1.0) as u32, self.viewport.height.max(1.0) as u32)
+    }
+}
+
 /// A frame being rendered - provides simple drawing API
+///
+/// Dropping a frame without passing it to `Graphics::end_frame` aborts it:
+/// the command list is closed and discarded and nothing is presented.
 pub struct RenderFrame {
-    cmd_list: CommandList,
-    rtv: D3D12_CPU_DESCRIPTOR_HANDLE,
-    back_buffer: std::mem::ManuallyDrop<Option<ID3D12Resource>>,
+    /// The backend's frame (taken by `end_frame`)
+    gpu: Option<Dx12Frame>,
+    committed: Arc<ResourceStateTracker>,
+    states: RefCell<StateChanges>,
+    token: FrameToken,
+    active_frame: Arc<AtomicU64>,
+    breadcrumbs: Option<Arc<Breadcrumbs>>,
+    frame_marker: Option<u32>,
+    output_format: SwapChainFormat,
+    srgb_view: bool,
+    tone_mapping: ToneMapSettings,
+    letterbox: Option<Letterbox>,
+    letterbox_color: Color,
     pub width: u32,
     pub height: u32,
 }
 
+impl Drop for RenderFrame {
+    fn drop(&mut self) {
+        if self.gpu.take().is_some() {
+            // The backend frame closes its list unexecuted, so the committed states stay valid
+            log::warn!("RenderFrame {:?} dropped without end_frame; aborting frame", self.token);
+        }
+        self.release();
+    }
+}
+
 impl RenderFrame {
+    /// Get the token identifying this frame
+    pub fn token(&self) -> FrameToken {
+        self.token
+    }
+
+    /// Get the backend's frame
+    fn gpu(&self) -> &Dx12Frame {
+        self.gpu.as_ref().expect("frame not ended yet")
+    }
+
+    /// Request a resource state; the barrier is emitted before the next command
+    ///
+    /// The resource must have been registered with `track`.
+    pub fn transition(&self, resource: &GpuResource, state: ResourceState) {
+        self.states.borrow_mut().transition(&self.committed, resource.raw(), state.raw());
+    }
+
+    /// Start tracking a resource currently in `state`
+    pub fn track(&self, resource: &GpuResource, state: ResourceState) {
+        self.states.borrow_mut().track(resource.raw(), state.raw());
+    }
+
+    /// Emit queued transitions as one barrier batch
+    pub fn flush_barriers(&self) {
+        self.states.borrow_mut().flush(self.gpu().command_list());
+    }
+
+    /// Free the owning Graphics' active-frame slot (if it is still ours)
+    fn release(&self) {
+        let _ = self.active_frame.compare_exchange(
+            self.token.0,
+            NO_ACTIVE_FRAME,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+    }
+
     /// Clear the screen with a color
+    ///
+    /// The color is treated as SDR; on HDR outputs it is placed at paper white.
+    /// With an internal resolution only the image area gets `color`; the bars
+    /// are cleared to `GraphicsConfig::letterbox_color`.
     pub fn clear(&self, color: Color) {
-        let clear_color = [color.r, color.g, color.b, color.a];
-        unsafe {
-            self.cmd_list.raw().ClearRenderTargetView(self.rtv, &clear_color, None);
+        match &self.letterbox {
+            Some(letterbox) => {
+                self.clear_rects(color, &[letterbox.content]);
+                self.clear_rects(self.letterbox_color, &letterbox.bars());
+            }
+            None => self.clear_rects(color, &[]),
         }
     }
+
+    /// Clear parts of the screen (the whole screen if `rects` is empty)
+    pub(crate) fn clear_rects(&self, color: Color, rects: &[Rect]) {
+        let mut color = self.tone_mapping.map_display(color, self.output_format);
+        if self.srgb_view {
+            // The view re-encodes on write, so hand it linear values
+            color = color.to_linear();
+        }
+        self.clear_raw(color, rects);
+    }
+
+    fn clear_raw(&self, color: Color, rects: &[Rect]) {
+        self.flush_barriers();
+        self.gpu().clear_rects(color, rects);
+    }
     
+    /// Clear with a linear scene color (may exceed 1.0)
+    ///
+    /// Applies exposure and tone mapping (SDR) or HDR encoding for the output.
+    pub fn clear_scene(&self, linear: Color) {
+        let mut color = self.tone_mapping.map_scene(linear, self.output_format);
+        if self.srgb_view {
+            color = color.to_linear();
+        }
+        self.clear_raw(color, &[]);
+    }
+
     /// Clear with RGBA values
     pub fn clear_rgba(&self, r: f32, g: f32, b: f32, a: f32) {
-        let clear_color = [r, g, b, a];
-        unsafe {
-            self.cmd_list.raw().ClearRenderTargetView(self.rtv, &clear_color, None);
+        self.clear(Color::rgba(r, g, b, a));
+    }
+
+    /// Convert a display color for the vertex data of a custom draw
+    ///
+    /// With linear blending the view encodes on write, so the color is
+    /// decoded here; otherwise it is passed through. Submitted quads do this
+    /// themselves.
+    pub fn vertex_color(&self, color: Color) -> [f32; 4] {
+        self.gpu().encode(color).to_array()
+    }
+
+    /// Check if this frame's render target blends in linear space
+    pub fn is_linear_blending(&self) -> bool {
+        self.srgb_view
+    }
+
+    /// Mark the start of a pass for GPU crash diagnostics
+    ///
+    /// Returns a marker to pass to `end_marker`; a no-op (returning `None`)
+    /// unless `GraphicsConfig::gpu_crash_diagnostics` is set.
+    pub fn begin_marker(&self, label: &str) -> Option<u32> {
+        self.breadcrumbs.as_ref().map(|b| b.begin(self.gpu().command_list(), label))
+    }
+
+    /// Mark the end of a pass started with `begin_marker`
+    pub fn end_marker(&self, marker: Option<u32>) {
+        if let (Some(b), Some(marker)) = (&self.breadcrumbs, marker) {
+            b.end(self.gpu().command_list(), marker);
         }
     }
+
+    /// Get the output format of this frame's back buffer
+    pub fn output_format(&self) -> SwapChainFormat {
+        self.output_format
+    }
     
     /// Get the raw command list for advanced operations
+    ///
+    /// Flushes queued transitions first so recorded commands see them.
     pub fn cmd_list(&self) -> &CommandList {
-        &self.cmd_list
+        self.flush_barriers();
+        self.gpu().command_list()
     }
     
     /// Set viewport
     pub fn set_viewport(&self, x: f32, y: f32, width: f32, height: f32) {
-        let viewport = D3D12_VIEWPORT {
-            TopLeftX: x,
-            TopLeftY: y,
-            Width: width,
-            Height: height,
-            MinDepth: 0.0,
-            MaxDepth: 1.0,
-        };
-        unsafe {
-            self.cmd_list.raw().RSSetViewports(&[viewport]);
-        }
+        self.gpu().command_list().set_viewport(x, y, width, height);
     }
     
     /// Set scissor rect
     pub fn set_scissor(&self, left: i32, top: i32, right: i32, bottom: i32) {
-        let scissor = windows::Win32::Foundation::RECT {
-            left,
-            top,
-            right,
-            bottom,
-        };
-        unsafe {
-            self.cmd_list.raw().RSSetScissorRects(&[scissor]);
-        }
+        self.gpu().command_list().set_scissor_rect(left, top, right, bottom);
     }
     
     /// Set full viewport and scissor
+    ///
+    /// With an internal resolution this is the letterboxed image area, so
+    /// geometry laid out for the internal size is scaled into place.
     pub fn set_full_viewport(&self) {
-        self.set_viewport(0.0, 0.0, self.width as f32, self.height as f32);
-        self.set_scissor(0, 0, self.width as i32, self.height as i32);
+        match &self.letterbox {
+            Some(letterbox) => {
+                let c = letterbox.content;
+                self.set_viewport(c.x, c.y, c.width, c.height);
+                self.set_scissor(c.x as i32, c.y as i32, (c.x + c.width) as i32, (c.y + c.height) as i32);
+            }
+            None => {
+                self.set_viewport(0.0, 0.0, self.width as f32, self.height as f32);
+                self.set_scissor(0, 0, self.width as i32, self.height as i32);
+            }
+        }
+    }
+
+    /// Get the letterbox placement for this frame, if an internal resolution is set
+    pub fn letterbox(&self) -> Option<&Letterbox> {
+        self.letterbox.as_ref()
+    }
+
+    /// Draw the scene once per viewport
+    ///
+    /// `views` pairs a camera with its viewport, in internal pixels when an
+    /// internal resolution is set (see `splitscreen_layout`). For each one
+    /// the viewport and scissor are set and `draw` gets the view, with the
+    /// camera's aspect taken from its rect, so it can upload per-view
+    /// constants, cull and run ISR analysis (`isr::ViewportAnalyzers`). The
+    /// full viewport is restored afterwards, so UI drawn next covers the
+    /// whole window once.
+    pub fn render_splitscreen(&self, views: &[(Camera3D, Rect)], mut draw: impl FnMut(&SplitView)) {
+        for (index, (camera, rect)) in views.iter().enumerate() {
+            let viewport = match &self.letterbox {
+                Some(letterbox) => {
+                    let origin = letterbox.internal_to_window(rect.position());
+                    let size = Vec2::new(rect.width, rect.height) * letterbox.scale();
+                    Rect::new(origin.x, origin.y, size.x, size.y)
+                }
+                None => *rect,
+            };
+            if viewport.width < 1.0 || viewport.height < 1.0 {
+                continue;
+            }
+
+            let marker = self.begin_marker("splitscreen view");
+            self.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+            self.set_scissor(
+                viewport.x as i32,
+                viewport.y as i32,
+                (viewport.x + viewport.width) as i32,
+                (viewport.y + viewport.height) as i32,
+            );
+            draw(&SplitView { index, viewport, camera: camera.for_viewport(*rect) });
+            self.end_marker(marker);
+        }
+        self.set_full_viewport();
     }
 }
@@ -6,8 +6,11 @@
 //! - Primitive meshes (cube, sphere, cylinder, plane)
 //! - Camera and transforms
 //! - Basic lighting
+//! - User clip plane (SV_ClipDistance)
 
-use crate::math::{Vec3, Mat4, Color};
+use crate::dx12::DepthMode;
+use crate::math::{Vec3, Vec4, Mat4, Color, Frustum, Rect};
+use super::Material;
 
 /// Vertex format for 3D rendering
 #[repr(C)]
@@ -39,8 +42,16 @@ pub struct TransformConstants {
     pub camera_pos: [f32; 4],
     pub ambient_color: [f32; 4],
     pub light_color: [f32; 4],
+    /// World-space plane (xyz normal, w offset); geometry where
+    /// `dot(plane.xyz, p) + plane.w < 0` is clipped
+    pub clip_plane: [f32; 4],
+    /// x: alpha cutoff for masked materials (0 never discards)
+    pub material_params: [f32; 4],
 }
 
+/// Clip plane value that never clips anything
+pub const NO_CLIP_PLANE: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
 impl Default for TransformConstants {
     fn default() -> Self {
         Self {
@@ -51,6 +62,58 @@ impl Default for TransformConstants {
             camera_pos: [0.0, 5.0, 10.0, 1.0],
             ambient_color: [0.2, 0.25, 0.3, 1.0],
             light_color: [1.0, 0.95, 0.9, 1.0],
+            clip_plane: NO_CLIP_PLANE,
+            material_params: [0.0; 4],
+        }
+    }
+}
+
+impl TransformConstants {
+    /// Set the clip plane, or disable clipping with None
+    pub fn set_clip_plane(&mut self, plane: Option<Vec4>) {
+        self.clip_plane = plane.map_or(NO_CLIP_PLANE, |p| p.to_array());
+    }
+
+    /// Set the world matrix and clip plane for drawing `object`
+    ///
+    /// `global` is the scene's clip plane; the object's override decides
+    /// whether it applies.
+    pub fn set_object(&mut self, object: &Object3D, global: Option<Vec4>) {
+        self.world = object.transform.matrix().to_cols_array_2d();
+        self.set_clip_plane(object.clip_plane.resolve(global));
+    }
+
+    /// Set the per-material values read by the pixel shader
+    pub fn set_material(&mut self, material: &Material) {
+        self.material_params[0] = material.alpha_mode.cutoff();
+    }
+}
+
+/// Per-object clip plane override
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ClipPlane {
+    /// Use the global plane
+    #[default]
+    Global,
+    /// Never clip this object
+    Disabled,
+    /// Clip this object against its own world-space plane
+    Plane(Vec4),
+}
+
+impl ClipPlane {
+    /// Plane through `point` keeping the side `normal` points to
+    pub fn equation(point: Vec3, normal: Vec3) -> Vec4 {
+        let normal = normal.normalize();
+        normal.extend(-normal.dot(point))
+    }
+
+    /// Plane to use given the global one
+    pub fn resolve(self, global: Option<Vec4>) -> Option<Vec4> {
+        match self {
+            ClipPlane::Global => global,
+            ClipPlane::Disabled => None,
+            ClipPlane::Plane(plane) => Some(plane),
         }
     }
 }
@@ -164,6 +227,49 @@ impl Mesh3D {
         Self { vertices, indices }
     }
     
+    /// Create a terrain mesh from a grid of heights
+    ///
+    /// `heights` is row-major, `width` vertices along X by `depth` along Z,
+    /// spaced `cell_size` apart and centered on the origin. `color_fn` gets
+    /// each vertex's height and normal.
+    pub fn heightfield(
+        heights: &[f32],
+        width: u32,
+        depth: u32,
+        cell_size: f32,
+        color_fn: impl Fn(f32, Vec3) -> Color,
+    ) -> Self {
+        assert_eq!(heights.len(), (width * depth) as usize, "heightfield size mismatch");
+        let origin = Vec3::new(
+            -((width.max(1) - 1) as f32) * cell_size * 0.5,
+            0.0,
+            -((depth.max(1) - 1) as f32) * cell_size * 0.5,
+        );
+
+        let mut vertices = Vec::with_capacity(heights.len());
+        for z in 0..depth {
+            for x in 0..width {
+                let height = heights[(z * width + x) as usize];
+                let pos = origin + Vec3::new(x as f32 * cell_size, height, z as f32 * cell_size);
+                let normal = heightfield_normal(heights, width, depth, x, z, cell_size);
+                vertices.push(Vertex3D::new(pos, normal, color_fn(height, normal)));
+            }
+        }
+
+        let mut indices = Vec::with_capacity((width.saturating_sub(1) * depth.saturating_sub(1) * 6) as usize);
+        for z in 0..depth.saturating_sub(1) {
+            for x in 0..width.saturating_sub(1) {
+                let i00 = z * width + x;
+                let i10 = i00 + 1;
+                let i01 = i00 + width;
+                let i11 = i01 + 1;
+                indices.extend_from_slice(&[i00, i01, i11, i00, i11, i10]);
+            }
+        }
+
+        Self { vertices, indices }
+    }
+    
     /// Create a cylinder mesh
     pub fn cylinder(radius: f32, height: f32, segments: u32, color: Color) -> Self {
         let mut vertices = Vec::new();
@@ -337,7 +443,18 @@ impl Mesh3D {
     }
 }
 
+/// Normal of a heightfield vertex from central differences (one-sided at edges)
+pub(crate) fn heightfield_normal(heights: &[f32], width: u32, depth: u32, x: u32, z: u32, cell_size: f32) -> Vec3 {
+    let h = |x: u32, z: u32| heights[(z * width + x) as usize];
+    let (x0, x1) = (x.saturating_sub(1), (x + 1).min(width - 1));
+    let (z0, z1) = (z.saturating_sub(1), (z + 1).min(depth - 1));
+    let dx = (h(x1, z) - h(x0, z)) / ((x1 - x0).max(1) as f32 * cell_size);
+    let dz = (h(x, z1) - h(x, z0)) / ((z1 - z0).max(1) as f32 * cell_size);
+    Vec3::new(-dx, 1.0, -dz).normalize()
+}
+
 /// Camera for 3D rendering
+#[derive(Clone, Debug)]
 pub struct Camera3D {
     pub position: Vec3,
     pub target: Vec3,
@@ -346,6 +463,8 @@ pub struct Camera3D {
     pub aspect: f32,
     pub near: f32,
     pub far: f32,
+    /// Map far to depth 0 and near to 1 (pair with `DepthMode::Reversed`)
+    pub reverse_z: bool,
 }
 
 impl Camera3D {
@@ -358,18 +477,82 @@ impl Camera3D {
             aspect,
             near: 0.1,
             far: 100.0,
+            reverse_z: false,
         }
     }
+
+    /// Enable or disable reverse-Z depth
+    pub fn with_reverse_z(mut self, reverse_z: bool) -> Self {
+        self.reverse_z = reverse_z;
+        self
+    }
     
     pub fn view_matrix(&self) -> Mat4 {
         Mat4::look_at_rh(self.position, self.target, self.up)
     }
     
     pub fn projection_matrix(&self) -> Mat4 {
-        Mat4::perspective_rh(self.fov, self.aspect, self.near, self.far)
+        if self.reverse_z {
+            // Swapping the planes maps near to 1 and far to 0
+            Mat4::perspective_rh(self.fov, self.aspect, self.far, self.near)
+        } else {
+            Mat4::perspective_rh(self.fov, self.aspect, self.near, self.far)
+        }
+    }
+
+    /// Depth test matching this camera's projection
+    pub fn depth_mode(&self) -> DepthMode {
+        if self.reverse_z { DepthMode::Reversed } else { DepthMode::Standard }
+    }
+
+    /// Value to clear the depth buffer to before drawing with this camera
+    pub fn depth_clear_value(&self) -> f32 {
+        self.depth_mode().clear_value()
+    }
+
+    /// View frustum for culling
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(self.projection_matrix() * self.view_matrix())
+    }
+
+    /// Copy of this camera with the aspect ratio of `viewport`
+    pub fn for_viewport(&self, viewport: Rect) -> Self {
+        Self {
+            aspect: viewport.width / viewport.height.max(1.0),
+            ..self.clone()
+        }
     }
 }
 
+/// Split `area` into viewports for `count` players
+///
+/// Two players sit side by side; three or four share a 2x2 grid (the
+/// fourth cell stays empty for three). Edges are rounded to whole pixels.
+pub fn splitscreen_layout(count: usize, area: Rect) -> Vec<Rect> {
+    let (columns, rows) = match count {
+        0 => return Vec::new(),
+        1 => (1, 1),
+        2 => (2, 1),
+        3 | 4 => (2, 2),
+        n => {
+            let columns = (n as f32).sqrt().ceil() as usize;
+            (columns, n.div_ceil(columns))
+        }
+    };
+    let edge = |start: f32, size: f32, i: usize, n: usize| (start + size * i as f32 / n as f32).round();
+
+    (0..count)
+        .map(|i| {
+            let (column, row) = (i % columns, i / columns);
+            let x0 = edge(area.x, area.width, column, columns);
+            let x1 = edge(area.x, area.width, column + 1, columns);
+            let y0 = edge(area.y, area.height, row, rows);
+            let y1 = edge(area.y, area.height, row + 1, rows);
+            Rect::new(x0, y0, x1 - x0, y1 - y0)
+        })
+        .collect()
+}
+
 /// Transform for 3D objects
 #[derive(Clone, Copy, Debug)]
 pub struct Transform3D {
@@ -419,11 +602,18 @@ impl Transform3D {
 pub struct Object3D {
     pub mesh: Mesh3D,
     pub transform: Transform3D,
+    pub clip_plane: ClipPlane,
 }
 
 impl Object3D {
     pub fn new(mesh: Mesh3D, transform: Transform3D) -> Self {
-        Self { mesh, transform }
+        Self { mesh, transform, clip_plane: ClipPlane::Global }
+    }
+
+    /// Override the global clip plane for this object
+    pub fn with_clip_plane(mut self, clip_plane: ClipPlane) -> Self {
+        self.clip_plane = clip_plane;
+        self
     }
     
     pub fn cube(size: f32, color: Color, position: Vec3) -> Self {
@@ -474,6 +664,8 @@ cbuffer TransformConstants : register(b0)
     float4 CameraPos;
     float4 AmbientColor;
     float4 LightColor;
+    float4 ClipPlane;
+    float4 MaterialParams;
 };
 
 struct VSInput
@@ -489,6 +681,8 @@ struct PSInput
     float3 WorldPos : TEXCOORD0;
     float3 Normal : TEXCOORD1;
     float4 Color : COLOR;
+    // Pixel shaders don't read it, so their inputs leave it out
+    float ClipDistance : SV_ClipDistance0;
 };
 
 PSInput VSMain(VSInput input)
@@ -502,6 +696,7 @@ PSInput VSMain(VSInput input)
     output.WorldPos = worldPos.xyz;
     output.Normal = normalize(mul(float4(input.Normal, 0.0), World).xyz);
     output.Color = input.Color;
+    output.ClipDistance = dot(worldPos, ClipPlane);
     
     return output;
 }
@@ -517,6 +712,8 @@ cbuffer TransformConstants : register(b0)
     float4 CameraPos;
     float4 AmbientColor;
     float4 LightColor;
+    float4 ClipPlane;
+    float4 MaterialParams;
 };
 
 struct PSInput
@@ -529,6 +726,10 @@ struct PSInput
 
 float4 PSMain(PSInput input) : SV_TARGET
 {
+    // Masked materials discard below the cutoff
+    if (MaterialParams.x > 0.0)
+        clip(input.Color.a - MaterialParams.x);
+
     float3 normal = normalize(input.Normal);
     float3 lightDir = normalize(LightDir.xyz);
     
@@ -551,3 +752,115 @@ float4 PSMain(PSInput input) : SV_TARGET
 }
 "#;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clip distance the vertex shader computes for a local-space position
+    fn clip_distance(constants: &TransformConstants, position: [f32; 3]) -> f32 {
+        let world = Mat4::from_cols_array_2d(&constants.world) * Vec3::from(position).extend(1.0);
+        Vec4::from(constants.clip_plane).dot(world)
+    }
+
+    #[test]
+    fn equation_keeps_the_side_the_normal_points_to() {
+        let plane = ClipPlane::equation(Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 3.0, 0.0));
+        assert_eq!(plane, Vec4::new(0.0, 1.0, 0.0, -2.0));
+        assert!(plane.dot(Vec4::new(5.0, 3.0, -1.0, 1.0)) > 0.0);
+        assert!(plane.dot(Vec4::new(5.0, 1.0, -1.0, 1.0)) < 0.0);
+    }
+
+    #[test]
+    fn objects_resolve_the_global_plane() {
+        let global = Some(Vec4::new(1.0, 0.0, 0.0, 0.0));
+        let own = Vec4::new(0.0, 0.0, 1.0, 0.5);
+        assert_eq!(ClipPlane::Global.resolve(global), global);
+        assert_eq!(ClipPlane::Global.resolve(None), None);
+        assert_eq!(ClipPlane::Disabled.resolve(global), None);
+        assert_eq!(ClipPlane::Plane(own).resolve(global), Some(own));
+        assert_eq!(ClipPlane::Plane(own).resolve(None), Some(own));
+
+        let mut constants = TransformConstants::default();
+        let object = Object3D::cube(1.0, Color::WHITE, Vec3::ZERO).with_clip_plane(ClipPlane::Disabled);
+        constants.set_object(&object, global);
+        assert_eq!(constants.clip_plane, NO_CLIP_PLANE);
+        constants.set_object(&object.with_clip_plane(ClipPlane::Plane(own)), global);
+        assert_eq!(constants.clip_plane, own.to_array());
+    }
+
+    #[test]
+    fn splitscreen_halves_and_grids_cover_the_area() {
+        let area = Rect::new(10.0, 20.0, 1280.0, 720.0);
+        assert!(splitscreen_layout(0, area).is_empty());
+        assert_eq!(splitscreen_layout(1, area), vec![area]);
+
+        let halves = splitscreen_layout(2, area);
+        assert_eq!(halves, vec![Rect::new(10.0, 20.0, 640.0, 720.0), Rect::new(650.0, 20.0, 640.0, 720.0)]);
+        let aspect = Camera3D::new(Vec3::Z, Vec3::ZERO, 16.0 / 9.0).for_viewport(halves[1]).aspect;
+        assert!((aspect - 640.0 / 720.0).abs() < 1e-6);
+
+        // Three players leave the fourth cell empty
+        let grid = splitscreen_layout(3, area);
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid[2], Rect::new(10.0, 380.0, 640.0, 360.0));
+        assert_eq!(splitscreen_layout(4, area)[3], Rect::new(650.0, 380.0, 640.0, 360.0));
+    }
+
+    #[test]
+    fn odd_sizes_round_to_whole_pixels_without_gaps() {
+        let area = Rect::new(0.0, 0.0, 101.0, 51.0);
+        for count in 1..=9 {
+            let views = splitscreen_layout(count, area);
+            assert_eq!(views.len(), count);
+            let covered: f32 = views.iter().map(|v| v.width * v.height).sum();
+            let full_grid = count == 1 || count == 2 || count == 4 || count == 6 || count == 9;
+            if full_grid {
+                assert_eq!(covered, 101.0 * 51.0, "{} views", count);
+            }
+            for view in &views {
+                assert_eq!(view.x.fract() + view.y.fract() + view.width.fract() + view.height.fract(), 0.0);
+                assert!(view.x + view.width <= 101.0 && view.y + view.height <= 51.0);
+            }
+        }
+    }
+
+    #[test]
+    fn plane_through_the_center_clips_half_a_sphere() {
+        let center = Vec3::new(3.0, 1.0, -2.0);
+        let sphere = Object3D::sphere(1.0, Color::WHITE, center);
+        let mut constants = TransformConstants::default();
+        constants.set_object(&sphere, Some(ClipPlane::equation(center, Vec3::Y)));
+
+        // The cut edge is the equator: kept vertices are the upper hemisphere
+        for vertex in &sphere.mesh.vertices {
+            let distance = clip_distance(&constants, vertex.position);
+            let expected = vertex.position[1];
+            assert!((distance - expected).abs() < 1e-5, "{:?} is {} from the plane", vertex.position, distance);
+        }
+        let kept = sphere.mesh.vertices.iter().filter(|v| clip_distance(&constants, v.position) > 1e-5).count();
+        let clipped = sphere.mesh.vertices.iter().filter(|v| clip_distance(&constants, v.position) < -1e-5).count();
+        assert_eq!(kept, clipped);
+
+        // Nothing is clipped without a plane
+        constants.set_object(&sphere, None);
+        assert!(sphere.mesh.vertices.iter().all(|v| clip_distance(&constants, v.position) > 0.0));
+    }
+
+    #[test]
+    fn slope_normals_match_at_edges_and_inside() {
+        let (width, depth, cell_size) = (5, 4, 2.0);
+        // h = 0.5 * world x
+        let heights: Vec<f32> = (0..width * depth).map(|i| 0.5 * (i % width) as f32 * cell_size).collect();
+        let expected = Vec3::new(-0.5, 1.0, 0.0).normalize();
+
+        for z in 0..depth {
+            for x in 0..width {
+                let normal = heightfield_normal(&heights, width, depth, x, z, cell_size);
+                assert!(normal.abs_diff_eq(expected, 1e-6), "({}, {}): {:?}", x, z, normal);
+            }
+        }
+        let mesh = Mesh3D::heightfield(&heights, width, depth, cell_size, |_, _| Color::WHITE);
+        assert!(mesh.vertices.iter().all(|v| Vec3::from(v.normal).abs_diff_eq(expected, 1e-6)));
+    }
+}
@@ -1,6 +1,6 @@
 //! GPU Resources - simplified resource management
 
-use crate::dx12::{Device, Buffer, BufferDesc, BufferUsage, VertexBuffer, IndexBuffer, Texture, TextureDesc, Dx12Result};
+use crate::dx12::{Device, Buffer, BufferDesc, BufferUsage, VertexBuffer, IndexBuffer, Texture, TextureDesc, Dx12Result, PipelineOptions};
 use crate::math::{Color, Vec2, Vec3};
 
 /// A GPU buffer with automatic management
@@ -196,6 +196,57 @@ impl GpuMesh {
     }
 }
 
+/// How a material's alpha is used
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AlphaMode {
+    /// Alpha is ignored
+    #[default]
+    Opaque,
+    /// Pixels with alpha below `cutoff` are discarded (foliage, fences)
+    Mask { cutoff: f32 },
+    /// Blended over what is behind, drawn after opaque geometry
+    Blend,
+}
+
+impl AlphaMode {
+    /// Alpha cutoff for the pixel shader (0 never discards)
+    pub fn cutoff(&self) -> f32 {
+        match self {
+            AlphaMode::Mask { cutoff } => cutoff.max(1e-4),
+            AlphaMode::Opaque | AlphaMode::Blend => 0.0,
+        }
+    }
+
+    /// Queue the material is drawn in
+    ///
+    /// Masked materials stay opaque: they write depth and need no sorting.
+    pub fn queue(&self) -> RenderQueue {
+        match self {
+            AlphaMode::Blend => RenderQueue::Transparent,
+            AlphaMode::Opaque | AlphaMode::Mask { .. } => RenderQueue::Opaque,
+        }
+    }
+
+    /// Pipeline state for this mode on top of `base`
+    ///
+    /// Masked materials use alpha-to-coverage when `sample_count > 1`, so
+    /// the pipeline must be looked up with the options this returns.
+    pub fn pipeline_options(&self, base: PipelineOptions, sample_count: u32) -> PipelineOptions {
+        PipelineOptions {
+            alpha_blend: *self == AlphaMode::Blend,
+            alpha_to_coverage: matches!(self, AlphaMode::Mask { .. }) && sample_count > 1,
+            ..base
+        }
+    }
+}
+
+/// Draw queue, in drawing order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RenderQueue {
+    Opaque,
+    Transparent,
+}
+
 /// Material properties for rendering
 #[derive(Debug, Clone)]
 pub struct Material {
@@ -204,6 +255,7 @@ pub struct Material {
     pub metallic: f32,
     pub roughness: f32,
     pub emissive: Color,
+    pub alpha_mode: AlphaMode,
 }
 
 impl Default for Material {
@@ -214,6 +266,7 @@ impl Default for Material {
             metallic: 0.0,
             roughness: 0.5,
             emissive: Color::BLACK,
+            alpha_mode: AlphaMode::Opaque,
         }
     }
 }
@@ -244,4 +297,66 @@ impl Material {
         self.roughness = roughness;
         self
     }
+
+    /// Set how alpha is used
+    pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dx12::DepthMode;
+
+    const MASK: AlphaMode = AlphaMode::Mask { cutoff: 0.5 };
+
+    fn base() -> PipelineOptions {
+        PipelineOptions { depth: DepthMode::Reversed, ..Default::default() }
+    }
+
+    #[test]
+    fn masks_stay_in_the_opaque_queue_with_depth() {
+        let material = Material::new("leaves").with_alpha_mode(MASK);
+        assert_eq!(material.alpha_mode.queue(), RenderQueue::Opaque);
+        assert_eq!(AlphaMode::Opaque.queue(), RenderQueue::Opaque);
+        assert_eq!(AlphaMode::Blend.queue(), RenderQueue::Transparent);
+        assert!(RenderQueue::Opaque < RenderQueue::Transparent);
+
+        // Masks keep the base depth test and write depth like opaque geometry
+        for samples in [1, 4] {
+            let options = MASK.pipeline_options(base(), samples);
+            assert_eq!(options.depth, DepthMode::Reversed);
+            assert!(!options.alpha_blend);
+        }
+        assert_eq!(MASK.cutoff(), 0.5);
+        assert!(AlphaMode::Mask { cutoff: 0.0 }.cutoff() > 0.0);
+        assert_eq!(AlphaMode::Blend.cutoff(), 0.0);
+    }
+
+    #[test]
+    fn alpha_to_coverage_only_for_multisampled_masks() {
+        for mode in [AlphaMode::Opaque, MASK, AlphaMode::Blend] {
+            for samples in [1, 2, 4] {
+                let options = mode.pipeline_options(base(), samples);
+                assert_eq!(options.alpha_to_coverage, mode == MASK && samples > 1, "{:?} x{}", mode, samples);
+                assert_eq!(options.alpha_blend, mode == AlphaMode::Blend);
+            }
+        }
+    }
+
+    #[test]
+    fn each_mode_keys_its_own_pipeline() {
+        let opaque = AlphaMode::Opaque.pipeline_options(base(), 4);
+        let mask = MASK.pipeline_options(base(), 4);
+        let blend = AlphaMode::Blend.pipeline_options(base(), 4);
+        assert_ne!(opaque, mask);
+        assert_ne!(opaque, blend);
+        assert_ne!(mask, blend);
+
+        // Without MSAA a mask needs no pipeline of its own
+        assert_eq!(MASK.pipeline_options(base(), 1), AlphaMode::Opaque.pipeline_options(base(), 1));
+        assert_eq!(opaque, base());
+    }
 }
@@ -0,0 +1,346 @@
+//! Chunked heightfield terrain
+//!
+//! A large heightfield is split into square chunks, each with its own mesh
+//! and bounding box for frustum culling. Chunks further from the camera use
+//! coarser meshes (every 2nd, 4th, ... vertex). Where a chunk borders a
+//! coarser one, its edge vertices are moved onto the coarser edge so the
+//! meshes meet without cracks.
+
+use super::renderer3d::{heightfield_normal, Mesh3D, Vertex3D};
+use crate::math::{Aabb, Color, Frustum, Vec3};
+
+/// Terrain layout and LOD settings
+#[derive(Debug, Clone)]
+pub struct TerrainConfig {
+    /// Quads per chunk side (a power of two)
+    pub chunk_size: u32,
+    /// Distance between height samples
+    pub cell_size: f32,
+    /// Coarsest LOD level (level n uses every 2^n-th vertex)
+    pub max_lod: u32,
+    /// Camera distance covered by each LOD level
+    pub lod_distance: f32,
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 32,
+            cell_size: 1.0,
+            max_lod: 3,
+            lod_distance: 64.0,
+        }
+    }
+}
+
+/// Neighbor order used for stitching: -X, +X, -Z, +Z
+const WEST: usize = 0;
+const EAST: usize = 1;
+const NORTH: usize = 2;
+const SOUTH: usize = 3;
+
+/// One chunk of a terrain
+pub struct TerrainChunk {
+    /// Chunk coordinates (in chunks)
+    pub coord: (u32, u32),
+    /// World-space bounds (full resolution)
+    pub bounds: Aabb,
+    /// Current LOD level
+    pub lod: u32,
+    /// Mesh at the current LOD, in world space
+    pub mesh: Mesh3D,
+    /// Vertex range covered: x0..=x1, z0..=z1
+    range: (u32, u32, u32, u32),
+    /// LOD and neighbor LODs the mesh was built for
+    built: Option<(u32, [u32; 4])>,
+}
+
+type ColorFn = Box<dyn Fn(f32, Vec3) -> Color + Send + Sync>;
+
+/// Heightfield terrain split into LOD chunks
+pub struct Terrain {
+    heights: Vec<f32>,
+    width: u32,
+    depth: u32,
+    config: TerrainConfig,
+    origin: Vec3,
+    chunks_x: u32,
+    chunks_z: u32,
+    chunks: Vec<TerrainChunk>,
+    color_fn: ColorFn,
+}
+
+/// Sample positions from `start` to `end` (inclusive) every `step`
+fn samples(start: u32, end: u32, step: u32) -> Vec<u32> {
+    let mut positions: Vec<u32> = (start..end).step_by(step as usize).collect();
+    positions.push(end);
+    positions
+}
+
+impl Terrain {
+    /// Create a terrain from a row-major grid of `width` x `depth` heights
+    ///
+    /// Like `Mesh3D::heightfield`, the terrain is centered on the origin.
+    /// Call `update` before drawing to build the chunk meshes.
+    pub fn new<F>(heights: Vec<f32>, width: u32, depth: u32, config: TerrainConfig, color_fn: F) -> Self
+    where
+        F: Fn(f32, Vec3) -> Color + Send + Sync + 'static,
+    {
+        assert_eq!(heights.len(), (width * depth) as usize, "heightfield size mismatch");
+        assert!(width >= 2 && depth >= 2, "terrain needs at least 2x2 heights");
+        assert!(config.chunk_size.is_power_of_two(), "chunk_size must be a power of two");
+
+        let origin = Vec3::new(
+            -((width - 1) as f32) * config.cell_size * 0.5,
+            0.0,
+            -((depth - 1) as f32) * config.cell_size * 0.5,
+        );
+        let chunks_x = (width - 1).div_ceil(config.chunk_size);
+        let chunks_z = (depth - 1).div_ceil(config.chunk_size);
+
+        let mut terrain = Self {
+            heights,
+            width,
+            depth,
+            config,
+            origin,
+            chunks_x,
+            chunks_z,
+            chunks: Vec::with_capacity((chunks_x * chunks_z) as usize),
+            color_fn: Box::new(color_fn),
+        };
+
+        let size = terrain.config.chunk_size;
+        for cz in 0..chunks_z {
+            for cx in 0..chunks_x {
+                let range = (cx * size, (cx * size + size).min(width - 1), cz * size, (cz * size + size).min(depth - 1));
+                let bounds = terrain.chunk_bounds(range);
+                terrain.chunks.push(TerrainChunk {
+                    coord: (cx, cz),
+                    bounds,
+                    lod: 0,
+                    mesh: Mesh3D { vertices: Vec::new(), indices: Vec::new() },
+                    range,
+                    built: None,
+                });
+            }
+        }
+        terrain
+    }
+
+    /// Create a terrain by sampling `height_fn(x, z)` for every grid vertex
+    pub fn from_fn<H, F>(width: u32, depth: u32, config: TerrainConfig, height_fn: H, color_fn: F) -> Self
+    where
+        H: Fn(u32, u32) -> f32,
+        F: Fn(f32, Vec3) -> Color + Send + Sync + 'static,
+    {
+        let heights = (0..depth).flat_map(|z| (0..width).map(move |x| (x, z))).map(|(x, z)| height_fn(x, z)).collect();
+        Self::new(heights, width, depth, config, color_fn)
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &TerrainConfig {
+        &self.config
+    }
+
+    /// Get all chunks
+    pub fn chunks(&self) -> &[TerrainChunk] {
+        &self.chunks
+    }
+
+    /// Get the chunks that intersect a frustum
+    pub fn visible_chunks<'a>(&'a self, frustum: &'a Frustum) -> impl Iterator<Item = &'a TerrainChunk> + 'a {
+        self.chunks.iter().filter(|chunk| frustum.intersects_aabb(&chunk.bounds))
+    }
+
+    /// Terrain height at a world position (bilinear), None outside the terrain
+    pub fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        let gx = (x - self.origin.x) / self.config.cell_size;
+        let gz = (z - self.origin.z) / self.config.cell_size;
+        if gx < 0.0 || gz < 0.0 || gx > (self.width - 1) as f32 || gz > (self.depth - 1) as f32 {
+            return None;
+        }
+        let (x0, z0) = ((gx as u32).min(self.width - 2), (gz as u32).min(self.depth - 2));
+        let (tx, tz) = (gx - x0 as f32, gz - z0 as f32);
+        let top = self.height(x0, z0) * (1.0 - tx) + self.height(x0 + 1, z0) * tx;
+        let bottom = self.height(x0, z0 + 1) * (1.0 - tx) + self.height(x0 + 1, z0 + 1) * tx;
+        Some(top * (1.0 - tz) + bottom * tz)
+    }
+
+    /// Pick LODs for the camera position and rebuild chunks that changed
+    ///
+    /// Returns the number of chunks rebuilt.
+    pub fn update(&mut self, camera: Vec3) -> usize {
+        for chunk in &mut self.chunks {
+            let distance = chunk.bounds.distance_to(camera);
+            chunk.lod = ((distance / self.config.lod_distance) as u32).min(self.config.max_lod);
+        }
+
+        let mut rebuilt = 0;
+        for index in 0..self.chunks.len() {
+            let lod = self.chunks[index].lod;
+            let neighbors = self.neighbor_lods(index);
+            if self.chunks[index].built == Some((lod, neighbors)) {
+                continue;
+            }
+            let mesh = self.build_chunk(self.chunks[index].range, lod, neighbors);
+            let chunk = &mut self.chunks[index];
+            chunk.mesh = mesh;
+            chunk.built = Some((lod, neighbors));
+            rebuilt += 1;
+        }
+        rebuilt
+    }
+
+    fn height(&self, x: u32, z: u32) -> f32 {
+        self.heights[(z * self.width + x) as usize]
+    }
+
+    fn position(&self, x: u32, z: u32, height: f32) -> Vec3 {
+        self.origin + Vec3::new(x as f32 * self.config.cell_size, height, z as f32 * self.config.cell_size)
+    }
+
+    fn chunk_bounds(&self, (x0, x1, z0, z1): (u32, u32, u32, u32)) -> Aabb {
+        let (mut min_h, mut max_h) = (f32::MAX, f32::MIN);
+        for z in z0..=z1 {
+            for x in x0..=x1 {
+                let h = self.height(x, z);
+                min_h = min_h.min(h);
+                max_h = max_h.max(h);
+            }
+        }
+        let min = self.position(x0, z0, min_h);
+        let max = self.position(x1, z1, max_h);
+        Aabb::new(min, max)
+    }
+
+    /// LODs of the four neighbors (own LOD where there is no neighbor)
+    fn neighbor_lods(&self, index: usize) -> [u32; 4] {
+        let (cx, cz) = self.chunks[index].coord;
+        let own = self.chunks[index].lod;
+        let lod_at = |x: Option<u32>, z: Option<u32>| match (x, z) {
+            (Some(x), Some(z)) if x < self.chunks_x && z < self.chunks_z => self.chunks[(z * self.chunks_x + x) as usize].lod,
+            _ => own,
+        };
+        [
+            lod_at(cx.checked_sub(1), Some(cz)),
+            lod_at(Some(cx + 1), Some(cz)),
+            lod_at(Some(cx), cz.checked_sub(1)),
+            lod_at(Some(cx), Some(cz + 1)),
+        ]
+    }
+
+    /// Height of an edge vertex, snapped onto a coarser neighbor's edge
+    ///
+    /// `along` is the position along the edge and `at` maps it to grid
+    /// coordinates.
+    fn edge_height(&self, along: u32, start: u32, end: u32, step: u32, at: impl Fn(u32) -> (u32, u32)) -> f32 {
+        let (x, z) = at(along);
+        if step <= 1 {
+            return self.height(x, z);
+        }
+        let coarse = samples(start, end, step);
+        let i = coarse.partition_point(|&p| p <= along).saturating_sub(1).min(coarse.len() - 2);
+        let (a, b) = (coarse[i], coarse[i + 1]);
+        let t = (along - a) as f32 / (b - a) as f32;
+        let (ax, az) = at(a);
+        let (bx, bz) = at(b);
+        self.height(ax, az) * (1.0 - t) + self.height(bx, bz) * t
+    }
+
+    fn build_chunk(&self, (x0, x1, z0, z1): (u32, u32, u32, u32), lod: u32, neighbors: [u32; 4]) -> Mesh3D {
+        let step = 1 << lod;
+        let xs = samples(x0, x1, step);
+        let zs = samples(z0, z1, step);
+        // Only edges next to a coarser chunk need stitching
+        let coarser = |side: usize| if neighbors[side] > lod { 1 << neighbors[side] } else { 1 };
+
+        let mut vertices = Vec::with_capacity(xs.len() * zs.len());
+        for &z in &zs {
+            for &x in &xs {
+                let height = if x == x0 && coarser(WEST) > 1 {
+                    self.edge_height(z, z0, z1, coarser(WEST), |p| (x, p))
+                } else if x == x1 && coarser(EAST) > 1 {
+                    self.edge_height(z, z0, z1, coarser(EAST), |p| (x, p))
+                } else if z == z0 && coarser(NORTH) > 1 {
+                    self.edge_height(x, x0, x1, coarser(NORTH), |p| (p, z))
+                } else if z == z1 && coarser(SOUTH) > 1 {
+                    self.edge_height(x, x0, x1, coarser(SOUTH), |p| (p, z))
+                } else {
+                    self.height(x, z)
+                };
+                let normal = heightfield_normal(&self.heights, self.width, self.depth, x, z, self.config.cell_size);
+                vertices.push(Vertex3D::new(self.position(x, z, height), normal, (self.color_fn)(height, normal)));
+            }
+        }
+
+        let row = xs.len() as u32;
+        let mut indices = Vec::with_capacity((xs.len() - 1) * (zs.len() - 1) * 6);
+        for j in 0..zs.len() as u32 - 1 {
+            for i in 0..row - 1 {
+                let i00 = j * row + i;
+                let i10 = i00 + 1;
+                let i01 = i00 + row;
+                let i11 = i01 + 1;
+                indices.extend_from_slice(&[i00, i01, i11, i00, i11, i10]);
+            }
+        }
+
+        Mesh3D { vertices, indices }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two chunks side by side along X over a bumpy, non-planar heightfield
+    fn two_chunks() -> Terrain {
+        let config = TerrainConfig { chunk_size: 8, ..Default::default() };
+        Terrain::from_fn(17, 9, config, |x, z| ((x * x * 7 + z * z * 13 + x * z * 3) % 11) as f32, |_, _| Color::WHITE)
+    }
+
+    /// Heights of the vertices on the chunk column at grid x, ordered by z
+    fn column(mesh: &Mesh3D, x: f32) -> Vec<(f32, f32)> {
+        let mut column: Vec<(f32, f32)> = mesh
+            .vertices
+            .iter()
+            .filter(|v| v.position[0] == x)
+            .map(|v| (v.position[2], v.position[1]))
+            .collect();
+        column.sort_by(|a, b| a.0.total_cmp(&b.0));
+        column
+    }
+
+    #[test]
+    fn lod_seams_share_edge_heights() {
+        let terrain = two_chunks();
+        let (west, east) = (terrain.chunks[0].range, terrain.chunks[1].range);
+        let fine = terrain.build_chunk(west, 0, [0, 1, 0, 0]);
+        let coarse = terrain.build_chunk(east, 1, [0, 1, 1, 1]);
+
+        let seam = terrain.position(west.1, 0, 0.0).x;
+        let fine_edge = column(&fine, seam);
+        let coarse_edge = column(&coarse, seam);
+        assert_eq!(fine_edge.len(), 9);
+        assert_eq!(coarse_edge.len(), 5);
+
+        // Vertices both meshes have sit at the same height
+        for &(z, height) in &coarse_edge {
+            let shared = fine_edge.iter().find(|v| v.0 == z).expect("coarse vertex missing from the fine edge");
+            assert_eq!(shared.1, height, "z = {}", z);
+        }
+        // The fine mesh's extra vertices lie on the coarse edge between them
+        for pair in coarse_edge.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            for &(z, height) in fine_edge.iter().filter(|v| v.0 > a.0 && v.0 < b.0) {
+                let t = (z - a.0) / (b.0 - a.0);
+                assert!((height - (a.1 + (b.1 - a.1) * t)).abs() < 1e-5, "crack at z = {}", z);
+            }
+        }
+
+        // Without the coarser neighbor the fine edge keeps its own heights
+        let unstitched = column(&terrain.build_chunk(west, 0, [0; 4]), seam);
+        assert!(unstitched.iter().zip(&fine_edge).any(|(a, b)| a.1 != b.1));
+    }
+}
@@ -0,0 +1,171 @@
+//! Tone mapping and HDR output encoding
+//!
+//! `ToneMapSettings` maps colors to output values on the CPU. `map_scene`
+//! takes linear scene colors, which may exceed 1.0, applies exposure and
+//! then tone maps them for SDR (ACES or Reinhard), scales them to scRGB or
+//! PQ-encodes them for HDR10. `map_display` places display-referred sRGB
+//! colors at the configured paper-white level on HDR outputs.
+//!
+//! Only clear colors go through this: `RenderFrame::clear` (including the
+//! letterbox bars) and `RenderFrame::clear_scene`. Drawn geometry is not
+//! tone mapped. There is no post-process pass, so pixel shaders write
+//! straight into the back buffer and any mapping is up to them.
+
+use crate::dx12::SwapChainFormat;
+use crate::math::{linear_to_srgb, srgb_to_linear, Color};
+
+/// scRGB reference white: 1.0 in an scRGB buffer is 80 nits
+pub const SCRGB_WHITE_NITS: f32 = 80.0;
+
+/// Peak luminance of the ST.2084 (PQ) curve in nits
+pub const PQ_MAX_NITS: f32 = 10000.0;
+
+/// Tone mapping curve used for the SDR fallback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMapOperator {
+    /// ACES filmic fit (Narkowicz)
+    #[default]
+    Aces,
+    /// Simple Reinhard x / (1 + x)
+    Reinhard,
+}
+
+/// Tone mapping controls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapSettings {
+    pub operator: ToneMapOperator,
+    /// Linear exposure multiplier applied to scene colors
+    pub exposure: f32,
+    /// Brightness of SDR white (UI, clear colors) on HDR displays, in nits
+    pub paper_white_nits: f32,
+}
+
+impl Default for ToneMapSettings {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapOperator::Aces,
+            exposure: 1.0,
+            paper_white_nits: 200.0,
+        }
+    }
+}
+
+impl ToneMapSettings {
+    /// Map a linear scene color to output values for the given format
+    pub fn map_scene(&self, linear: Color, format: SwapChainFormat) -> Color {
+        let exposed = [linear.r, linear.g, linear.b].map(|c| c.max(0.0) * self.exposure);
+        let rgb = match format {
+            SwapChainFormat::Sdr => exposed.map(|c| linear_to_srgb(self.curve(c))),
+            SwapChainFormat::Rgba16Float => exposed.map(|c| c * self.paper_white_nits / SCRGB_WHITE_NITS),
+            SwapChainFormat::Hdr10 => {
+                rec709_to_rec2020(exposed).map(|c| pq_encode(c * self.paper_white_nits))
+            }
+        };
+        Color::rgba(rgb[0], rgb[1], rgb[2], linear.a)
+    }
+
+    /// Map a display-referred (sRGB) color to output values for the given format
+    ///
+    /// SDR output returns the color unchanged.
+    pub fn map_display(&self, color: Color, format: SwapChainFormat) -> Color {
+        if format == SwapChainFormat::Sdr {
+            return color;
+        }
+        let linear = [color.r, color.g, color.b].map(srgb_to_linear);
+        let rgb = match format {
+            SwapChainFormat::Rgba16Float => linear.map(|c| c * self.paper_white_nits / SCRGB_WHITE_NITS),
+            _ => rec709_to_rec2020(linear).map(|c| pq_encode(c * self.paper_white_nits)),
+        };
+        Color::rgba(rgb[0], rgb[1], rgb[2], color.a)
+    }
+
+    fn curve(&self, x: f32) -> f32 {
+        match self.operator {
+            ToneMapOperator::Aces => aces_filmic(x),
+            ToneMapOperator::Reinhard => reinhard(x),
+        }
+    }
+}
+
+/// ACES filmic approximation (Krzysztof Narkowicz)
+pub fn aces_filmic(x: f32) -> f32 {
+    let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+    ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(0.0, 1.0)
+}
+
+/// Reinhard tone mapping
+pub fn reinhard(x: f32) -> f32 {
+    x / (1.0 + x)
+}
+
+/// ST.2084 (PQ) inverse EOTF: absolute nits to a 0-1 signal
+pub fn pq_encode(nits: f32) -> f32 {
+    let (m1, m2) = (0.159_301_76, 78.84375);
+    let (c1, c2, c3) = (0.8359375, 18.851_563, 18.6875);
+    let y = (nits / PQ_MAX_NITS).clamp(0.0, 1.0).powf(m1);
+    ((c1 + c2 * y) / (1.0 + c3 * y)).powf(m2)
+}
+
+fn rec709_to_rec2020(c: [f32; 3]) -> [f32; 3] {
+    [
+        0.627_403_9 * c[0] + 0.329_283_04 * c[1] + 0.043_313_07 * c[2],
+        0.069_097_29 * c[0] + 0.919_540_4 * c[1] + 0.011_362_32 * c[2],
+        0.016_391_44 * c[0] + 0.088_013_31 * c[1] + 0.895_595_24 * c[2],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Samples from 0 to 20 in steps of 1/64
+    fn inputs() -> impl Iterator<Item = f32> {
+        (0..=1280).map(|i| i as f32 / 64.0)
+    }
+
+    #[test]
+    fn pq_reference_points() {
+        assert!(pq_encode(0.0).abs() < 1e-6);
+        assert!((pq_encode(PQ_MAX_NITS) - 1.0).abs() < 1e-6);
+        assert!((pq_encode(100.0) - 0.508).abs() < 1e-3);
+        // Out-of-range input is clamped to the curve's range
+        assert_eq!(pq_encode(-5.0), pq_encode(0.0));
+        assert_eq!(pq_encode(2.0 * PQ_MAX_NITS), pq_encode(PQ_MAX_NITS));
+    }
+
+    #[test]
+    fn curves_are_monotonic() {
+        let values = |curve: fn(f32) -> f32| inputs().map(curve).collect::<Vec<_>>();
+        assert!(values(aces_filmic).windows(2).all(|w| w[1] >= w[0]));
+        assert!(values(reinhard).windows(2).all(|w| w[1] > w[0]));
+        assert!(inputs().map(|x| pq_encode(x * 500.0)).collect::<Vec<_>>().windows(2).all(|w| w[1] > w[0]));
+    }
+
+    #[test]
+    fn curves_keep_black_and_approach_white() {
+        assert_eq!(aces_filmic(0.0), 0.0);
+        assert_eq!(reinhard(0.0), 0.0);
+
+        // ACES saturates to white a little above 7
+        assert!(aces_filmic(7.0) < 1.0);
+        assert_eq!(aces_filmic(7.5), 1.0);
+        assert_eq!(aces_filmic(1000.0), 1.0);
+
+        // Reinhard maps 1 to half and only reaches white in the limit
+        assert_eq!(reinhard(1.0), 0.5);
+        assert!(reinhard(1000.0) < 1.0);
+        assert!(reinhard(1000.0) > 0.999);
+    }
+
+    #[test]
+    fn sdr_scene_white_follows_the_operator() {
+        let bright = Color::rgb(100.0, 100.0, 100.0);
+        let aces = ToneMapSettings::default();
+        let white = aces.map_scene(bright, SwapChainFormat::Sdr);
+        assert!([white.r, white.g, white.b].iter().all(|c| (c - 1.0).abs() < 1e-6), "{:?}", white);
+        assert_eq!(aces.map_scene(Color::BLACK, SwapChainFormat::Sdr), Color::BLACK);
+
+        let reinhard = ToneMapSettings { operator: ToneMapOperator::Reinhard, ..Default::default() };
+        assert!(reinhard.map_scene(bright, SwapChainFormat::Sdr).r < 1.0);
+    }
+}
@@ -0,0 +1,94 @@
+//! Content hashing
+//!
+//! XXH64, used for frame hashes, asset deduplication and archive checksums.
+//! Fast and stable across platforms and runs, but not cryptographic.
+
+const PRIME1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+fn round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME2)).rotate_left(31).wrapping_mul(PRIME1)
+}
+
+fn merge(acc: u64, value: u64) -> u64 {
+    (acc ^ round(0, value)).wrapping_mul(PRIME1).wrapping_add(PRIME4)
+}
+
+/// XXH64 of `data` with `seed`
+pub fn xxh64(data: &[u8], seed: u64) -> u64 {
+    let mut rest = data;
+    let mut hash = if data.len() >= 32 {
+        let mut v = [
+            seed.wrapping_add(PRIME1).wrapping_add(PRIME2),
+            seed.wrapping_add(PRIME2),
+            seed,
+            seed.wrapping_sub(PRIME1),
+        ];
+        while rest.len() >= 32 {
+            for (i, lane) in v.iter_mut().enumerate() {
+                *lane = round(*lane, read_u64(&rest[i * 8..]));
+            }
+            rest = &rest[32..];
+        }
+        let mut hash = v[0]
+            .rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18));
+        for lane in v {
+            hash = merge(hash, lane);
+        }
+        hash
+    } else {
+        seed.wrapping_add(PRIME5)
+    };
+
+    hash = hash.wrapping_add(data.len() as u64);
+    while rest.len() >= 8 {
+        hash ^= round(0, read_u64(rest));
+        hash = hash.rotate_left(27).wrapping_mul(PRIME1).wrapping_add(PRIME4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        hash ^= (read_u32(rest) as u64).wrapping_mul(PRIME1);
+        hash = hash.rotate_left(23).wrapping_mul(PRIME2).wrapping_add(PRIME3);
+        rest = &rest[4..];
+    }
+    for &byte in rest {
+        hash ^= (byte as u64).wrapping_mul(PRIME5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME3);
+    hash ^ (hash >> 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_vectors() {
+        assert_eq!(xxh64(b"", 0), 0xEF46_DB37_51D8_E999);
+        assert_eq!(xxh64(b"a", 0), 0xD24E_C4F1_A98C_6E5B);
+        assert_eq!(xxh64(b"abc", 0), 0x44BC_2CF5_AD77_0999);
+        assert_eq!(xxh64(b"xxhash", 0), 0x32DD_3895_2C4B_C720);
+        assert_eq!(xxh64(b"xxhash", 20141025), 0xB559_B98D_844E_0635);
+        // 39 bytes: one 32-byte stripe, a 4-byte word and 3 single bytes
+        assert_eq!(xxh64(b"Nobody inspects the spammish repetition", 0), 0xFBCE_A83C_8A37_8BF1);
+    }
+}
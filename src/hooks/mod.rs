@@ -1,7 +1,13 @@
 //! React-style hooks for EPICX
 //!
 //! Provides familiar React hooks for state management and side effects.
+//! Hooks that keep state between renders (currently `use_spring`) need the
+//! render to run inside `with_hooks`, which `Memo` and `FunctionalComponent`
+//! do; they are matched to the previous render's hooks by call order.
 
+use crate::core::ComponentId;
+use crate::i18n;
+use crate::math::{Spring, SpringValue};
 use parking_lot::RwLock;
 use std::any::Any;
 use std::cell::RefCell;
@@ -14,10 +20,13 @@ thread_local! {
 
 /// Internal hook state
 struct HookState {
-    current_component: Option<String>,
+    current_component: Option<ComponentId>,
     hook_index: usize,
-    states: HashMap<String, Vec<Arc<dyn Any + Send + Sync>>>,
-    effects: HashMap<String, Vec<EffectState>>,
+    /// Seconds since the current component's previous frame
+    delta_time: f32,
+    /// A hook of the current component asked for another render
+    rerender: bool,
+    states: HashMap<ComponentId, Vec<Arc<dyn Any + Send + Sync>>>,
 }
 
 impl HookState {
@@ -25,12 +34,73 @@ impl HookState {
         Self {
             current_component: None,
             hook_index: 0,
+            delta_time: 0.0,
+            rerender: false,
             states: HashMap::new(),
-            effects: HashMap::new(),
         }
     }
 }
 
+/// Run a component's render with hooks that persist across its renders
+///
+/// `delta_time` is what animating hooks advance by. Returns the render's
+/// result and whether a hook asked to be rendered again next frame (e.g. a
+/// spring that hasn't settled), which `Memo` treats as dirty.
+pub fn with_hooks<R>(component: ComponentId, delta_time: f32, render: impl FnOnce() -> R) -> (R, bool) {
+    // Components render other components, so save the outer scope
+    let outer = HOOK_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let outer = (state.current_component, state.hook_index, state.delta_time, state.rerender);
+        state.current_component = Some(component);
+        state.hook_index = 0;
+        state.delta_time = delta_time;
+        state.rerender = false;
+        outer
+    });
+    let result = render();
+    let rerender = HOOK_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let rerender = state.rerender;
+        (state.current_component, state.hook_index, state.delta_time, state.rerender) = outer;
+        rerender
+    });
+    (result, rerender)
+}
+
+/// Forget a component's hooks (call when it unmounts)
+pub fn drop_hooks(component: ComponentId) {
+    HOOK_STATE.with(|state| state.borrow_mut().states.remove(&component));
+}
+
+/// The current component's next hook slot, created by `init` on first use
+///
+/// Outside `with_hooks` (or if the hook order changed) a fresh value is
+/// returned. Also returns the frame's delta time, or None outside a render.
+fn use_slot<T: Send + Sync + 'static>(init: impl FnOnce() -> T) -> (Arc<T>, Option<f32>) {
+    HOOK_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let Some(component) = state.current_component else {
+            return (Arc::new(init()), None);
+        };
+        let (index, delta_time) = (state.hook_index, state.delta_time);
+        state.hook_index += 1;
+
+        let slots = state.states.entry(component).or_default();
+        if let Some(existing) = slots.get(index).and_then(|slot| Arc::clone(slot).downcast::<T>().ok()) {
+            return (existing, Some(delta_time));
+        }
+        let value = Arc::new(init());
+        slots.truncate(index);
+        slots.push(value.clone());
+        (value, Some(delta_time))
+    })
+}
+
+/// Ask for the current component to render again next frame
+fn request_render() {
+    HOOK_STATE.with(|state| state.borrow_mut().rerender = true);
+}
+
 struct EffectState {
     cleanup: Option<Box<dyn FnOnce() + Send>>,
     deps: Option<Vec<u64>>,
@@ -148,6 +218,163 @@ pub fn use_ref<T: Send + Sync + 'static>(initial: T) -> UseRef<T> {
     }
 }
 
+/// Spring animation hook state
+struct SpringState<T> {
+    spring: Spring,
+    value: T,
+    velocity: T,
+    target: T,
+    rest_delta: f32,
+    rest_speed: f32,
+}
+
+/// Spring hook - animates a value towards a target
+///
+/// Inside a render, `use_spring(target)` keeps the spring between renders,
+/// advances it by the frame's delta time and keeps the component dirty
+/// until it settles. Outside a render, call `update` once per frame and
+/// keep requesting frames while `is_animating` is true. Changing the target
+/// mid-flight keeps the current velocity, so interrupted animations stay
+/// smooth.
+#[derive(Clone)]
+pub struct UseSpring<T> {
+    state: Arc<RwLock<SpringState<T>>>,
+}
+
+impl<T: SpringValue> UseSpring<T> {
+    /// Use different spring parameters
+    pub fn with_spring(self, spring: Spring) -> Self {
+        self.state.write().spring = spring;
+        self
+    }
+
+    /// Distance and speed below which the spring counts as settled
+    pub fn with_rest_thresholds(self, delta: f32, speed: f32) -> Self {
+        {
+            let mut state = self.state.write();
+            state.rest_delta = delta;
+            state.rest_speed = speed;
+        }
+        self
+    }
+
+    /// Get the current value
+    pub fn value(&self) -> T {
+        self.state.read().value
+    }
+
+    /// Get the current velocity
+    pub fn velocity(&self) -> T {
+        self.state.read().velocity
+    }
+
+    /// Get the target
+    pub fn target(&self) -> T {
+        self.state.read().target
+    }
+
+    /// Animate towards a new target
+    pub fn set_target(&self, target: T) {
+        self.state.write().target = target;
+    }
+
+    /// Jump to a value and stop
+    pub fn snap_to(&self, value: T) {
+        let mut state = self.state.write();
+        state.value = value;
+        state.target = value;
+        state.velocity = T::from_components([0.0; 4]);
+    }
+
+    /// Advance by `dt` seconds and return the new value
+    ///
+    /// Snaps to the target once settled.
+    pub fn update(&self, dt: f32) -> T {
+        let mut state = self.state.write();
+        let (value, velocity) = state.spring.update(state.value, state.velocity, state.target, dt);
+        state.value = value;
+        state.velocity = velocity;
+        if settled(&state) {
+            state.value = state.target;
+            state.velocity = T::from_components([0.0; 4]);
+        }
+        state.value
+    }
+
+    /// Check if the value is still moving
+    pub fn is_animating(&self) -> bool {
+        !settled(&self.state.read())
+    }
+}
+
+fn settled<T: SpringValue>(state: &SpringState<T>) -> bool {
+    let (value, target, velocity) =
+        (state.value.to_components(), state.target.to_components(), state.velocity.to_components());
+    (0..4).all(|i| (value[i] - target[i]).abs() <= state.rest_delta && velocity[i].abs() <= state.rest_speed)
+}
+
+/// Create a spring hook animating towards `target`
+///
+/// The first render starts at rest on `target`.
+pub fn use_spring<T: SpringValue + Send + Sync + 'static>(target: T) -> UseSpring<T> {
+    let (state, delta_time) = use_slot(|| {
+        RwLock::new(SpringState {
+            spring: Spring::default(),
+            value: target,
+            velocity: T::from_components([0.0; 4]),
+            target,
+            rest_delta: 0.001,
+            rest_speed: 0.001,
+        })
+    });
+    let spring = UseSpring { state };
+    if let Some(dt) = delta_time {
+        spring.set_target(target);
+        spring.update(dt);
+        if spring.is_animating() {
+            request_render();
+        }
+    }
+    spring
+}
+
+/// Translation hook state
+pub struct UseTranslation {
+    version: u64,
+}
+
+impl UseTranslation {
+    /// Translate a key with the global catalog
+    pub fn t(&self, key: &str) -> String {
+        i18n::translate(key, &[])
+    }
+
+    /// Translate a key with `{name}` arguments
+    pub fn t_with(&self, key: &str, args: &[(&str, i18n::Arg)]) -> String {
+        i18n::translate(key, args)
+    }
+
+    /// Current language code
+    pub fn language(&self) -> String {
+        i18n::language()
+    }
+
+    /// Check if the language changed since the hook was created
+    pub fn changed(&self) -> bool {
+        i18n::language_atom().version() != self.version
+    }
+}
+
+/// Create a translation hook
+///
+/// To re-render automatically on a language change, wrap the component in
+/// `Memo` with `depends_on(i18n::language_atom())`.
+pub fn use_translation() -> UseTranslation {
+    UseTranslation {
+        version: i18n::language_atom().version(),
+    }
+}
+
 /// Reducer hook - similar to React's useReducer
 pub struct UseReducer<S, A> {
     state: Arc<RwLock<S>>,
@@ -192,3 +419,63 @@ pub fn use_context<T: Clone + Send + Sync + 'static>(
 ) -> Option<Arc<T>> {
     context.get::<T>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn springs_persist_across_renders() {
+        let component = ComponentId::new();
+        let render = |target: f32| with_hooks(component, FRAME, || use_spring(target).value());
+
+        assert_eq!(render(0.0), (0.0, false));
+        let (first, dirty) = render(10.0);
+        assert!(first > 0.0 && first < 10.0 && dirty);
+        let (second, _) = render(10.0);
+        assert!(second > first);
+
+        // Dirty until settled, then quiet
+        let mut frames = 0;
+        while render(10.0).1 {
+            frames += 1;
+            assert!(frames < 600, "spring never settled");
+        }
+        assert_eq!(render(10.0), (10.0, false));
+
+        // Forgotten hooks start over at rest
+        drop_hooks(component);
+        assert_eq!(render(3.0), (3.0, false));
+    }
+
+    #[test]
+    fn nested_components_keep_their_own_hooks() {
+        let (outer, inner) = (ComponentId::new(), ComponentId::new());
+        let render = |target: f32| {
+            with_hooks(outer, FRAME, || {
+                let (child, child_dirty) = with_hooks(inner, FRAME, || use_spring(Vec2::splat(target)).value());
+                (use_spring(-target).value(), child, child_dirty)
+            })
+        };
+
+        render(0.0);
+        let ((parent, child, child_dirty), dirty) = render(1.0);
+        assert!(parent < 0.0 && child.x > 0.0);
+        assert!(dirty && child_dirty);
+    }
+
+    #[test]
+    fn springs_outside_a_render_are_driven_by_hand() {
+        let spring = use_spring(5.0f32);
+        assert_eq!(spring.value(), 5.0);
+        assert!(!spring.is_animating());
+        spring.set_target(6.0);
+        assert!(spring.is_animating());
+        assert!(spring.update(FRAME) > 5.0);
+        // Nothing is kept between calls
+        assert_eq!(use_spring(5.0f32).value(), 5.0);
+    }
+}
@@ -0,0 +1,412 @@
+//! Localization - string catalogs with runtime language switching
+//!
+//! Each language is a simple key/value file:
+//!
+//! ```text
+//! # menu.en.lang
+//! menu.start = Start game
+//! score.one = {points} point
+//! score.other = {points} points
+//! ```
+//!
+//! Install a catalog with `set_catalog`, look strings up with `t!` and
+//! switch languages with `set_language`. Components that should re-render
+//! on a language change can depend on `language_atom()` (see `Memo`).
+//! Rendering non-Latin scripts still needs a font that covers them.
+
+use crate::core::Atom;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// Localization errors
+#[derive(Error, Debug)]
+pub enum I18nError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("Unknown language: {0}")]
+    UnknownLanguage(String),
+}
+
+pub type I18nResult<T> = Result<T, I18nError>;
+
+/// An argument interpolated into a translated string
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arg {
+    Number(f64),
+    Text(String),
+}
+
+impl std::fmt::Display for Arg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arg::Number(n) => write!(f, "{}", n),
+            Arg::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+macro_rules! number_arg {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for Arg {
+            fn from(value: $ty) -> Self {
+                Arg::Number(value as f64)
+            }
+        })*
+    };
+}
+
+number_arg!(i32, i64, u32, u64, usize, f32, f64);
+
+impl From<&str> for Arg {
+    fn from(value: &str) -> Self {
+        Arg::Text(value.to_string())
+    }
+}
+
+impl From<String> for Arg {
+    fn from(value: String) -> Self {
+        Arg::Text(value)
+    }
+}
+
+/// Plural category of a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    One,
+    Other,
+}
+
+impl PluralCategory {
+    fn suffix(self) -> &'static str {
+        match self {
+            PluralCategory::One => "one",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// Plural rule for a language code
+///
+/// English-style (only 1 is singular) unless the language is known to
+/// differ.
+pub fn plural_category(language: &str, n: f64) -> PluralCategory {
+    let base = language.split(['-', '_']).next().unwrap_or(language);
+    match base {
+        // No grammatical plural
+        "ja" | "zh" | "ko" | "vi" | "th" => PluralCategory::Other,
+        // 0 and 1 are singular
+        "fr" | "pt" => {
+            if n.abs() < 2.0 { PluralCategory::One } else { PluralCategory::Other }
+        }
+        _ => {
+            if n == 1.0 { PluralCategory::One } else { PluralCategory::Other }
+        }
+    }
+}
+
+/// Parse a `key = value` file; `#` starts a comment line
+pub fn parse(source: &str) -> I18nResult<BTreeMap<String, String>> {
+    let mut entries = BTreeMap::new();
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(I18nError::Parse { line: index + 1, message: "expected 'key = value'".to_string() });
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(I18nError::Parse { line: index + 1, message: "empty key".to_string() });
+        }
+        entries.insert(key.to_string(), value.trim().replace("\\n", "\n"));
+    }
+    Ok(entries)
+}
+
+/// Translations for several languages
+#[derive(Debug, Default)]
+pub struct Catalog {
+    languages: BTreeMap<String, BTreeMap<String, String>>,
+    language: String,
+    fallback: Option<String>,
+    /// Missing (language, key) pairs already logged
+    reported: Mutex<HashSet<(String, String)>>,
+}
+
+impl Catalog {
+    /// Create an empty catalog
+    pub fn new(language: impl Into<String>) -> Self {
+        Self { language: language.into(), ..Default::default() }
+    }
+
+    /// Language used when a key is missing from the current one
+    pub fn with_fallback(mut self, language: impl Into<String>) -> Self {
+        self.fallback = Some(language.into());
+        self
+    }
+
+    /// Add (or extend) a language from `key = value` source
+    pub fn add_language(&mut self, language: &str, source: &str) -> I18nResult<()> {
+        let entries = parse(source)?;
+        self.languages.entry(language.to_string()).or_default().extend(entries);
+        Ok(())
+    }
+
+    /// Add a language from a file
+    pub fn load_file(&mut self, language: &str, path: impl AsRef<Path>) -> I18nResult<()> {
+        self.add_language(language, &std::fs::read_to_string(path)?)
+    }
+
+    /// Load every `<language>.lang` file in a directory
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> I18nResult<()> {
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "lang"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Some(language) = path.file_stem().and_then(|s| s.to_str()) {
+                let language = language.to_string();
+                self.load_file(&language, &path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loaded language codes, sorted
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.languages.keys().map(String::as_str)
+    }
+
+    /// Current language code
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Switch language
+    pub fn set_language(&mut self, language: &str) -> I18nResult<()> {
+        if !self.languages.contains_key(language) {
+            return Err(I18nError::UnknownLanguage(language.to_string()));
+        }
+        self.language = language.to_string();
+        Ok(())
+    }
+
+    fn lookup(&self, language: &str, key: &str, count: Option<f64>) -> Option<&str> {
+        let entries = self.languages.get(language)?;
+        if let Some(n) = count {
+            let plural = format!("{}.{}", key, plural_category(language, n).suffix());
+            if let Some(value) = entries.get(&plural).or_else(|| entries.get(&format!("{}.other", key))) {
+                return Some(value);
+            }
+        }
+        entries.get(key).map(String::as_str)
+    }
+
+    /// Translate a key, interpolating `{name}` arguments
+    ///
+    /// The first number argument picks the plural form (`key.one` /
+    /// `key.other`). Missing keys return the key itself and are logged
+    /// once per language.
+    pub fn translate(&self, key: &str, args: &[(&str, Arg)]) -> String {
+        let count = args.iter().find_map(|(_, arg)| match arg {
+            Arg::Number(n) => Some(*n),
+            Arg::Text(_) => None,
+        });
+        let found = self
+            .lookup(&self.language, key, count)
+            .or_else(|| self.fallback.as_deref().and_then(|fallback| self.lookup(fallback, key, count)));
+
+        let Some(template) = found else {
+            if self.reported.lock().insert((self.language.clone(), key.to_string())) {
+                log::warn!("Missing translation for '{}' in '{}'", key, self.language);
+            }
+            return key.to_string();
+        };
+
+        interpolate(template, args)
+    }
+}
+
+/// Replace each `{name}` in `template` with its argument
+///
+/// The template is scanned once, so argument values are never substituted
+/// again. Placeholders without an argument are kept as they are.
+fn interpolate(template: &str, args: &[(&str, Arg)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = &after[..end];
+        match args.iter().find(|(arg_name, _)| *arg_name == name) {
+            Some((_, arg)) => text.push_str(&arg.to_string()),
+            None => text.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    text.push_str(rest);
+    text
+}
+
+static CATALOG: RwLock<Option<Catalog>> = parking_lot::const_rwlock(None);
+
+/// Atom holding the current language; bumps its version on every switch
+pub fn language_atom() -> &'static Atom<String> {
+    static LANGUAGE: OnceLock<Atom<String>> = OnceLock::new();
+    LANGUAGE.get_or_init(|| Atom::new("i18n.language", String::new()))
+}
+
+/// Install the global catalog used by `t!`
+pub fn set_catalog(catalog: Catalog) {
+    let language = catalog.language().to_string();
+    *CATALOG.write() = Some(catalog);
+    language_atom().set(language);
+}
+
+/// Switch the global catalog's language
+///
+/// Returns `UnknownLanguage` if the catalog has no such language, or if no
+/// catalog was installed with `set_catalog` yet.
+pub fn set_language(language: &str) -> I18nResult<()> {
+    CATALOG
+        .write()
+        .as_mut()
+        .ok_or_else(|| I18nError::UnknownLanguage(language.to_string()))?
+        .set_language(language)?;
+    language_atom().set(language.to_string());
+    Ok(())
+}
+
+/// Current global language (empty before `set_catalog`)
+pub fn language() -> String {
+    language_atom().get().clone()
+}
+
+/// Translate with the global catalog (the key itself if none is installed)
+pub fn translate(key: &str, args: &[(&str, Arg)]) -> String {
+    match CATALOG.read().as_ref() {
+        Some(catalog) => catalog.translate(key, args),
+        None => key.to_string(),
+    }
+}
+
+/// Translate a key with the global catalog
+///
+/// ```ignore
+/// let title = t!("menu.start");
+/// let score = t!("score", points = 42);
+/// ```
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::translate($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate($key, &[$((stringify!($name), $crate::i18n::Arg::from($value))),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = "\
+# Menu
+menu.start = Start game
+menu.help = Line one\\nline two
+formula = a = b
+
+score.one = {points} point
+score.other = {points} points
+lives.other = {count} lives
+greeting = Hello, {name}! You have {count} messages
+";
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::new("fr").with_fallback("en");
+        catalog.add_language("en", EN).unwrap();
+        catalog.add_language("fr", "score.one = {points} point\nscore.other = {points} points (fr)").unwrap();
+        catalog
+    }
+
+    #[test]
+    fn parser_skips_comments_and_keeps_values_whole() {
+        let entries = parse(EN).unwrap();
+        assert_eq!(entries["menu.start"], "Start game");
+        assert_eq!(entries["menu.help"], "Line one\nline two");
+        assert_eq!(entries["formula"], "a = b");
+        assert_eq!(entries.len(), 7);
+    }
+
+    #[test]
+    fn parser_errors_name_the_line() {
+        let error = parse("a = 1\n\n# fine\nno separator").unwrap_err();
+        assert!(matches!(error, I18nError::Parse { line: 4, .. }), "{}", error);
+        let error = parse(" = value").unwrap_err();
+        assert!(matches!(error, I18nError::Parse { line: 1, ref message } if message == "empty key"));
+    }
+
+    #[test]
+    fn plural_rules_follow_the_language() {
+        use PluralCategory::{One, Other};
+        let rules = [
+            ("en", 1.0, One),
+            ("en", 0.0, Other),
+            ("en", 2.0, Other),
+            ("en_US", 1.0, One),
+            ("fr", 0.0, One),
+            ("fr", 1.5, One),
+            ("pt-BR", 2.0, Other),
+            ("ja", 1.0, Other),
+            ("zh-Hant", 1.0, Other),
+        ];
+        for (language, n, expected) in rules {
+            assert_eq!(plural_category(language, n), expected, "{} {}", language, n);
+        }
+    }
+
+    #[test]
+    fn translate_picks_plurals_and_falls_back() {
+        let catalog = catalog();
+        assert_eq!(catalog.translate("score", &[("points", 0.into())]), "0 point");
+        assert_eq!(catalog.translate("score", &[("points", 3.into())]), "3 points (fr)");
+        // Missing from French, and English only has the other form
+        assert_eq!(catalog.translate("menu.start", &[]), "Start game");
+        assert_eq!(catalog.translate("lives", &[("count", 1.into())]), "1 lives");
+    }
+
+    #[test]
+    fn arguments_are_substituted_once() {
+        let catalog = catalog();
+        let args = [("name", Arg::from("{count}")), ("count", Arg::from(2))];
+        assert_eq!(catalog.translate("greeting", &args), "Hello, {count}! You have 2 messages");
+        // Unknown and unterminated placeholders stay as written
+        assert_eq!(interpolate("{a} {b} {c", &[("a", Arg::from("x"))]), "x {b} {c");
+        assert_eq!(interpolate("}{}{a}", &[("a", Arg::from(1.5))]), "}{}1.5");
+    }
+
+    #[test]
+    fn missing_keys_are_reported_once_per_language() {
+        let mut catalog = catalog();
+        for _ in 0..3 {
+            assert_eq!(catalog.translate("missing.key", &[]), "missing.key");
+        }
+        assert_eq!(catalog.reported.lock().len(), 1);
+
+        catalog.set_language("en").unwrap();
+        catalog.translate("missing.key", &[]);
+        assert_eq!(catalog.reported.lock().len(), 2);
+        assert!(matches!(catalog.set_language("de"), Err(I18nError::UnknownLanguage(_))));
+    }
+}
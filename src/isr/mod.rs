@@ -55,7 +55,7 @@ impl ShadingRate {
 }
 
 /// ISR Configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct IsrConfig {
     /// Tile size for hierarchical analysis
     pub tile_size: u32,
@@ -155,6 +155,25 @@ impl IsrAnalyzer {
         }
     }
     
+    /// Get the analyzed area in pixels
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &IsrConfig {
+        &self.config
+    }
+
+    /// Replace the configuration (a new tile size resets the importance)
+    pub fn set_config(&mut self, config: IsrConfig) {
+        if config.tile_size != self.config.tile_size {
+            *self = Self::new(self.width, self.height, config);
+        } else {
+            self.config = config;
+        }
+    }
+
     /// Calculate importance for a pixel
     pub fn calculate_pixel_importance(
         &self,
@@ -267,6 +286,63 @@ impl IsrAnalyzer {
     }
 }
 
+/// One analyzer per split-screen view
+///
+/// Importance is tracked per viewport, so each view keeps its own temporal
+/// history and foveation center. Pass each `SplitView`'s index and size.
+pub struct ViewportAnalyzers {
+    config: IsrConfig,
+    analyzers: Vec<IsrAnalyzer>,
+}
+
+impl ViewportAnalyzers {
+    pub fn new(config: IsrConfig) -> Self {
+        Self { config, analyzers: Vec::new() }
+    }
+
+    /// Get the analyzer for view `index`, recreated if its size changed
+    pub fn get(&mut self, index: usize, (width, height): (u32, u32)) -> &mut IsrAnalyzer {
+        while self.analyzers.len() <= index {
+            self.analyzers.push(IsrAnalyzer::new(width, height, self.config.clone()));
+        }
+        let analyzer = &mut self.analyzers[index];
+        if analyzer.size() != (width, height) {
+            *analyzer = IsrAnalyzer::new(width, height, self.config.clone());
+        }
+        analyzer
+    }
+
+    /// Get the configuration shared by every view
+    pub fn config(&self) -> &IsrConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of every view
+    pub fn set_config(&mut self, config: IsrConfig) {
+        for analyzer in &mut self.analyzers {
+            analyzer.set_config(config.clone());
+        }
+        self.config = config;
+    }
+
+    /// Drop the analyzers of views past the first `count` (e.g. a player left)
+    pub fn truncate(&mut self, count: usize) {
+        self.analyzers.truncate(count);
+    }
+
+    /// Advance every view to the next frame
+    pub fn next_frame(&mut self) {
+        for analyzer in &mut self.analyzers {
+            analyzer.next_frame();
+        }
+    }
+
+    /// Statistics of each view, in view order
+    pub fn stats(&self) -> Vec<IsrStats> {
+        self.analyzers.iter().map(IsrAnalyzer::stats).collect()
+    }
+}
+
 /// ISR Statistics
 #[derive(Debug, Clone)]
 pub struct IsrStats {
@@ -302,3 +378,42 @@ pub fn visualize_shading_rate(rate: ShadingRate) -> Color {
         ShadingRate::Eighth => Color::new(1.0, 0.0, 0.0, 1.0),  // Red - lowest
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_viewport_keeps_its_own_importance() {
+        let mut views = ViewportAnalyzers::new(IsrConfig { temporal_blend: 0.0, ..Default::default() });
+        views.get(0, (64, 64)).update_tile_importance(0, 0, 1.0);
+        views.get(1, (32, 64)).update_tile_importance(0, 0, 0.0);
+        assert_eq!(views.get(0, (64, 64)).get_tile_shading_rate(0, 0), ShadingRate::Full);
+        assert_eq!(views.get(1, (32, 64)).get_tile_shading_rate(0, 0), ShadingRate::Eighth);
+
+        let stats = views.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].total_tiles, stats[1].total_tiles), (64, 32));
+
+        // A resized viewport starts over at its new size
+        let resized = views.get(0, (128, 64));
+        assert_eq!(resized.size(), (128, 64));
+        assert_eq!(resized.stats().total_tiles, 128);
+        assert_eq!(views.get(1, (32, 64)).get_tile_shading_rate(0, 0), ShadingRate::Eighth);
+    }
+
+    #[test]
+    fn config_reaches_every_viewport() {
+        let mut views = ViewportAnalyzers::new(IsrConfig::default());
+        views.get(0, (64, 64));
+        views.get(1, (64, 64));
+        let config = IsrConfig { tile_size: 16, ..Default::default() };
+        views.set_config(config.clone());
+        assert!(views.stats().iter().all(|stats| stats.total_tiles == 16));
+        // Views added later use it too
+        assert_eq!(views.get(2, (64, 64)).config(), &config);
+
+        views.truncate(1);
+        assert_eq!(views.stats().len(), 1);
+    }
+}
@@ -48,6 +48,9 @@ pub mod dx12;
 // Level B: Mid-level graphics abstractions
 pub mod graphics;
 
+// Backend seam (DX12 + software)
+pub mod backend;
+
 // Level C: Simple high-level API
 pub mod easy;
 
@@ -69,6 +72,30 @@ pub mod window;
 // Event system
 pub mod events;
 
+// Content hashing (XXH64)
+pub mod hashing;
+
+// Deterministic frame hashing
+pub mod testing;
+
+// Fixed-timestep simulation with rollback
+pub mod sim;
+
+// Asset handles and hot reload
+pub mod assets;
+
+// Localization
+pub mod i18n;
+
+// Log sink with in-memory history
+pub mod logging;
+
+// Crash reports
+pub mod crash;
+
+// Runtime command console
+pub mod console;
+
 // React-style hooks
 pub mod hooks;
 
@@ -105,7 +132,7 @@ pub mod prelude {
     pub use crate::window::{Window, WindowConfig};
     
     // Events
-    pub use crate::events::{Event, EventHandler, MouseEvent, KeyEvent};
+    pub use crate::events::{Event, EventHandler, MouseEvent, KeyEvent, InputState, AppLifecycleEvent, GraphicsEvent};
     
     // Hooks
     pub use crate::hooks::{use_state, use_effect, use_memo, use_ref};
@@ -0,0 +1,311 @@
+//! Log sink with an in-memory ring buffer
+//!
+//! `init` installs a logger that writes to stderr like env_logger (honoring
+//! `RUST_LOG`) and also keeps the most recent records in memory, where the
+//! `LogConsole` component can show them on machines without a terminal.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+/// A reasonable capacity for `init`
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// A captured log record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    /// Increasing number, unique per record
+    pub sequence: u64,
+}
+
+/// Which records to show
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Least severe level shown
+    pub level: LevelFilter,
+    /// Only targets starting with this prefix
+    pub target: Option<String>,
+    /// Only messages containing this text (case-insensitive)
+    pub search: Option<String>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self { level: LevelFilter::Trace, target: None, search: None }
+    }
+}
+
+impl LogFilter {
+    /// Check if a record passes the filter
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        record.level <= self.level
+            && self.target.as_ref().is_none_or(|t| record.target.starts_with(t.as_str()))
+            && self
+                .search
+                .as_ref()
+                .is_none_or(|s| record.message.to_lowercase().contains(&s.to_lowercase()))
+    }
+}
+
+/// Fixed-capacity ring buffer of records
+///
+/// Storage is allocated once; when full the oldest record is dropped.
+#[derive(Debug)]
+pub struct LogBuffer {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    next_sequence: u64,
+    dropped: u64,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+            next_sequence: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Add a record, dropping the oldest one if full
+    ///
+    /// Once the buffer is full, the dropped record's strings are reused, so
+    /// pushing doesn't allocate unless a message outgrows them.
+    pub fn push(&mut self, level: Level, target: &str, message: impl fmt::Display) {
+        if self.capacity == 0 {
+            self.dropped += 1;
+            return;
+        }
+        let recycled = if self.records.len() == self.capacity {
+            self.dropped += 1;
+            self.records.pop_front()
+        } else {
+            None
+        };
+        let mut record = recycled.unwrap_or_else(|| LogRecord {
+            level,
+            target: String::new(),
+            message: String::new(),
+            sequence: 0,
+        });
+        record.level = level;
+        record.target.clear();
+        record.target.push_str(target);
+        record.message.clear();
+        let _ = write!(record.message, "{}", message);
+        record.sequence = self.next_sequence;
+        self.records.push_back(record);
+        self.next_sequence += 1;
+    }
+
+    /// Records from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &LogRecord> {
+        self.records.iter()
+    }
+
+    /// Records passing a filter, oldest first
+    pub fn filtered<'a>(&'a self, filter: &'a LogFilter) -> impl Iterator<Item = &'a LogRecord> + 'a {
+        self.records.iter().filter(move |r| filter.matches(r))
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of records dropped because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+static BUFFER: Mutex<Option<LogBuffer>> = parking_lot::const_mutex(None);
+
+struct EpicxLogger {
+    stderr: env_logger::Logger,
+    capture: LevelFilter,
+}
+
+impl Log for EpicxLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.capture || self.stderr.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+        if record.level() <= self.capture {
+            capture(record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+    }
+}
+
+/// How long `capture` waits for another thread to release the buffer
+const CAPTURE_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Store a record in the ring buffer (if `init` has been called)
+///
+/// The record is dropped if the buffer stays locked, e.g. when logging from
+/// inside `with_buffer`, rather than deadlocking.
+pub fn capture(level: Level, target: &str, message: impl fmt::Display) {
+    if let Some(mut buffer) = BUFFER.try_lock_for(CAPTURE_TIMEOUT) {
+        if let Some(buffer) = buffer.as_mut() {
+            buffer.push(level, target, message);
+        }
+    }
+}
+
+/// Install the EPICX logger
+///
+/// Keeps the last `capacity` records at `capture` level and above; stderr
+/// output is filtered by `RUST_LOG` as usual.
+pub fn init(capacity: usize, capture: LevelFilter) -> Result<(), log::SetLoggerError> {
+    let stderr = env_logger::Builder::from_default_env().build();
+    let max_level = stderr.filter().max(capture);
+    *BUFFER.lock() = Some(LogBuffer::new(capacity));
+    log::set_boxed_logger(Box::new(EpicxLogger { stderr, capture }))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// Run `f` with the ring buffer (None before `init`)
+///
+/// The buffer is locked while `f` runs, so `f` must not log.
+pub fn with_buffer<R>(f: impl FnOnce(Option<&LogBuffer>) -> R) -> R {
+    f(BUFFER.lock().as_ref())
+}
+
+/// Copy the records passing a filter, oldest first
+pub fn records(filter: &LogFilter) -> Vec<LogRecord> {
+    with_buffer(|buffer| buffer.map(|b| b.filtered(filter).cloned().collect()).unwrap_or_default())
+}
+
+/// Like `records`, but None if the buffer stays locked (e.g. by this thread)
+pub fn try_records(filter: &LogFilter) -> Option<Vec<LogRecord>> {
+    let buffer = BUFFER.try_lock_for(CAPTURE_TIMEOUT)?;
+    Some(buffer.as_ref().map(|b| b.filtered(filter).cloned().collect()).unwrap_or_default())
+}
+
+/// Capture panic messages (target `panic`), then run the previous hook
+///
+/// A panic while this thread holds the buffer (inside `with_buffer`) is not
+/// captured; it is still printed.
+pub fn capture_panics() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Never wait here: the panicking thread may be the one holding the lock
+        if let Some(mut buffer) = BUFFER.try_lock() {
+            if let Some(buffer) = buffer.as_mut() {
+                buffer.push(Level::Error, "panic", info);
+            }
+        }
+        // The previous hook already prints to stderr
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(level: Level, target: &str, message: &str) -> LogRecord {
+        LogRecord { level, target: target.to_string(), message: message.to_string(), sequence: 0 }
+    }
+
+    #[test]
+    fn filter_by_level_target_and_search() {
+        let filter = LogFilter { level: LevelFilter::Warn, ..Default::default() };
+        assert!(filter.matches(&record(Level::Error, "epicx", "x")));
+        assert!(filter.matches(&record(Level::Warn, "epicx", "x")));
+        assert!(!filter.matches(&record(Level::Info, "epicx", "x")));
+
+        let filter = LogFilter { target: Some("epicx::graphics".to_string()), ..Default::default() };
+        assert!(filter.matches(&record(Level::Info, "epicx::graphics::tonemap", "x")));
+        assert!(!filter.matches(&record(Level::Info, "epicx::assets", "x")));
+
+        let filter = LogFilter { search: Some("SwAp".to_string()), ..Default::default() };
+        assert!(filter.matches(&record(Level::Info, "epicx", "Swap chain resized")));
+        assert!(!filter.matches(&record(Level::Info, "epicx", "Device created")));
+
+        let mut buffer = LogBuffer::new(8);
+        buffer.push(Level::Info, "epicx::graphics", "resized".to_string());
+        buffer.push(Level::Error, "epicx::graphics", "device removed".to_string());
+        buffer.push(Level::Error, "epicx::assets", "missing file".to_string());
+        let filter = LogFilter {
+            level: LevelFilter::Error,
+            target: Some("epicx::graphics".to_string()),
+            search: Some("device".to_string()),
+        };
+        let found: Vec<_> = buffer.filtered(&filter).map(|r| r.message.as_str()).collect();
+        assert_eq!(found, ["device removed"]);
+    }
+
+    #[test]
+    fn full_buffer_drops_the_oldest_records() {
+        let mut buffer = LogBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(Level::Info, "epicx", format!("record {}", i));
+        }
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.capacity(), 3);
+        assert_eq!(buffer.dropped(), 2);
+        let kept: Vec<_> = buffer.iter().map(|r| (r.sequence, r.message.as_str())).collect();
+        assert_eq!(kept, [(2, "record 2"), (3, "record 3"), (4, "record 4")]);
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        buffer.push(Level::Info, "epicx", "after clear".to_string());
+        assert_eq!(buffer.iter().next().unwrap().sequence, 5);
+
+        let mut empty = LogBuffer::new(0);
+        empty.push(Level::Info, "epicx", "nowhere".to_string());
+        assert!(empty.is_empty());
+        assert_eq!(empty.dropped(), 1);
+    }
+
+    #[test]
+    fn full_buffer_reuses_the_dropped_strings() {
+        let mut buffer = LogBuffer::new(1);
+        buffer.push(Level::Info, "epicx::graphics::tonemap", "x".repeat(256));
+        let old = buffer.iter().next().unwrap();
+        let (target_ptr, message_ptr) = (old.target.as_ptr(), old.message.as_ptr());
+
+        buffer.push(Level::Warn, "epicx", format_args!("record {}", 1));
+        let new = buffer.iter().next().unwrap();
+        assert_eq!((new.level, new.target.as_str(), new.message.as_str()), (Level::Warn, "epicx", "record 1"));
+        assert_eq!(new.target.as_ptr(), target_ptr);
+        assert_eq!(new.message.as_ptr(), message_ptr);
+        assert!(new.message.capacity() >= 256);
+    }
+
+    #[test]
+    fn capture_inside_with_buffer_does_not_deadlock() {
+        *BUFFER.lock() = Some(LogBuffer::new(4));
+        with_buffer(|_| capture(Level::Info, "epicx", "dropped".to_string()));
+        capture(Level::Info, "epicx", "kept".to_string());
+        let messages: Vec<_> = records(&LogFilter::default()).into_iter().map(|r| r.message).collect();
+        assert_eq!(messages, ["kept"]);
+    }
+}
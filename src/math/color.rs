@@ -1,8 +1,14 @@
 //! Color type for EPICX
+//!
+//! Colors are sRGB-encoded (gamma space) unless stated otherwise, which is
+//! what hex codes and color pickers produce. Convert with `to_linear` before
+//! doing lighting or blending math and back with `to_srgb` afterwards.
 
 use serde::{Deserialize, Serialize};
 
 /// RGBA color representation
+///
+/// RGB channels are sRGB-encoded by convention; alpha is always linear.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Color {
     pub r: f32,
@@ -12,6 +18,8 @@ pub struct Color {
 }
 
 impl Color {
+    // Named constants are sRGB-encoded (identical in linear space only for
+    // 0.0 and 1.0 channels, which all of these use)
     pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
     pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
     pub const RED: Color = Color::rgb(1.0, 0.0, 0.0);
@@ -41,6 +49,8 @@ impl Color {
     }
 
     /// Create a color from hex value (0xRRGGBB or 0xRRGGBBAA)
+    ///
+    /// The result is sRGB-encoded, like the hex code itself.
     pub fn from_hex(hex: u32) -> Self {
         if hex > 0xFFFFFF {
             // RGBA format
@@ -82,7 +92,50 @@ impl Color {
         [self.r, self.g, self.b, self.a]
     }
 
+    /// Decode sRGB channels to linear light (alpha unchanged)
+    pub fn to_linear(self) -> Color {
+        Color::rgba(
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        )
+    }
+
+    /// Encode linear channels to sRGB (alpha unchanged)
+    pub fn to_srgb(self) -> Color {
+        Color::rgba(
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        )
+    }
+
+    /// Composite this color over `dst` with straight alpha
+    ///
+    /// With `linear` set, both colors are decoded first and the result is
+    /// re-encoded, which is physically correct. Otherwise the math happens on
+    /// the sRGB values directly (the traditional, darker result).
+    pub fn blend_over(self, dst: Color, linear: bool) -> Color {
+        let (src, dst) = if linear { (self.to_linear(), dst.to_linear()) } else { (self, dst) };
+        let a = src.a + dst.a * (1.0 - src.a);
+        let mix = |s: f32, d: f32| {
+            if a > 0.0 { (s * src.a + d * dst.a * (1.0 - src.a)) / a } else { 0.0 }
+        };
+        let out = Color::rgba(mix(src.r, dst.r), mix(src.g, dst.g), mix(src.b, dst.b), a);
+        if linear { out.to_srgb() } else { out }
+    }
+
+    /// Interpolate between two sRGB colors in linear space
+    pub fn lerp_linear(self, other: Color, t: f32) -> Color {
+        self.to_linear().lerp(other.to_linear(), t).to_srgb()
+    }
+
     /// Linearly interpolate between two colors
+    ///
+    /// Interpolates the stored values directly; for sRGB colors prefer
+    /// `lerp_linear` to get perceptually correct midpoints.
     pub fn lerp(self, other: Color, t: f32) -> Color {
         let t = t.clamp(0.0, 1.0);
         Color::rgba(
@@ -99,6 +152,24 @@ impl Color {
     }
 }
 
+/// Decode one sRGB channel to linear light
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode one linear channel to sRGB
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 impl Default for Color {
     fn default() -> Self {
         Self::WHITE
@@ -0,0 +1,167 @@
+//! Letterboxed scaling of a fixed internal resolution into a window
+
+use super::Rect;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// How a fixed internal resolution is scaled to the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScalingMode {
+    /// Largest whole-number scale that fits, nearest filtering (pixel art)
+    #[default]
+    IntegerNearest,
+    /// Largest scale that fits keeping aspect ratio, linear filtering
+    FitLinear,
+    /// Fill the whole window, ignoring aspect ratio
+    Stretch,
+}
+
+/// Placement of the internal image inside the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Letterbox {
+    /// Internal resolution in pixels
+    pub internal: (u32, u32),
+    /// Window size in pixels
+    pub window: (u32, u32),
+    /// Scaling mode used to compute the placement
+    pub mode: ScalingMode,
+    /// Area covered by the image, in window pixels
+    pub content: Rect,
+}
+
+impl Letterbox {
+    /// Compute the placement of `internal` in a `window`-sized surface
+    pub fn new(internal: (u32, u32), window: (u32, u32), mode: ScalingMode) -> Self {
+        let (iw, ih) = (internal.0.max(1) as f32, internal.1.max(1) as f32);
+        let (ww, wh) = (window.0 as f32, window.1 as f32);
+
+        let (sx, sy) = match mode {
+            ScalingMode::Stretch => (ww / iw, wh / ih),
+            ScalingMode::FitLinear => {
+                let s = (ww / iw).min(wh / ih);
+                (s, s)
+            }
+            ScalingMode::IntegerNearest => {
+                // Never below 1x; a window smaller than the internal size crops
+                let s = (ww / iw).min(wh / ih).floor().max(1.0);
+                (s, s)
+            }
+        };
+
+        let (cw, ch) = (iw * sx, ih * sy);
+        // Whole-pixel offsets keep integer scaling crisp
+        let content = Rect::new(((ww - cw) / 2.0).floor(), ((wh - ch) / 2.0).floor(), cw, ch);
+
+        Self { internal, window, mode, content }
+    }
+
+    /// Scale factor from internal pixels to window pixels
+    pub fn scale(&self) -> Vec2 {
+        Vec2::new(
+            self.content.width / self.internal.0.max(1) as f32,
+            self.content.height / self.internal.1.max(1) as f32,
+        )
+    }
+
+    /// Map a window position to internal pixels, or None outside the image
+    pub fn window_to_internal(&self, position: Vec2) -> Option<Vec2> {
+        let local = position - self.content.position();
+        if local.x < 0.0 || local.y < 0.0 || local.x >= self.content.width || local.y >= self.content.height {
+            return None;
+        }
+        Some(local / self.scale())
+    }
+
+    /// Map a window position to internal pixels, clamping to the image edges
+    pub fn window_to_internal_clamped(&self, position: Vec2) -> Vec2 {
+        let local = (position - self.content.position()) / self.scale();
+        let max = Vec2::new(self.internal.0 as f32, self.internal.1 as f32);
+        local.clamp(Vec2::ZERO, max)
+    }
+
+    /// Map an internal position to window pixels
+    pub fn internal_to_window(&self, position: Vec2) -> Vec2 {
+        self.content.position() + position * self.scale()
+    }
+
+    /// Rectangles of the bars around the image (empty when it fills the window)
+    pub fn bars(&self) -> Vec<Rect> {
+        let (ww, wh) = (self.window.0 as f32, self.window.1 as f32);
+        let c = self.content;
+        let right = c.x + c.width;
+        let bottom = c.y + c.height;
+
+        [
+            Rect::new(0.0, 0.0, ww, c.y),
+            Rect::new(0.0, bottom, ww, wh - bottom),
+            Rect::new(0.0, c.y, c.x, c.height),
+            Rect::new(right, c.y, ww - right, c.height),
+        ]
+        .into_iter()
+        .filter(|r| r.width > 0.0 && r.height > 0.0)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel_art() -> Letterbox {
+        Letterbox::new((320, 180), (1000, 600), ScalingMode::IntegerNearest)
+    }
+
+    #[test]
+    fn integer_scaling_centers_the_image_with_four_bars() {
+        let letterbox = pixel_art();
+        assert_eq!(letterbox.scale(), Vec2::splat(3.0));
+        assert_eq!(letterbox.content, Rect::new(20.0, 30.0, 960.0, 540.0));
+        assert_eq!(
+            letterbox.bars(),
+            [
+                Rect::new(0.0, 0.0, 1000.0, 30.0),
+                Rect::new(0.0, 570.0, 1000.0, 30.0),
+                Rect::new(0.0, 30.0, 20.0, 540.0),
+                Rect::new(980.0, 30.0, 20.0, 540.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_in_the_bars_map_to_nothing() {
+        let letterbox = pixel_art();
+        for position in [(10.0, 300.0), (500.0, 15.0), (980.0, 300.0), (500.0, 570.0), (0.0, 0.0)] {
+            assert_eq!(letterbox.window_to_internal(Vec2::from(position)), None, "{:?}", position);
+        }
+        assert_eq!(letterbox.window_to_internal(Vec2::new(20.0, 30.0)), Some(Vec2::ZERO));
+        assert_eq!(letterbox.window_to_internal(Vec2::new(500.0, 300.0)), Some(Vec2::new(160.0, 90.0)));
+        assert_eq!(letterbox.internal_to_window(Vec2::new(160.0, 90.0)), Vec2::new(500.0, 300.0));
+    }
+
+    #[test]
+    fn clamped_mapping_stops_at_the_image_edges() {
+        let letterbox = pixel_art();
+        assert_eq!(letterbox.window_to_internal_clamped(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(letterbox.window_to_internal_clamped(Vec2::new(1000.0, 600.0)), Vec2::new(320.0, 180.0));
+        assert_eq!(letterbox.window_to_internal_clamped(Vec2::new(5.0, 300.0)), Vec2::new(0.0, 90.0));
+        assert_eq!(letterbox.window_to_internal_clamped(Vec2::new(500.0, 300.0)), Vec2::new(160.0, 90.0));
+    }
+
+    #[test]
+    fn fit_linear_keeps_the_aspect_ratio_with_fractional_scale() {
+        let letterbox = Letterbox::new((320, 180), (1000, 600), ScalingMode::FitLinear);
+        assert_eq!(letterbox.scale(), Vec2::splat(3.125));
+        assert_eq!(letterbox.content, Rect::new(0.0, 18.0, 1000.0, 562.5));
+        // The image spans the full width, so only the top and bottom bars remain
+        assert_eq!(letterbox.bars(), [Rect::new(0.0, 0.0, 1000.0, 18.0), Rect::new(0.0, 580.5, 1000.0, 19.5)]);
+    }
+
+    #[test]
+    fn stretch_fills_the_window() {
+        let letterbox = Letterbox::new((320, 180), (1000, 600), ScalingMode::Stretch);
+        assert_eq!(letterbox.content, Rect::new(0.0, 0.0, 1000.0, 600.0));
+        assert_eq!(letterbox.scale(), Vec2::new(3.125, 600.0 / 180.0));
+        assert!(letterbox.bars().is_empty());
+        assert_eq!(letterbox.window_to_internal(Vec2::new(500.0, 300.0)), Some(Vec2::new(160.0, 90.0)));
+    }
+}
@@ -5,9 +5,17 @@
 mod color;
 mod rect;
 mod transform;
+mod letterbox;
+mod bounds;
+mod rng;
+mod spring;
 
-pub use color::Color;
+pub use color::{Color, linear_to_srgb, srgb_to_linear};
 pub use rect::Rect;
 pub use transform::Transform;
+pub use letterbox::{Letterbox, ScalingMode};
+pub use bounds::{Aabb, Frustum};
+pub use rng::Rng;
+pub use spring::{Spring, SpringValue};
 
 pub use glam::{Vec2, Vec3, Vec4, Mat4, Quat};
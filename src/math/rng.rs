@@ -0,0 +1,48 @@
+//! Seeded pseudo-random numbers
+
+/// Small deterministic random number generator (SplitMix64)
+///
+/// The same seed always produces the same sequence on every platform, so
+/// it is safe to use in replays and deterministic runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Next 32 random bits
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Uniform float in 0..1
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits fill the f32 mantissa exactly
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform float in min..max
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Uniform integer in min..max (max exclusive)
+    pub fn range_u32(&mut self, min: u32, max: u32) -> u32 {
+        assert!(min < max, "empty range");
+        min + (self.next_u64() % (max - min) as u64) as u32
+    }
+}
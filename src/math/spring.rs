@@ -0,0 +1,208 @@
+//! Damped springs for interactive animation
+//!
+//! Unlike a tween, a spring keeps its velocity when the target changes
+//! mid-flight, so interrupted animations stay smooth. Updates use the exact
+//! solution of the damped oscillator, so large or uneven time steps never
+//! blow up.
+
+use super::{Color, Vec2, Vec3};
+
+/// A value a spring can animate (up to four independent components)
+pub trait SpringValue: Copy {
+    fn to_components(self) -> [f32; 4];
+    fn from_components(c: [f32; 4]) -> Self;
+}
+
+impl SpringValue for f32 {
+    fn to_components(self) -> [f32; 4] {
+        [self, 0.0, 0.0, 0.0]
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        c[0]
+    }
+}
+
+impl SpringValue for Vec2 {
+    fn to_components(self) -> [f32; 4] {
+        [self.x, self.y, 0.0, 0.0]
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        Vec2::new(c[0], c[1])
+    }
+}
+
+impl SpringValue for Vec3 {
+    fn to_components(self) -> [f32; 4] {
+        [self.x, self.y, self.z, 0.0]
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        Vec3::new(c[0], c[1], c[2])
+    }
+}
+
+impl SpringValue for Color {
+    fn to_components(self) -> [f32; 4] {
+        self.to_array()
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        Color::rgba(c[0], c[1], c[2], c[3])
+    }
+}
+
+/// Spring parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spring {
+    pub stiffness: f32,
+    pub damping: f32,
+    pub mass: f32,
+}
+
+impl Default for Spring {
+    fn default() -> Self {
+        Self { stiffness: 170.0, damping: 26.0, mass: 1.0 }
+    }
+}
+
+impl Spring {
+    pub fn new(stiffness: f32, damping: f32, mass: f32) -> Self {
+        Self { stiffness, damping, mass }
+    }
+
+    /// Spring that settles as fast as possible without overshooting
+    pub fn critically_damped(stiffness: f32, mass: f32) -> Self {
+        Self::new(stiffness, 2.0 * (stiffness * mass).sqrt(), mass)
+    }
+
+    /// Damping ratio: below 1 bounces, 1 is critical, above 1 is sluggish
+    ///
+    /// Infinite without stiffness, since nothing pulls the value back.
+    pub fn damping_ratio(&self) -> f32 {
+        let critical = 2.0 * (self.stiffness * self.mass.max(1e-6)).sqrt();
+        if critical > 0.0 {
+            self.damping / critical
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    /// Advance `current` towards `target` by `dt` seconds
+    ///
+    /// Returns the new value and velocity.
+    pub fn update<T: SpringValue>(&self, current: T, velocity: T, target: T, dt: f32) -> (T, T) {
+        let (x, v, t) = (current.to_components(), velocity.to_components(), target.to_components());
+        let mut value = [0.0; 4];
+        let mut speed = [0.0; 4];
+        for i in 0..4 {
+            let (offset, new_v) = self.step(x[i] - t[i], v[i], dt);
+            value[i] = t[i] + offset;
+            speed[i] = new_v;
+        }
+        (T::from_components(value), T::from_components(speed))
+    }
+
+    /// Exact solution for one component, as displacement from the target
+    fn step(&self, x0: f32, v0: f32, dt: f32) -> (f32, f32) {
+        let omega = (self.stiffness / self.mass.max(1e-6)).sqrt();
+        let zeta = self.damping_ratio();
+
+        if omega <= 0.0 {
+            // No spring: the velocity only decays through damping
+            let gamma = self.damping / self.mass.max(1e-6);
+            if gamma <= 0.0 {
+                return (x0 + v0 * dt, v0);
+            }
+            let decay = (-gamma * dt).exp();
+            (x0 + v0 * (1.0 - decay) / gamma, v0 * decay)
+        } else if (zeta - 1.0).abs() < 1e-4 {
+            let b = v0 + omega * x0;
+            let decay = (-omega * dt).exp();
+            ((x0 + b * dt) * decay, (v0 - omega * b * dt) * decay)
+        } else if zeta < 1.0 {
+            let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+            let a = (v0 + zeta * omega * x0) / omega_d;
+            let decay = (-zeta * omega * dt).exp();
+            let (sin, cos) = (omega_d * dt).sin_cos();
+            let x = decay * (x0 * cos + a * sin);
+            let v = decay * (-zeta * omega * (x0 * cos + a * sin) + omega_d * (a * cos - x0 * sin));
+            (x, v)
+        } else {
+            let root = (zeta * zeta - 1.0).sqrt();
+            let (r1, r2) = (-omega * (zeta - root), -omega * (zeta + root));
+            let c1 = (v0 - r2 * x0) / (r1 - r2);
+            let c2 = x0 - c1;
+            let (e1, e2) = ((r1 * dt).exp(), (r2 * dt).exp());
+            (c1 * e1 + c2 * e2, c1 * r1 * e1 + c2 * r2 * e2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Step `from` towards 0 with zero initial velocity, returning every value
+    fn trajectory(spring: Spring, from: f32, dt: f32, steps: usize) -> Vec<f32> {
+        let (mut x, mut v) = (from, 0.0);
+        (0..steps)
+            .map(|_| {
+                (x, v) = spring.update(x, v, 0.0, dt);
+                x
+            })
+            .collect()
+    }
+
+    #[test]
+    fn critically_damped_converges_without_overshoot() {
+        let spring = Spring::critically_damped(170.0, 1.0);
+        assert!((spring.damping_ratio() - 1.0).abs() < 1e-6);
+
+        for dt in [1.0 / 240.0, 1.0 / 60.0, 1.0 / 15.0, 0.5] {
+            let values = trajectory(spring, 100.0, dt, (3.0 / dt) as usize);
+            // Approaches monotonically and never crosses the target
+            assert!(values.iter().all(|&x| x >= 0.0), "overshoot at dt {}", dt);
+            assert!(values.windows(2).all(|w| w[1] <= w[0]), "moved away at dt {}", dt);
+            assert!(*values.last().unwrap() < 1e-3, "not settled at dt {}", dt);
+        }
+
+        // From the other side and in several components at once
+        let (mut value, mut velocity) = (Vec2::new(-50.0, 20.0), Vec2::ZERO);
+        for _ in 0..180 {
+            (value, velocity) = spring.update(value, velocity, Vec2::ZERO, 1.0 / 60.0);
+            assert!(value.x <= 0.0 && value.y >= 0.0);
+        }
+        assert!(value.length() < 1e-3);
+    }
+
+    #[test]
+    fn step_size_does_not_change_the_result() {
+        for spring in [Spring::default(), Spring::critically_damped(100.0, 2.0), Spring::new(100.0, 5.0, 1.0)] {
+            let (one, _) = spring.update(10.0, 3.0, 0.0, 0.25);
+            let fine = (0..60).fold((10.0f32, 3.0f32), |(x, v), _| spring.update(x, v, 0.0, 0.25 / 60.0));
+            assert!((one - fine.0).abs() < 1e-3, "{:?}: {} vs {}", spring, one, fine.0);
+        }
+    }
+
+    #[test]
+    fn underdamped_springs_overshoot() {
+        let spring = Spring::new(170.0, 5.0, 1.0);
+        assert!(spring.damping_ratio() < 1.0);
+        assert!(trajectory(spring, 100.0, 1.0 / 60.0, 60).iter().any(|&x| x < 0.0));
+    }
+
+    #[test]
+    fn zero_stiffness_stays_finite() {
+        let spring = Spring::new(0.0, 10.0, 1.0);
+        assert_eq!(spring.damping_ratio(), f32::INFINITY);
+        let (x, v) = spring.update(0.0, 5.0, 100.0, 1.0);
+        assert!(x.is_finite() && v.is_finite());
+        // Coasts to a stop half a unit away (v0 / (damping / mass))
+        assert!((x - 0.5).abs() < 1e-3 && v.abs() < 1e-3);
+
+        let (x, v) = Spring::new(0.0, 0.0, 1.0).update(0.0, 5.0, 100.0, 1.0);
+        assert_eq!((x, v), (5.0, 5.0));
+    }
+}
@@ -11,11 +11,13 @@ mod primitives;
 mod operations;
 mod bezier;
 mod antialiasing;
+mod scene_file;
 
 pub use primitives::*;
 pub use operations::*;
 pub use bezier::*;
 pub use antialiasing::*;
+pub use scene_file::*;
 
 use crate::math::{Vec2, Vec3};
 
@@ -39,6 +41,16 @@ pub trait Sdf: Send + Sync {
     }
 }
 
+impl<S: Sdf + ?Sized> Sdf for &S {
+    fn distance(&self, p: Vec3) -> f32 {
+        (**self).distance(p)
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (**self).bounds()
+    }
+}
+
 /// Ray marching configuration
 #[derive(Debug, Clone)]
 pub struct RayMarchConfig {
@@ -107,21 +119,57 @@ pub fn ray_march<S: Sdf>(sdf: &S, origin: Vec3, direction: Vec3, config: &RayMar
 }
 
 /// SDF Scene - collection of SDF objects
+///
+/// Objects added in code live alongside the nodes of a `SceneDescription`,
+/// which is what scene files load and save.
 pub struct SdfScene {
     objects: Vec<Box<dyn Sdf>>,
+    description: SceneDescription,
 }
 
 impl SdfScene {
     pub fn new() -> Self {
-        Self { objects: Vec::new() }
+        Self::from_description(SceneDescription::default())
+    }
+
+    /// Create a scene from a description (camera, lights, materials, nodes)
+    pub fn from_description(description: SceneDescription) -> Self {
+        Self { objects: Vec::new(), description }
     }
     
     pub fn add<S: Sdf + 'static>(&mut self, sdf: S) {
         self.objects.push(Box::new(sdf));
     }
+
+    /// Add a serializable node
+    pub fn add_node(&mut self, node: SceneNode) {
+        self.description.nodes.push(node);
+    }
     
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.description.nodes.clear();
+    }
+
+    pub fn description(&self) -> &SceneDescription {
+        &self.description
+    }
+
+    pub fn description_mut(&mut self) -> &mut SceneDescription {
+        &mut self.description
+    }
+
+    /// Distance and material of the closest node at a point
+    ///
+    /// Objects added with `add` have no material.
+    pub fn distance_material(&self, p: Vec3) -> (f32, Option<&SceneMaterial>) {
+        let objects = self.objects.iter().map(|obj| (obj.distance(p), None));
+        let nodes = self.description.nodes.iter().map(|node| node.distance_material(p));
+        objects
+            .chain(nodes)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(d, name)| (d, name.and_then(|name| self.description.material(name))))
+            .unwrap_or((f32::MAX, None))
     }
 }
 
@@ -136,6 +184,7 @@ impl Sdf for SdfScene {
         self.objects
             .iter()
             .map(|obj| obj.distance(p))
+            .chain(self.description.nodes.iter().map(|node| node.distance(p)))
             .fold(f32::MAX, f32::min)
     }
 }
@@ -0,0 +1,779 @@
+//! Declarative SDF scene files
+//!
+//! A scene file is JSON describing a tree of nodes (primitives, CSG
+//! operations and transforms) plus materials, lights and a camera:
+//!
+//! ```json
+//! {
+//!   "camera": { "position": [0, 5, 12], "target": [0, 1, 0] },
+//!   "materials": [{ "name": "red", "color": [0.9, 0.3, 0.3] }],
+//!   "nodes": [
+//!     { "kind": "smooth_union", "k": 0.3, "material": "red",
+//!       "a": { "kind": "sphere", "center": [0, 1, 0], "radius": 1 },
+//!       "b": { "kind": "box", "center": [1, 1, 0], "half_extents": [0.5, 0.5, 0.5] } }
+//!   ]
+//! }
+//! ```
+//!
+//! The same description is evaluated on the CPU (`SceneNode` implements
+//! `Sdf`) and turned into HLSL with `SceneDescription::to_hlsl`.
+
+use super::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::Path;
+use thiserror::Error;
+
+/// Scene file errors
+#[derive(Error, Debug)]
+pub enum SceneFileError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Parse error: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("{path}: unknown node kind '{kind}' (expected one of: {})", NODE_KINDS.join(", "))]
+    UnknownKind { path: String, kind: String },
+    #[error("{path}: node has no 'kind'")]
+    MissingKind { path: String },
+    #[error("{path}: unknown material '{name}'")]
+    UnknownMaterial { path: String, name: String },
+    #[error("Scene has {0} objects added in code, which cannot be saved")]
+    NotSerializable(usize),
+}
+
+pub type SceneFileResult<T> = Result<T, SceneFileError>;
+
+/// All node kinds accepted in scene files
+pub const NODE_KINDS: &[&str] = &[
+    "sphere", "box", "rounded_box", "cylinder", "torus", "capsule", "cone", "plane",
+    "union", "intersection", "subtraction", "smooth_union", "smooth_subtraction", "smooth_intersection",
+    "translate", "rotate_y", "scale", "round", "onion", "twist", "bend", "repeat",
+];
+
+/// Scene camera
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneCamera {
+    pub position: Vec3,
+    pub target: Vec3,
+    /// Vertical field of view in degrees
+    #[serde(default = "default_fov")]
+    pub fov: f32,
+}
+
+fn default_fov() -> f32 {
+    60.0
+}
+
+impl Default for SceneCamera {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(0.0, 2.0, 8.0),
+            target: Vec3::ZERO,
+            fov: default_fov(),
+        }
+    }
+}
+
+/// Scene light
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SceneLight {
+    /// Light from a direction (pointing towards the light)
+    Directional { direction: Vec3, color: Vec3 },
+    Point { position: Vec3, color: Vec3, range: f32 },
+}
+
+/// Named surface material
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneMaterial {
+    pub name: String,
+    pub color: Vec3,
+    #[serde(default = "default_roughness")]
+    pub roughness: f32,
+    #[serde(default)]
+    pub metallic: f32,
+}
+
+fn default_roughness() -> f32 {
+    0.5
+}
+
+/// Node shape: a primitive, an operation on other nodes or a transform
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NodeShape {
+    Sphere { center: Vec3, radius: f32 },
+    Box { center: Vec3, half_extents: Vec3 },
+    RoundedBox { center: Vec3, half_extents: Vec3, radius: f32 },
+    Cylinder { center: Vec3, radius: f32, height: f32 },
+    Torus { center: Vec3, major_radius: f32, minor_radius: f32 },
+    Capsule { a: Vec3, b: Vec3, radius: f32 },
+    Cone { tip: Vec3, height: f32, angle: f32 },
+    Plane { normal: Vec3, distance: f32 },
+    Union { children: Vec<SceneNode> },
+    Intersection { children: Vec<SceneNode> },
+    /// `a` with `b` cut out
+    Subtraction { a: Box<SceneNode>, b: Box<SceneNode> },
+    SmoothUnion { a: Box<SceneNode>, b: Box<SceneNode>, k: f32 },
+    SmoothSubtraction { a: Box<SceneNode>, b: Box<SceneNode>, k: f32 },
+    SmoothIntersection { a: Box<SceneNode>, b: Box<SceneNode>, k: f32 },
+    Translate { offset: Vec3, child: Box<SceneNode> },
+    /// Rotation around Y in radians
+    RotateY { angle: f32, child: Box<SceneNode> },
+    Scale { scale: f32, child: Box<SceneNode> },
+    Round { radius: f32, child: Box<SceneNode> },
+    Onion { thickness: f32, child: Box<SceneNode> },
+    Twist { k: f32, child: Box<SceneNode> },
+    Bend { k: f32, child: Box<SceneNode> },
+    Repeat { period: Vec3, child: Box<SceneNode> },
+}
+
+/// A node in the scene tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneNode {
+    #[serde(flatten)]
+    pub shape: NodeShape,
+    /// Material name; nodes without one use their operand's material
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<String>,
+}
+
+impl SceneNode {
+    pub fn new(shape: NodeShape) -> Self {
+        Self { shape, material: None }
+    }
+
+    pub fn with_material(mut self, material: impl Into<String>) -> Self {
+        self.material = Some(material.into());
+        self
+    }
+
+    /// Approximate memory used by the node and its children
+    pub fn memory_size(&self) -> usize {
+        let material = self.material.as_ref().map_or(0, String::capacity);
+        let children: usize = self.children().iter().map(|(_, child)| child.memory_size()).sum();
+        std::mem::size_of::<Self>() + material + children
+    }
+
+    /// Child nodes with their path segment
+    fn children(&self) -> Vec<(String, &SceneNode)> {
+        match &self.shape {
+            NodeShape::Union { children } | NodeShape::Intersection { children } => {
+                children.iter().enumerate().map(|(i, c)| (format!("children[{}]", i), c)).collect()
+            }
+            NodeShape::Subtraction { a, b }
+            | NodeShape::SmoothUnion { a, b, .. }
+            | NodeShape::SmoothSubtraction { a, b, .. }
+            | NodeShape::SmoothIntersection { a, b, .. } => vec![("a".into(), &**a), ("b".into(), &**b)],
+            NodeShape::Translate { child, .. }
+            | NodeShape::RotateY { child, .. }
+            | NodeShape::Scale { child, .. }
+            | NodeShape::Round { child, .. }
+            | NodeShape::Onion { child, .. }
+            | NodeShape::Twist { child, .. }
+            | NodeShape::Bend { child, .. }
+            | NodeShape::Repeat { child, .. } => vec![("child".into(), &**child)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Distance and material name at a point
+    ///
+    /// A node's own material wins; unions take the material of the closest
+    /// child and other operations the material of their first operand.
+    pub fn distance_material(&self, p: Vec3) -> (f32, Option<&str>) {
+        let d = self.distance(p);
+        if let Some(material) = &self.material {
+            return (d, Some(material));
+        }
+        let material = match &self.shape {
+            NodeShape::Union { children } => children
+                .iter()
+                .map(|c| c.distance_material(p))
+                .min_by(|x, y| x.0.total_cmp(&y.0))
+                .and_then(|(_, m)| m),
+            _ => match self.children().first() {
+                // Transforms change the point the child sees
+                Some((_, child)) => child.distance_material(self.child_point(p)).1,
+                None => None,
+            },
+        };
+        (d, material)
+    }
+
+    /// The point the first child is evaluated at
+    fn child_point(&self, p: Vec3) -> Vec3 {
+        match &self.shape {
+            NodeShape::Translate { offset, .. } => p - *offset,
+            NodeShape::RotateY { angle, .. } => rotate_y(p, *angle),
+            NodeShape::Scale { scale, .. } => p / *scale,
+            NodeShape::Twist { k, .. } => twist(p, *k),
+            NodeShape::Bend { k, .. } => bend(p, *k),
+            NodeShape::Repeat { period, .. } => repeat(p, *period),
+            _ => p,
+        }
+    }
+}
+
+fn rotate_y(p: Vec3, angle: f32) -> Vec3 {
+    let (s, c) = angle.sin_cos();
+    Vec3::new(p.x * c + p.z * s, p.y, -p.x * s + p.z * c)
+}
+
+fn twist(p: Vec3, k: f32) -> Vec3 {
+    let (s, c) = (k * p.y).sin_cos();
+    Vec3::new(c * p.x - s * p.z, p.y, s * p.x + c * p.z)
+}
+
+fn bend(p: Vec3, k: f32) -> Vec3 {
+    let (s, c) = (k * p.x).sin_cos();
+    Vec3::new(c * p.x - s * p.y, s * p.x + c * p.y, p.z)
+}
+
+fn repeat(p: Vec3, period: Vec3) -> Vec3 {
+    ((p / period).fract() - Vec3::splat(0.5)) * period
+}
+
+impl Sdf for SceneNode {
+    fn distance(&self, p: Vec3) -> f32 {
+        match &self.shape {
+            NodeShape::Sphere { center, radius } => Sphere::new(*center, *radius).distance(p),
+            NodeShape::Box { center, half_extents } => Box3D::new(*center, *half_extents).distance(p),
+            NodeShape::RoundedBox { center, half_extents, radius } => {
+                RoundedBox::new(*center, *half_extents, *radius).distance(p)
+            }
+            NodeShape::Cylinder { center, radius, height } => Cylinder::new(*center, *radius, *height).distance(p),
+            NodeShape::Torus { center, major_radius, minor_radius } => {
+                Torus::new(*center, *major_radius, *minor_radius).distance(p)
+            }
+            NodeShape::Capsule { a, b, radius } => Capsule::new(*a, *b, *radius).distance(p),
+            NodeShape::Cone { tip, height, angle } => Cone::new(*tip, *height, *angle).distance(p),
+            NodeShape::Plane { normal, distance } => Plane::new(*normal, *distance).distance(p),
+            NodeShape::Union { children } => children.iter().map(|c| c.distance(p)).fold(f32::MAX, f32::min),
+            NodeShape::Intersection { children } => children.iter().map(|c| c.distance(p)).fold(f32::MIN, f32::max),
+            NodeShape::Subtraction { a, b } => Subtraction::new(&**a, &**b).distance(p),
+            NodeShape::SmoothUnion { a, b, k } => SmoothUnion::new(&**a, &**b, *k).distance(p),
+            NodeShape::SmoothSubtraction { a, b, k } => SmoothSubtraction::new(&**a, &**b, *k).distance(p),
+            NodeShape::SmoothIntersection { a, b, k } => SmoothIntersection::new(&**a, &**b, *k).distance(p),
+            NodeShape::Translate { offset, child } => Translate::new(&**child, *offset).distance(p),
+            NodeShape::RotateY { angle, child } => child.distance(rotate_y(p, *angle)),
+            NodeShape::Scale { scale, child } => Scale::new(&**child, *scale).distance(p),
+            NodeShape::Round { radius, child } => Round::new(&**child, *radius).distance(p),
+            NodeShape::Onion { thickness, child } => Onion::new(&**child, *thickness).distance(p),
+            NodeShape::Twist { k, child } => Twist::new(&**child, *k).distance(p),
+            NodeShape::Bend { k, child } => Bend::new(&**child, *k).distance(p),
+            NodeShape::Repeat { period, child } => Repeat::new(&**child, *period).distance(p),
+        }
+    }
+}
+
+/// Everything stored in a scene file
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SceneDescription {
+    #[serde(default)]
+    pub camera: SceneCamera,
+    #[serde(default)]
+    pub lights: Vec<SceneLight>,
+    /// Ambient light color
+    #[serde(default)]
+    pub ambient: Vec3,
+    #[serde(default)]
+    pub materials: Vec<SceneMaterial>,
+    /// Top-level nodes, combined with a union
+    #[serde(default)]
+    pub nodes: Vec<SceneNode>,
+}
+
+impl SceneDescription {
+    /// Parse a scene from JSON
+    pub fn from_json(json: &str) -> SceneFileResult<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        // Check node kinds first so errors point at the offending node
+        if let Some(nodes) = value.get("nodes").and_then(Value::as_array) {
+            for (i, node) in nodes.iter().enumerate() {
+                check_kinds(node, &format!("nodes[{}]", i))?;
+            }
+        }
+        let description: Self = serde_json::from_value(value)?;
+        description.check_materials()?;
+        Ok(description)
+    }
+
+    /// Serialize to pretty-printed JSON
+    pub fn to_json(&self) -> SceneFileResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Approximate memory used, e.g. for asset budgets
+    pub fn memory_size(&self) -> usize {
+        let materials: usize =
+            self.materials.iter().map(|m| std::mem::size_of::<SceneMaterial>() + m.name.capacity()).sum();
+        let lights = self.lights.len() * std::mem::size_of::<SceneLight>();
+        let nodes: usize = self.nodes.iter().map(SceneNode::memory_size).sum();
+        std::mem::size_of::<Self>() + materials + lights + nodes
+    }
+
+    /// Find a material by name
+    pub fn material(&self, name: &str) -> Option<&SceneMaterial> {
+        self.materials.iter().find(|m| m.name == name)
+    }
+
+    fn check_materials(&self) -> SceneFileResult<()> {
+        fn walk(desc: &SceneDescription, node: &SceneNode, path: String) -> SceneFileResult<()> {
+            if let Some(name) = &node.material {
+                if desc.material(name).is_none() {
+                    return Err(SceneFileError::UnknownMaterial { path, name: name.clone() });
+                }
+            }
+            for (segment, child) in node.children() {
+                walk(desc, child, format!("{}.{}", path, segment))?;
+            }
+            Ok(())
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            walk(self, node, format!("nodes[{}]", i))?;
+        }
+        Ok(())
+    }
+
+    /// Generate an HLSL `float sceneSDF(float3 p)` function (with helpers)
+    ///
+    /// Mirrors the CPU evaluation, so the GPU raymarcher sees the same scene.
+    pub fn to_hlsl(&self) -> String {
+        let mut body = String::new();
+        let mut next = 0;
+        let mut result = None;
+        for node in &self.nodes {
+            let d = emit_hlsl(node, "p", &mut body, &mut next);
+            result = Some(match result {
+                None => d,
+                Some(prev) => {
+                    let name = temp("d", &mut next);
+                    let _ = writeln!(body, "    float {} = min({}, {});", name, prev, d);
+                    name
+                }
+            });
+        }
+        let result = result.unwrap_or_else(|| "1e10".to_string());
+        format!("{}\nfloat sceneSDF(float3 p) {{\n{}    return {};\n}}\n", HLSL_HELPERS, body, result)
+    }
+}
+
+/// Check every node has a known `kind`, reporting the path of the first bad one
+fn check_kinds(node: &Value, path: &str) -> SceneFileResult<()> {
+    let Some(kind) = node.get("kind") else {
+        return Err(SceneFileError::MissingKind { path: path.to_string() });
+    };
+    let kind = kind.as_str().unwrap_or_default();
+    if !NODE_KINDS.contains(&kind) {
+        return Err(SceneFileError::UnknownKind { path: path.to_string(), kind: kind.to_string() });
+    }
+    if let Some(children) = node.get("children").and_then(Value::as_array) {
+        for (i, child) in children.iter().enumerate() {
+            check_kinds(child, &format!("{}.children[{}]", path, i))?;
+        }
+    }
+    for key in ["a", "b", "child"] {
+        if let Some(child) = node.get(key).filter(|c| c.is_object()) {
+            check_kinds(child, &format!("{}.{}", path, key))?;
+        }
+    }
+    Ok(())
+}
+
+/// HLSL versions of the primitives and domain operations
+const HLSL_HELPERS: &str = r#"
+float sdBox(float3 p, float3 b) {
+    float3 q = abs(p) - b;
+    return length(max(q, 0.0)) + min(max(q.x, max(q.y, q.z)), 0.0);
+}
+
+float sdCylinder(float3 p, float r, float h) {
+    float2 d = float2(length(p.xz) - r, abs(p.y) - h * 0.5);
+    return min(max(d.x, d.y), 0.0) + length(max(d, 0.0));
+}
+
+float sdTorus(float3 p, float R, float r) {
+    return length(float2(length(p.xz) - R, p.y)) - r;
+}
+
+float sdCapsule(float3 p, float3 a, float3 b, float r) {
+    float3 pa = p - a, ba = b - a;
+    float h = saturate(dot(pa, ba) / dot(ba, ba));
+    return length(pa - ba * h) - r;
+}
+
+float sdCone(float3 p, float h, float angle) {
+    float q = length(p.xz);
+    return max(cos(angle) * q + sin(angle) * p.y, -p.y - h);
+}
+
+float opSmoothUnion(float d1, float d2, float k) {
+    float h = saturate(0.5 + 0.5 * (d2 - d1) / k);
+    return lerp(d2, d1, h) - k * h * (1.0 - h);
+}
+
+float opSmoothSubtraction(float d1, float d2, float k) {
+    float h = saturate(0.5 - 0.5 * (d2 + d1) / k);
+    return lerp(d1, -d2, h) + k * h * (1.0 - h);
+}
+
+float opSmoothIntersection(float d1, float d2, float k) {
+    float h = saturate(0.5 - 0.5 * (d2 - d1) / k);
+    return lerp(d2, d1, h) + k * h * (1.0 - h);
+}
+
+float3 opRotateY(float3 p, float a) {
+    float c = cos(a), s = sin(a);
+    return float3(p.x * c + p.z * s, p.y, -p.x * s + p.z * c);
+}
+
+float3 opTwist(float3 p, float k) {
+    float c = cos(k * p.y), s = sin(k * p.y);
+    return float3(c * p.x - s * p.z, p.y, s * p.x + c * p.z);
+}
+
+float3 opBend(float3 p, float k) {
+    float c = cos(k * p.x), s = sin(k * p.x);
+    return float3(c * p.x - s * p.y, s * p.x + c * p.y, p.z);
+}
+
+float3 opRepeat(float3 p, float3 period) {
+    float3 q = p / period;
+    return (q - trunc(q) - 0.5) * period;
+}
+"#;
+
+fn f3(v: Vec3) -> String {
+    format!("float3({:?}, {:?}, {:?})", v.x, v.y, v.z)
+}
+
+/// Fresh HLSL variable name
+fn temp(prefix: &str, next: &mut u32) -> String {
+    let name = format!("{}{}", prefix, next);
+    *next += 1;
+    name
+}
+
+/// Emit statements for a node; returns the variable holding its distance
+fn emit_hlsl(node: &SceneNode, p: &str, out: &mut String, next: &mut u32) -> String {
+    let expr = match &node.shape {
+        NodeShape::Sphere { center, radius } => format!("length({} - {}) - {:?}", p, f3(*center), radius),
+        NodeShape::Box { center, half_extents } => format!("sdBox({} - {}, {})", p, f3(*center), f3(*half_extents)),
+        NodeShape::RoundedBox { center, half_extents, radius } => format!(
+            "sdBox({} - {}, {} - {:?}) - {:?}",
+            p, f3(*center), f3(*half_extents), radius, radius
+        ),
+        NodeShape::Cylinder { center, radius, height } => {
+            format!("sdCylinder({} - {}, {:?}, {:?})", p, f3(*center), radius, height)
+        }
+        NodeShape::Torus { center, major_radius, minor_radius } => {
+            format!("sdTorus({} - {}, {:?}, {:?})", p, f3(*center), major_radius, minor_radius)
+        }
+        NodeShape::Capsule { a, b, radius } => format!("sdCapsule({}, {}, {}, {:?})", p, f3(*a), f3(*b), radius),
+        NodeShape::Cone { tip, height, angle } => format!("sdCone({} - {}, {:?}, {:?})", p, f3(*tip), height, angle),
+        NodeShape::Plane { normal, distance } => format!("dot({}, {}) + {:?}", p, f3(*normal), distance),
+        NodeShape::Union { children } | NodeShape::Intersection { children } => {
+            let op = if matches!(node.shape, NodeShape::Union { .. }) { "min" } else { "max" };
+            let ds: Vec<String> = children.iter().map(|c| emit_hlsl(c, p, out, next)).collect();
+            match ds.split_first() {
+                None => "1e10".to_string(),
+                Some((first, rest)) => rest.iter().fold(first.clone(), |acc, d| format!("{}({}, {})", op, acc, d)),
+            }
+        }
+        NodeShape::Subtraction { a, b } => {
+            let (da, db) = (emit_hlsl(a, p, out, next), emit_hlsl(b, p, out, next));
+            format!("max({}, -{})", da, db)
+        }
+        NodeShape::SmoothUnion { a, b, k }
+        | NodeShape::SmoothSubtraction { a, b, k }
+        | NodeShape::SmoothIntersection { a, b, k } => {
+            let function = match node.shape {
+                NodeShape::SmoothUnion { .. } => "opSmoothUnion",
+                NodeShape::SmoothSubtraction { .. } => "opSmoothSubtraction",
+                _ => "opSmoothIntersection",
+            };
+            let (da, db) = (emit_hlsl(a, p, out, next), emit_hlsl(b, p, out, next));
+            format!("{}({}, {}, {:?})", function, da, db, k)
+        }
+        NodeShape::Scale { scale, child } => {
+            let q = temp("q", next);
+            let _ = writeln!(out, "    float3 {} = {} / {:?};", q, p, scale);
+            format!("{} * {:?}", emit_hlsl(child, &q, out, next), scale)
+        }
+        NodeShape::Round { radius, child } => format!("{} - {:?}", emit_hlsl(child, p, out, next), radius),
+        NodeShape::Onion { thickness, child } => format!("abs({}) - {:?}", emit_hlsl(child, p, out, next), thickness),
+        NodeShape::Translate { child, .. }
+        | NodeShape::RotateY { child, .. }
+        | NodeShape::Twist { child, .. }
+        | NodeShape::Bend { child, .. }
+        | NodeShape::Repeat { child, .. } => {
+            let point = match &node.shape {
+                NodeShape::Translate { offset, .. } => format!("{} - {}", p, f3(*offset)),
+                NodeShape::RotateY { angle, .. } => format!("opRotateY({}, {:?})", p, angle),
+                NodeShape::Twist { k, .. } => format!("opTwist({}, {:?})", p, k),
+                NodeShape::Bend { k, .. } => format!("opBend({}, {:?})", p, k),
+                NodeShape::Repeat { period, .. } => format!("opRepeat({}, {})", p, f3(*period)),
+                _ => unreachable!(),
+            };
+            let q = temp("q", next);
+            let _ = writeln!(out, "    float3 {} = {};", q, point);
+            return emit_hlsl(child, &q, out, next);
+        }
+    };
+
+    let name = temp("d", next);
+    let _ = writeln!(out, "    float {} = {};", name, expr);
+    name
+}
+
+impl SdfScene {
+    /// Load a scene file
+    pub fn from_file(path: impl AsRef<Path>) -> SceneFileResult<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse a scene from JSON
+    pub fn from_json(json: &str) -> SceneFileResult<Self> {
+        Ok(Self::from_description(SceneDescription::from_json(json)?))
+    }
+
+    /// Save the scene to a file
+    ///
+    /// Only scenes built from nodes can be saved; objects added with `add`
+    /// are opaque and produce `SceneFileError::NotSerializable`.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> SceneFileResult<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Serialize the scene to JSON
+    pub fn to_json(&self) -> SceneFileResult<String> {
+        if !self.objects.is_empty() {
+            return Err(SceneFileError::NotSerializable(self.objects.len()));
+        }
+        self.description.to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDF_SCENE: &str = include_str!("../../examples/scenes/sdf_scene.json");
+    const GAME_SCENE: &str = include_str!("../../examples/scenes/game_scene.json");
+
+    /// An object from an example's hand-written scene
+    struct SourceObject {
+        distance: Box<dyn Fn(Vec3) -> f32>,
+        color: Vec3,
+        /// The scene file uses a bound (same sign, never larger) instead
+        bound: bool,
+    }
+
+    fn sd_box(p: Vec3, b: Vec3) -> f32 {
+        let q = p.abs() - b;
+        q.max(Vec3::ZERO).length() + q.x.max(q.y.max(q.z)).min(0.0)
+    }
+
+    fn exact(color: Vec3, distance: impl Fn(Vec3) -> f32 + 'static) -> SourceObject {
+        SourceObject { distance: Box::new(distance), color, bound: false }
+    }
+
+    /// `Scene::new` in examples/sdf_scene.rs, before any animation
+    fn sdf_scene_source() -> Vec<SourceObject> {
+        let mut objects = vec![exact(Vec3::new(0.3, 0.35, 0.3), |p| {
+            sd_box(p - Vec3::new(0.0, -0.5, 0.0), Vec3::new(20.0, 0.5, 20.0) * 0.5)
+        })];
+        let cubes = [
+            (Vec3::new(-3.0, 0.5, -2.0), Vec3::new(0.8, 0.4, 0.9)),
+            (Vec3::new(0.0, 0.75, 0.0), Vec3::new(0.2, 0.6, 0.9)),
+            (Vec3::new(3.0, 0.5, -2.0), Vec3::new(0.9, 0.3, 0.3)),
+            (Vec3::new(-2.0, 0.4, 3.0), Vec3::new(0.9, 0.8, 0.2)),
+            (Vec3::new(2.5, 0.5, 2.5), Vec3::new(0.3, 0.8, 0.4)),
+        ];
+        for (i, (position, color)) in cubes.into_iter().enumerate() {
+            let size = Vec3::new(0.8, position.y * 2.0, 0.8);
+            objects.push(exact(color, move |p| sd_box(rotate_y(p - position, i as f32 * 0.3), size * 0.5)));
+        }
+        objects.push(exact(Vec3::splat(0.95), |p| (p - Vec3::new(-1.0, 1.0, 1.5)).length() - 0.6));
+        objects
+    }
+
+    /// `GameScene::new` in examples/game_scene.rs, before any animation
+    fn game_scene_source() -> Vec<SourceObject> {
+        let cube = |center: Vec3, size: f32, color: Vec3| exact(color, move |p| sd_box(p - center, Vec3::splat(size * 0.5)));
+        let sphere = |center: Vec3, radius: f32, color: Vec3| exact(color, move |p| (p - center).length() - radius);
+        let cylinder = |center: Vec3, radius: f32, height: f32, color: Vec3| {
+            exact(color, move |p| {
+                let p = p - center;
+                let d = Vec2::new(Vec2::new(p.x, p.z).length() - radius, p.y.abs() - height * 0.5);
+                d.x.max(d.y).min(0.0) + d.max(Vec2::ZERO).length()
+            })
+        };
+        // Exact cone with its base centered on `base`
+        let pyramid = |base: Vec3, width: f32, height: f32, color: Vec3| {
+            let distance = move |p: Vec3| {
+                let p = p - base;
+                let q = Vec2::new(Vec2::new(p.x, p.z).length(), p.y);
+                let (tip, rim) = (Vec2::new(0.0, height), Vec2::new(width * 0.5, 0.0));
+                let (e, w) = (rim - tip, q - tip);
+                let d1 = w - e * (w.dot(e) / e.dot(e)).clamp(0.0, 1.0);
+                let d2 = q - Vec2::new(q.x.min(rim.x), 0.0);
+                let inside = q.y >= 0.0 && q.x <= rim.x * (1.0 - q.y / height);
+                d1.length().min(d2.length()) * if inside { -1.0 } else { 1.0 }
+            };
+            SourceObject { distance: Box::new(distance), color, bound: true }
+        };
+
+        let stone = Vec3::new(0.7, 0.7, 0.75);
+        let gold = Vec3::new(0.95, 0.85, 0.3);
+        let pillars = [
+            Vec3::new(-4.0, 1.0, -4.0),
+            Vec3::new(4.0, 1.0, -4.0),
+            Vec3::new(-4.0, 1.0, 4.0),
+            Vec3::new(4.0, 1.0, 4.0),
+        ];
+        let mut objects = vec![
+            exact(Vec3::new(0.25, 0.3, 0.25), |p| sd_box(p - Vec3::new(0.0, -0.25, 0.0), Vec3::new(50.0, 0.5, 50.0))),
+            cube(Vec3::new(0.0, 0.5, 0.0), 1.5, Vec3::new(0.2, 0.5, 0.8)),
+            cube(Vec3::new(0.0, 1.5, 0.0), 1.2, Vec3::new(0.3, 0.6, 0.9)),
+            cube(Vec3::new(0.0, 2.3, 0.0), 0.9, Vec3::new(0.4, 0.7, 1.0)),
+        ];
+        objects.extend(pillars.map(|position| cylinder(position, 0.4, 2.0, stone)));
+        objects.extend(pillars.map(|position| sphere(position + Vec3::new(0.0, 2.3, 0.0), 0.5, gold)));
+        objects.extend([
+            pyramid(Vec3::new(-3.0, 0.0, 0.0), 1.2, 1.5, Vec3::new(0.9, 0.3, 0.2)),
+            pyramid(Vec3::new(3.0, 0.0, 0.0), 1.2, 1.5, Vec3::new(0.2, 0.8, 0.3)),
+            pyramid(Vec3::new(0.0, 0.0, -4.0), 1.0, 1.2, Vec3::new(0.8, 0.2, 0.8)),
+            cube(Vec3::new(-2.0, 0.4, 2.5), 0.8, Vec3::new(0.9, 0.6, 0.2)),
+            cube(Vec3::new(2.5, 0.35, 2.0), 0.7, Vec3::new(0.3, 0.9, 0.5)),
+            cube(Vec3::new(-1.5, 0.3, -2.5), 0.6, Vec3::new(0.9, 0.4, 0.6)),
+            sphere(Vec3::new(1.5, 0.6, 3.0), 0.6, Vec3::new(0.9, 0.9, 0.95)),
+        ]);
+        objects
+    }
+
+    /// Points on a grid over the scenes' objects
+    fn sample_points() -> impl Iterator<Item = Vec3> {
+        (0..17).flat_map(|x| {
+            (0..11).flat_map(move |y| (0..17).map(move |z| Vec3::new(x as f32 * 0.75 - 6.0, y as f32 * 0.5 - 1.0, z as f32 * 0.75 - 6.0)))
+        })
+    }
+
+    /// Check each node of a scene file against the object it was written from
+    fn assert_matches_source(json: &str, source: &[SourceObject]) {
+        let description = SceneDescription::from_json(json).unwrap();
+        assert_eq!(description.nodes.len(), source.len());
+        for (i, (node, object)) in description.nodes.iter().zip(source).enumerate() {
+            let material = description.material(node.material.as_deref().unwrap()).unwrap();
+            assert!((material.color - object.color).abs().max_element() < 1e-6, "nodes[{}] material", i);
+
+            for p in sample_points() {
+                let (expected, actual) = ((object.distance)(p), node.distance(p));
+                let tolerance = 1e-4 * (1.0 + expected.abs());
+                if object.bound {
+                    assert!(actual <= expected + tolerance, "nodes[{}] at {}: {} > {}", i, p, actual, expected);
+                    if expected.abs() > 1e-3 {
+                        assert_eq!(actual < 0.0, expected < 0.0, "nodes[{}] at {}: {} vs {}", i, p, actual, expected);
+                    }
+                } else {
+                    assert!((actual - expected).abs() < tolerance, "nodes[{}] at {}: {} vs {}", i, p, actual, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn example_scene_files_match_their_source_scenes() {
+        assert_matches_source(SDF_SCENE, &sdf_scene_source());
+        assert_matches_source(GAME_SCENE, &game_scene_source());
+
+        let scene = SdfScene::from_json(SDF_SCENE).unwrap();
+        let (d, material) = scene.distance_material(Vec3::new(-1.0, 1.0, 1.5));
+        assert!((d + 0.6).abs() < 1e-6);
+        assert_eq!(material.unwrap().name, "chrome");
+        assert_eq!(scene.distance_material(Vec3::new(8.0, -0.5, 8.0)).1.unwrap().name, "ground");
+    }
+
+    #[test]
+    fn json_round_trip() {
+        for json in [SDF_SCENE, GAME_SCENE] {
+            let description = SceneDescription::from_json(json).unwrap();
+            assert_eq!(SceneDescription::from_json(&description.to_json().unwrap()).unwrap(), description);
+        }
+
+        // Every node kind, nested
+        let leaf = |x: f32| SceneNode::new(NodeShape::Sphere { center: Vec3::new(x, 0.0, 0.0), radius: 0.5 });
+        let boxed = |x: f32| Box::new(leaf(x));
+        let shapes = vec![
+            NodeShape::Box { center: Vec3::ZERO, half_extents: Vec3::ONE },
+            NodeShape::RoundedBox { center: Vec3::ZERO, half_extents: Vec3::ONE, radius: 0.1 },
+            NodeShape::Cylinder { center: Vec3::ZERO, radius: 1.0, height: 2.0 },
+            NodeShape::Torus { center: Vec3::ZERO, major_radius: 1.0, minor_radius: 0.25 },
+            NodeShape::Capsule { a: Vec3::ZERO, b: Vec3::Y, radius: 0.3 },
+            NodeShape::Cone { tip: Vec3::Y, height: 1.0, angle: 0.4 },
+            NodeShape::Plane { normal: Vec3::Y, distance: 0.0 },
+            NodeShape::Union { children: vec![leaf(0.0), leaf(1.0)] },
+            NodeShape::Intersection { children: vec![leaf(0.0), leaf(0.5)] },
+            NodeShape::Subtraction { a: boxed(0.0), b: boxed(0.5) },
+            NodeShape::SmoothUnion { a: boxed(0.0), b: boxed(1.0), k: 0.3 },
+            NodeShape::SmoothSubtraction { a: boxed(0.0), b: boxed(0.5), k: 0.2 },
+            NodeShape::SmoothIntersection { a: boxed(0.0), b: boxed(0.5), k: 0.2 },
+            NodeShape::Translate { offset: Vec3::X, child: boxed(0.0) },
+            NodeShape::RotateY { angle: 0.5, child: boxed(1.0) },
+            NodeShape::Scale { scale: 2.0, child: boxed(0.0) },
+            NodeShape::Round { radius: 0.1, child: boxed(0.0) },
+            NodeShape::Onion { thickness: 0.05, child: boxed(0.0) },
+            NodeShape::Twist { k: 0.5, child: boxed(0.0) },
+            NodeShape::Bend { k: 0.5, child: boxed(0.0) },
+            NodeShape::Repeat { period: Vec3::splat(4.0), child: boxed(0.0) },
+        ];
+        let description = SceneDescription {
+            materials: vec![SceneMaterial { name: "red".into(), color: Vec3::X, roughness: 0.5, metallic: 0.0 }],
+            lights: vec![SceneLight::Point { position: Vec3::Y, color: Vec3::ONE, range: 10.0 }],
+            nodes: shapes.into_iter().map(|shape| SceneNode::new(shape).with_material("red")).collect(),
+            ..Default::default()
+        };
+        let json = description.to_json().unwrap();
+        assert_eq!(SceneDescription::from_json(&json).unwrap(), description);
+        assert_eq!(description.nodes.len() + 1, NODE_KINDS.len());
+    }
+
+    #[test]
+    fn memory_size_grows_with_the_scene() {
+        let empty = SceneDescription::default().memory_size();
+        let small = SceneDescription::from_json(SDF_SCENE).unwrap().memory_size();
+        let large = SceneDescription::from_json(GAME_SCENE).unwrap().memory_size();
+        assert!(small >= empty + 7 * std::mem::size_of::<SceneNode>());
+        assert!(large >= empty + 19 * std::mem::size_of::<SceneNode>());
+        assert!(large > small);
+
+        // Nested children count too
+        let leaf = SceneNode::new(NodeShape::Sphere { center: Vec3::ZERO, radius: 1.0 });
+        let union = SceneNode::new(NodeShape::Union { children: vec![leaf.clone(), leaf.clone()] });
+        assert_eq!(union.memory_size(), 3 * leaf.memory_size());
+    }
+
+    #[test]
+    fn errors_name_the_offending_node() {
+        let json = r#"{ "nodes": [{ "kind": "union", "children": [
+            { "kind": "sphere", "center": [0, 0, 0], "radius": 1 },
+            { "kind": "translate", "offset": [1, 0, 0], "child": { "kind": "blob" } }
+        ] }] }"#;
+        match SceneDescription::from_json(json) {
+            Err(SceneFileError::UnknownKind { path, kind }) => {
+                assert_eq!((path.as_str(), kind.as_str()), ("nodes[0].children[1].child", "blob"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let json = r#"{ "nodes": [{ "kind": "sphere", "center": [0, 0, 0], "radius": 1, "material": "gold" }] }"#;
+        assert!(matches!(SceneDescription::from_json(json), Err(SceneFileError::UnknownMaterial { .. })));
+
+        let mut scene = SdfScene::from_json(SDF_SCENE).unwrap();
+        scene.add(Sphere::new(Vec3::ZERO, 1.0));
+        assert!(matches!(scene.to_json(), Err(SceneFileError::NotSerializable(1))));
+    }
+}
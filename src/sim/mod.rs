@@ -0,0 +1,299 @@
+//! Fixed-timestep simulation with snapshot/rollback hooks
+//!
+//! `Simulation` owns the game state and steps it at `FIXED_TIMESTEP` with
+//! the input recorded for each tick. With `SimHooks` registered it keeps a
+//! snapshot and hash per tick, so it can roll back, re-run ticks with
+//! corrected input and compare hashes against another run. There is no
+//! networking here, only the plumbing a rollback netcode layer needs.
+
+use crate::easy::FIXED_TIMESTEP;
+use crate::events::Event;
+use crate::testing::RecordedInput;
+use std::collections::VecDeque;
+use thiserror::Error;
+
+/// Simulation errors
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SimError {
+    #[error("Rollback needs SimHooks")]
+    NoHooks,
+    #[error("No snapshot for tick {0}")]
+    SnapshotUnavailable(u64),
+}
+
+pub type SimResult<T> = Result<T, SimError>;
+
+/// Hooks to save, restore and hash the simulation state
+pub struct SimHooks<S> {
+    pub snapshot: fn(&S) -> Vec<u8>,
+    pub restore: fn(&mut S, &[u8]),
+    pub hash: fn(&S) -> u64,
+}
+
+impl<S> Clone for SimHooks<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for SimHooks<S> {}
+
+type StepFn<S> = Box<dyn FnMut(&mut S, &[Event], f32)>;
+
+/// A deterministic fixed-timestep simulation
+pub struct Simulation<S> {
+    state: S,
+    step: StepFn<S>,
+    hooks: Option<SimHooks<S>>,
+    tick: u64,
+    input: RecordedInput,
+    /// (tick, snapshot taken before running it), oldest first
+    snapshots: VecDeque<(u64, Vec<u8>)>,
+    max_snapshots: usize,
+    /// Hash after each tick, indexed by tick
+    hashes: Vec<u64>,
+}
+
+impl<S> Simulation<S> {
+    /// Create a simulation at tick 0
+    ///
+    /// `step` advances the state by one tick given that tick's events and
+    /// the timestep.
+    pub fn new(state: S, step: impl FnMut(&mut S, &[Event], f32) + 'static) -> Self {
+        Self {
+            state,
+            step: Box::new(step),
+            hooks: None,
+            tick: 0,
+            input: RecordedInput::new(),
+            snapshots: VecDeque::new(),
+            max_snapshots: 120,
+            hashes: Vec::new(),
+        }
+    }
+
+    /// Register the snapshot/restore/hash hooks
+    pub fn with_hooks(mut self, hooks: SimHooks<S>) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// How many ticks back `rollback_to` can go (default 120, 2 seconds)
+    pub fn with_max_rollback(mut self, ticks: usize) -> Self {
+        self.max_snapshots = ticks.max(1);
+        self
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Mutable state; changes are not recorded, so replays won't repeat them
+    pub fn state_mut(&mut self) -> &mut S {
+        &mut self.state
+    }
+
+    /// Next tick to run
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Record an event for `tick`
+    ///
+    /// An event for a tick that already ran takes effect after
+    /// `rollback_to` and `simulate_to` re-run it.
+    pub fn record_input(&mut self, tick: u64, event: Event) {
+        self.input.record(tick, event);
+    }
+
+    /// Recorded input for every tick
+    pub fn input(&self) -> &RecordedInput {
+        &self.input
+    }
+
+    /// Hash of the current state (None without hooks)
+    pub fn hash(&self) -> Option<u64> {
+        self.hooks.map(|hooks| (hooks.hash)(&self.state))
+    }
+
+    /// Hash after each tick run so far, indexed by tick (empty without hooks)
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Run one tick
+    pub fn advance(&mut self) {
+        if let Some(hooks) = self.hooks {
+            if self.snapshots.len() == self.max_snapshots {
+                self.snapshots.pop_front();
+            }
+            self.snapshots.push_back((self.tick, (hooks.snapshot)(&self.state)));
+        }
+
+        let events: Vec<Event> = self.input.events(self.tick).cloned().collect();
+        (self.step)(&mut self.state, &events, FIXED_TIMESTEP);
+
+        if let Some(hooks) = self.hooks {
+            self.hashes.truncate(self.tick as usize);
+            self.hashes.push((hooks.hash)(&self.state));
+        }
+        self.tick += 1;
+    }
+
+    /// Run ticks until `tick` is the next one to run
+    pub fn simulate_to(&mut self, tick: u64) {
+        while self.tick < tick {
+            self.advance();
+        }
+    }
+
+    /// Restore the state from just before `tick` ran
+    ///
+    /// Later ticks are forgotten; call `simulate_to` to re-run them with
+    /// the (possibly corrected) recorded input.
+    pub fn rollback_to(&mut self, tick: u64) -> SimResult<()> {
+        let hooks = self.hooks.ok_or(SimError::NoHooks)?;
+        let index = self
+            .snapshots
+            .iter()
+            .position(|(t, _)| *t == tick)
+            .ok_or(SimError::SnapshotUnavailable(tick))?;
+
+        (hooks.restore)(&mut self.state, &self.snapshots[index].1);
+        self.snapshots.truncate(index);
+        self.hashes.truncate(tick as usize);
+        self.tick = tick;
+        Ok(())
+    }
+
+    /// Roll back `ticks` ticks and re-run them; returns the new hash
+    ///
+    /// Zero ticks leaves the simulation as it is.
+    pub fn resimulate(&mut self, ticks: u64) -> SimResult<u64> {
+        let hooks = self.hooks.ok_or(SimError::NoHooks)?;
+        if ticks == 0 {
+            return Ok((hooks.hash)(&self.state));
+        }
+        let current = self.tick;
+        self.rollback_to(current.saturating_sub(ticks))?;
+        self.simulate_to(current);
+        Ok((hooks.hash)(&self.state))
+    }
+}
+
+/// First tick where two runs' hashes differ (None if the shared ticks match)
+pub fn first_divergence(recorded: &[u64], replayed: &[u64]) -> Option<u64> {
+    recorded
+        .iter()
+        .zip(replayed)
+        .position(|(a, b)| a != b)
+        .map(|tick| tick as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::MouseEvent;
+    use crate::hashing::xxh64;
+    use crate::math::Vec2;
+
+    /// A point that moves towards the last mouse position
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Chaser {
+        position: Vec2,
+        target: Vec2,
+    }
+
+    fn step(state: &mut Chaser, events: &[Event], dt: f32) {
+        for event in events {
+            if let Event::MouseMove(mouse) = event {
+                state.target = mouse.position;
+            }
+        }
+        state.position += (state.target - state.position) * (5.0 * dt);
+    }
+
+    fn to_bytes(state: &Chaser) -> Vec<u8> {
+        [state.position.x, state.position.y, state.target.x, state.target.y]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+
+    const HOOKS: SimHooks<Chaser> = SimHooks {
+        snapshot: to_bytes,
+        restore: |state, bytes| {
+            let value = |i: usize| f32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap());
+            state.position = Vec2::new(value(0), value(1));
+            state.target = Vec2::new(value(2), value(3));
+        },
+        hash: |state| xxh64(&to_bytes(state), 0),
+    };
+
+    fn mouse_to(x: f32, y: f32) -> Event {
+        Event::MouseMove(MouseEvent { position: Vec2::new(x, y), ..Default::default() })
+    }
+
+    fn simulation() -> Simulation<Chaser> {
+        let mut sim = Simulation::new(Chaser::default(), step).with_hooks(HOOKS);
+        sim.record_input(0, mouse_to(100.0, 0.0));
+        sim.record_input(20, mouse_to(0.0, 50.0));
+        sim
+    }
+
+    #[test]
+    fn rollback_and_replay_restore_the_same_hashes() {
+        let mut sim = simulation();
+        sim.simulate_to(60);
+        let hashes = sim.hashes().to_vec();
+        let state = sim.state().clone();
+
+        // Perturb the state outside the recorded input, then undo it by
+        // rolling back past it and replaying
+        sim.state_mut().position = Vec2::new(-1000.0, -1000.0);
+        assert_ne!(sim.hash(), Some(hashes[59]));
+        assert_eq!(sim.resimulate(10).unwrap(), hashes[59]);
+        assert_eq!(sim.tick(), 60);
+        assert_eq!(sim.hashes(), hashes);
+        assert_eq!(sim.state(), &state);
+    }
+
+    #[test]
+    fn late_input_matches_a_run_that_had_it_all_along() {
+        let mut reference = simulation();
+        reference.record_input(55, mouse_to(-30.0, 10.0));
+        reference.simulate_to(60);
+
+        let mut sim = simulation();
+        sim.simulate_to(60);
+        sim.record_input(55, mouse_to(-30.0, 10.0));
+        assert_eq!(first_divergence(reference.hashes(), sim.hashes()), Some(55));
+
+        sim.resimulate(10).unwrap();
+        assert_eq!(sim.hashes(), reference.hashes());
+        assert_eq!(first_divergence(reference.hashes(), sim.hashes()), None);
+    }
+
+    #[test]
+    fn resimulating_zero_ticks_changes_nothing() {
+        let mut sim = simulation();
+        sim.simulate_to(30);
+        let hashes = sim.hashes().to_vec();
+        assert_eq!(sim.resimulate(0), Ok(hashes[29]));
+        assert_eq!((sim.tick(), sim.hashes()), (30, hashes.as_slice()));
+        assert_eq!(sim.resimulate(30), Ok(hashes[29]));
+    }
+
+    #[test]
+    fn rollback_limits() {
+        let mut sim = simulation().with_max_rollback(10);
+        sim.simulate_to(30);
+        assert_eq!(sim.rollback_to(19), Err(SimError::SnapshotUnavailable(19)));
+        assert_eq!(sim.resimulate(10).map(|_| sim.tick()), Ok(30));
+
+        let mut plain = Simulation::new(Chaser::default(), step);
+        plain.simulate_to(5);
+        assert_eq!(plain.resimulate(0), Err(SimError::NoHooks));
+        assert!(plain.hashes().is_empty());
+    }
+}
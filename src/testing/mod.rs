@@ -0,0 +1,48 @@
+//! Helpers for deterministic rendering checks
+//!
+//! `EasyApp::run_deterministic` renders with the software backend at a fixed
+//! timestep and returns one `hash_frame` per frame. Comparing the sequence
+//! against a committed one catches rendering changes without storing
+//! golden images.
+
+use crate::events::Event;
+use crate::hashing::xxh64;
+
+/// Hash a frame's pixels (XXH64 of the little-endian bytes, seed 0)
+pub fn hash_frame(pixels: &[u32]) -> u64 {
+    let bytes: Vec<u8> = pixels.iter().flat_map(|p| p.to_le_bytes()).collect();
+    xxh64(&bytes, 0)
+}
+
+/// Input events to replay, keyed by frame number
+#[derive(Debug, Clone, Default)]
+pub struct RecordedInput {
+    events: Vec<(u64, Event)>,
+}
+
+impl RecordedInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an event delivered at the start of `frame` (0-based)
+    pub fn record(&mut self, frame: u64, event: Event) {
+        // Keep events sorted by frame, in recording order within a frame
+        let index = self.events.partition_point(|(f, _)| *f <= frame);
+        self.events.insert(index, (frame, event));
+    }
+
+    /// Events for one frame, in recording order
+    pub fn events(&self, frame: u64) -> impl Iterator<Item = &Event> {
+        let start = self.events.partition_point(|(f, _)| *f < frame);
+        self.events[start..].iter().take_while(move |(f, _)| *f == frame).map(|(_, e)| e)
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
@@ -1,5 +1,6 @@
 //! Window management for EPICX
 
+use crate::events::AppLifecycleEvent;
 use crate::math::Rect;
 use thiserror::Error;
 
@@ -104,3 +105,31 @@ impl Window {
         // In a full implementation, this would poll OS events
     }
 }
+
+// Message and notification codes from WinUser.h / WtsApi32.h
+const WM_POWERBROADCAST: u32 = 0x0218;
+const WM_WTSSESSION_CHANGE: u32 = 0x02B1;
+const PBT_APMSUSPEND: usize = 0x4;
+const PBT_APMRESUMESUSPEND: usize = 0x7;
+const PBT_APMRESUMEAUTOMATIC: usize = 0x12;
+const WTS_CONSOLE_CONNECT: usize = 0x1;
+const WTS_CONSOLE_DISCONNECT: usize = 0x2;
+const WTS_REMOTE_CONNECT: usize = 0x3;
+const WTS_REMOTE_DISCONNECT: usize = 0x4;
+
+/// Map a window message to a lifecycle event
+///
+/// Handles `WM_POWERBROADCAST` (sleep/wake) and `WM_WTSSESSION_CHANGE`
+/// (console or remote desktop connect/disconnect, which can move the
+/// session to another adapter). Session messages only arrive after
+/// `WTSRegisterSessionNotification` was called for the window. Pass the
+/// result to `Graphics::handle_lifecycle`.
+pub fn lifecycle_event_from_message(message: u32, wparam: usize) -> Option<AppLifecycleEvent> {
+    match (message, wparam) {
+        (WM_POWERBROADCAST, PBT_APMSUSPEND) => Some(AppLifecycleEvent::Suspending),
+        (WM_POWERBROADCAST, PBT_APMRESUMESUSPEND | PBT_APMRESUMEAUTOMATIC) => Some(AppLifecycleEvent::Resumed),
+        (WM_WTSSESSION_CHANGE, WTS_CONSOLE_DISCONNECT | WTS_REMOTE_DISCONNECT) => Some(AppLifecycleEvent::Suspending),
+        (WM_WTSSESSION_CHANGE, WTS_CONSOLE_CONNECT | WTS_REMOTE_CONNECT) => Some(AppLifecycleEvent::Resumed),
+        _ => None,
+    }
+}
@@ -0,0 +1,315 @@
+//! Offscreen rendering and windows shared by the WARP integration tests
+//!
+//! `Offscreen` draws colored triangles into an RGBA8 render target and reads
+//! the pixels back, so tests can check what actually reached the screen.
+//! `TestWindow` is a hidden window for tests that need a swap chain, and
+//! `Readback` copies any RGBA8 target (e.g. a back buffer) out of a frame.
+
+// Each test file uses a different subset
+#![allow(dead_code)]
+
+use epicx::dx12::{
+    Buffer, BufferDesc, BufferUsage, CommandAllocator, CommandList, CommandQueue, DepthMode, DepthStencil,
+    DescriptorHeap, Device, Pipeline, PipelineOptions, PipelineState, RenderTarget, RootSignature,
+    ShaderCompiler, ShaderType, VertexBuffer,
+};
+use epicx::graphics::{Graphics, GraphicsConfig};
+use windows::core::{w, PCSTR};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Direct3D::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST;
+use windows::Win32::Graphics::Direct3D12::*;
+use windows::Win32::Graphics::Dxgi::Common::*;
+use windows::Win32::UI::WindowsAndMessaging::{CreateWindowExW, DestroyWindow, WINDOW_EX_STYLE, WS_OVERLAPPEDWINDOW};
+
+/// A hidden window to present to
+pub struct TestWindow(HWND);
+
+impl TestWindow {
+    pub fn new(width: u32, height: u32) -> Self {
+        // SAFETY: the system STATIC class needs no registration or window procedure
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("STATIC"),
+                w!("epicx test"),
+                WS_OVERLAPPEDWINDOW,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        Self(hwnd.expect("failed to create a test window"))
+    }
+
+    /// Graphics presenting to this window on the WARP adapter
+    ///
+    /// Width and height are taken from `config`. None (skip the test) when
+    /// WARP is unavailable.
+    pub fn graphics(&self, config: GraphicsConfig) -> Option<Graphics> {
+        let config = GraphicsConfig { warp: true, vsync: false, ..config };
+        match Graphics::new(self.0.into(), config) {
+            Ok(graphics) => Some(graphics),
+            Err(e) => {
+                log::warn!("Skipping: no WARP graphics ({})", e);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for TestWindow {
+    fn drop(&mut self) {
+        // SAFETY: the window was created by this thread and is destroyed once
+        unsafe {
+            let _ = DestroyWindow(self.0);
+        }
+    }
+}
+
+/// Passes clip-space positions and colors straight through
+const COLOR_SHADER: &str = r#"
+struct VSInput { float4 position : POSITION; float4 color : COLOR; };
+struct PSInput { float4 position : SV_POSITION; float4 color : COLOR; };
+
+PSInput vs_main(VSInput input) {
+    PSInput output;
+    output.position = input.position;
+    output.color = input.color;
+    return output;
+}
+
+float4 ps_main(PSInput input) : SV_TARGET {
+    return input.color;
+}
+"#;
+
+/// Vertex for `COLOR_SHADER`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ColorVertex {
+    /// Clip-space position
+    pub position: [f32; 4],
+    pub color: [f32; 4],
+}
+
+/// Two clockwise triangles covering the clip-space rectangle `min..max` at `depth`
+pub fn quad(min: [f32; 2], max: [f32; 2], depth: f32, color: [f32; 4]) -> [ColorVertex; 6] {
+    let vertex = |x: f32, y: f32| ColorVertex { position: [x, y, depth, 1.0], color };
+    let (bottom_left, top_left) = (vertex(min[0], min[1]), vertex(min[0], max[1]));
+    let (top_right, bottom_right) = (vertex(max[0], max[1]), vertex(max[0], min[1]));
+    [bottom_left, top_left, top_right, bottom_left, top_right, bottom_right]
+}
+
+/// Pipeline drawing `ColorVertex` triangles into targets of `options.render_target_format`
+pub fn color_pipeline(device: &Device, root_signature: &RootSignature, options: PipelineOptions) -> PipelineState {
+    let compiler = ShaderCompiler::new();
+    let vertex_shader = compiler.compile(COLOR_SHADER, "vs_main", ShaderType::Vertex).unwrap();
+    let pixel_shader = compiler.compile(COLOR_SHADER, "ps_main", ShaderType::Pixel).unwrap();
+    let element = |name: &'static [u8], offset| D3D12_INPUT_ELEMENT_DESC {
+        SemanticName: PCSTR(name.as_ptr()),
+        SemanticIndex: 0,
+        Format: DXGI_FORMAT_R32G32B32A32_FLOAT,
+        InputSlot: 0,
+        AlignedByteOffset: offset,
+        InputSlotClass: D3D12_INPUT_CLASSIFICATION_PER_VERTEX_DATA,
+        InstanceDataStepRate: 0,
+    };
+    let layout = [element(b"POSITION\0", 0), element(b"COLOR\0", 16)];
+    Pipeline::create_graphics_pipeline_with(
+        device,
+        root_signature,
+        vertex_shader.bytecode(),
+        pixel_shader.bytecode(),
+        &layout,
+        options,
+    )
+    .unwrap()
+}
+
+/// Readback buffer for a `width` x `height` RGBA8 target
+pub struct Readback {
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+}
+
+impl Readback {
+    pub fn new(device: &Device, width: u32, height: u32) -> Self {
+        let size = (row_pitch(width) * height) as u64;
+        let buffer = Buffer::new(device, BufferDesc { size, usage: BufferUsage::Readback, stride: 0 }).unwrap();
+        Self { buffer, width, height }
+    }
+
+    /// Record a copy of `target`, which is a render target before and after
+    pub fn copy(&self, list: &CommandList, target: &ID3D12Resource) {
+        list.resource_barrier(&[transition(target, D3D12_RESOURCE_STATE_RENDER_TARGET, D3D12_RESOURCE_STATE_COPY_SOURCE)]);
+        // SAFETY: the target is a live texture
+        let format = unsafe { target.GetDesc() }.Format;
+        let source = D3D12_TEXTURE_COPY_LOCATION {
+            pResource: unsafe { std::mem::transmute_copy(target) },
+            Type: D3D12_TEXTURE_COPY_TYPE_SUBRESOURCE_INDEX,
+            Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 { SubresourceIndex: 0 },
+        };
+        let destination = D3D12_TEXTURE_COPY_LOCATION {
+            pResource: unsafe { std::mem::transmute_copy(self.buffer.raw()) },
+            Type: D3D12_TEXTURE_COPY_TYPE_PLACED_FOOTPRINT,
+            Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+                PlacedFootprint: D3D12_PLACED_SUBRESOURCE_FOOTPRINT {
+                    Offset: 0,
+                    Footprint: D3D12_SUBRESOURCE_FOOTPRINT {
+                        Format: format,
+                        Width: self.width,
+                        Height: self.height,
+                        Depth: 1,
+                        RowPitch: row_pitch(self.width),
+                    },
+                },
+            },
+        };
+        unsafe { list.raw().CopyTextureRegion(&destination, 0, 0, 0, &source, None) };
+        list.resource_barrier(&[transition(target, D3D12_RESOURCE_STATE_COPY_SOURCE, D3D12_RESOURCE_STATE_RENDER_TARGET)]);
+    }
+
+    /// Pixels once the copy has executed, row by row
+    pub fn pixels(&self) -> Vec<[u8; 4]> {
+        let mapped = self.buffer.map().unwrap();
+        let mut pixels = Vec::with_capacity((self.width * self.height) as usize);
+        for y in 0..self.height {
+            // SAFETY: the readback buffer holds `height` rows of `row_pitch` bytes
+            let row = unsafe {
+                std::slice::from_raw_parts(mapped.add((y * row_pitch(self.width)) as usize), (self.width * 4) as usize)
+            };
+            pixels.extend(row.chunks_exact(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]));
+        }
+        self.buffer.unmap();
+        pixels
+    }
+}
+
+/// RGBA8 render target with an optional depth buffer and a readback copy
+pub struct Offscreen {
+    pub device: Device,
+    queue: CommandQueue,
+    allocator: CommandAllocator,
+    pub list: CommandList,
+    _rtv_heap: DescriptorHeap,
+    _dsv_heap: DescriptorHeap,
+    pub target: RenderTarget,
+    pub depth: Option<DepthStencil>,
+    readback: Readback,
+    root_signature: RootSignature,
+    /// Vertex buffers drawn this pass (kept alive until it executes)
+    vertex_buffers: Vec<VertexBuffer>,
+    width: u32,
+    height: u32,
+}
+
+impl Offscreen {
+    /// Create a `width` x `height` target (`DepthMode::Disabled` = no depth buffer)
+    pub fn new(device: Device, width: u32, height: u32, format: DXGI_FORMAT, depth: DepthMode) -> Self {
+        let queue = CommandQueue::graphics(&device).unwrap();
+        let allocator = CommandAllocator::graphics(&device).unwrap();
+        let list = CommandList::new(&device, &allocator, None).unwrap();
+        list.close().unwrap();
+
+        let rtv_heap = DescriptorHeap::rtv(&device, 1).unwrap();
+        let dsv_heap = DescriptorHeap::dsv(&device, 1).unwrap();
+        let target = RenderTarget::new(&device, width, height, format, rtv_heap.raw(), 0).unwrap();
+        let depth_stencil = (depth != DepthMode::Disabled)
+            .then(|| DepthStencil::new(&device, width, height, depth, dsv_heap.raw(), 0).unwrap());
+        let readback = Readback::new(&device, width, height);
+        let root_signature = RootSignature::new_simple(&device).unwrap();
+
+        Self {
+            device,
+            queue,
+            allocator,
+            list,
+            _rtv_heap: rtv_heap,
+            _dsv_heap: dsv_heap,
+            target,
+            depth: depth_stencil,
+            readback,
+            root_signature,
+            vertex_buffers: Vec::new(),
+            width,
+            height,
+        }
+    }
+
+    /// Pipeline for `ColorVertex` drawing into this target
+    pub fn color_pipeline(&self, options: PipelineOptions) -> PipelineState {
+        let options = PipelineOptions {
+            depth: self.depth.as_ref().map_or(DepthMode::Disabled, |depth| depth.depth_mode()),
+            render_target_format: Some(self.target.texture().desc().format),
+            ..options
+        };
+        color_pipeline(&self.device, &self.root_signature, options)
+    }
+
+    /// Start recording: bind the target and clear it (and the depth buffer)
+    pub fn begin(&mut self, clear_color: [f32; 4]) {
+        self.allocator.reset().unwrap();
+        self.list.reset(&self.allocator, None).unwrap();
+        self.list.set_viewport(0.0, 0.0, self.width as f32, self.height as f32);
+        self.list.set_scissor_rect(0, 0, self.width as i32, self.height as i32);
+        let dsv = self.depth.as_ref().map(|depth| depth.dsv());
+        self.list.set_render_targets(&[self.target.rtv()], dsv.as_ref().map(|dsv| dsv as *const _));
+        self.list.clear_render_target(self.target.rtv(), clear_color);
+        if let Some(depth) = &self.depth {
+            self.list.clear_depth_stencil(depth.dsv(), depth.clear_value(), 0);
+        }
+    }
+
+    /// Draw a triangle list with `pipeline`
+    pub fn draw(&mut self, pipeline: &PipelineState, vertices: &[ColorVertex]) {
+        let stride = std::mem::size_of::<ColorVertex>() as u32;
+        let buffer = VertexBuffer::new(&self.device, (vertices.len() * stride as usize) as u64, stride).unwrap();
+        buffer.write(vertices).unwrap();
+        self.list.set_root_signature(&self.root_signature);
+        self.list.set_pipeline_state(pipeline);
+        self.list.set_primitive_topology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+        self.list.set_vertex_buffers(0, &[*buffer.view()]);
+        self.list.draw_instanced(vertices.len() as u32, 1, 0, 0);
+        self.vertex_buffers.push(buffer);
+    }
+
+    /// Execute the recorded pass and read the target back, row by row
+    pub fn finish(&mut self) -> Vec<[u8; 4]> {
+        self.readback.copy(&self.list, self.target.texture().raw());
+        self.list.close().unwrap();
+        self.queue.execute(&[&self.list]);
+        self.queue.flush().unwrap();
+        self.vertex_buffers.clear();
+        self.readback.pixels()
+    }
+}
+
+/// Bytes per row of a copied RGBA8 texture (rows are 256-byte aligned)
+fn row_pitch(width: u32) -> u32 {
+    (width * 4).div_ceil(D3D12_TEXTURE_DATA_PITCH_ALIGNMENT) * D3D12_TEXTURE_DATA_PITCH_ALIGNMENT
+}
+
+fn transition(
+    resource: &ID3D12Resource,
+    before: D3D12_RESOURCE_STATES,
+    after: D3D12_RESOURCE_STATES,
+) -> D3D12_RESOURCE_BARRIER {
+    D3D12_RESOURCE_BARRIER {
+        Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
+        Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+        Anonymous: D3D12_RESOURCE_BARRIER_0 {
+            Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
+                pResource: unsafe { std::mem::transmute_copy(resource) },
+                StateBefore: before,
+                StateAfter: after,
+                Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+            }),
+        },
+    }
+}
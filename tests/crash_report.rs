@@ -0,0 +1,75 @@
+//! Crash bundles written by the panic hook
+//!
+//! `crash::install` and `logging::init` are once per process, so everything
+//! runs in one test.
+
+use epicx::core::{Context, ErrorBoundary, RenderContext};
+use epicx::crash::{self, CrashOptions};
+use epicx::logging;
+use epicx::math::Rect;
+use std::fs;
+use std::path::PathBuf;
+
+/// Text of the bundle whose summary contains `message`
+///
+/// Entries are stored uncompressed, so their contents can be searched directly.
+fn find_bundle(directory: &PathBuf, message: &str) -> String {
+    let bundles: Vec<String> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| String::from_utf8_lossy(&fs::read(entry.unwrap().path()).unwrap()).into_owned())
+        .filter(|text| text.contains(&format!("message: {}", message)))
+        .collect();
+    assert_eq!(bundles.len(), 1, "expected one bundle for '{}'", message);
+    bundles.into_iter().next().unwrap()
+}
+
+#[test]
+fn child_thread_panics_write_bundles() {
+    let directory = std::env::temp_dir().join(format!("epicx-crash-report-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    logging::init(64, log::LevelFilter::Info).unwrap();
+    crash::install(CrashOptions {
+        directory: directory.clone(),
+        app_name: "crash test".to_string(),
+        log_lines: 10,
+        native_faults: false,
+    });
+    assert!(crash::is_installed());
+    crash::set_context("scene", "level 3");
+    log::info!("loading level 3");
+
+    let result = std::thread::Builder::new()
+        .name("loader".to_string())
+        .spawn(|| panic!("loader thread failed"))
+        .unwrap()
+        .join();
+    assert!(result.is_err());
+
+    let bundle = find_bundle(&directory, "loader thread failed");
+    assert!(bundle.contains("crash test"));
+    assert!(bundle.contains("kind: panic"));
+    assert!(bundle.contains("thread: loader"));
+    assert!(bundle.contains("location: tests"));
+    assert!(bundle.contains("summary.txt"));
+    assert!(bundle.contains("scene.txt") && bundle.contains("level 3"));
+    assert!(bundle.contains("log.txt") && bundle.contains("loading level 3"));
+
+    // A panic while holding the log buffer still produces a bundle
+    let result = std::thread::spawn(|| logging::with_buffer(|_| panic!("panicked holding the log"))).join();
+    assert!(result.is_err());
+    let bundle = find_bundle(&directory, "panicked holding the log");
+    assert!(bundle.contains("log left out"));
+    assert!(bundle.contains("scene.txt"));
+
+    // Panics an error boundary contains are reported as non-fatal
+    let boundary = ErrorBoundary::from_fn(|_| panic!("boundary child failed"));
+    let context = Context::new();
+    boundary.render(&mut RenderContext::new(&context, Rect::new(0.0, 0.0, 100.0, 100.0)));
+    assert!(boundary.has_error());
+    let bundle = find_bundle(&directory, "boundary child failed");
+    assert!(bundle.contains("kind: non-fatal error"));
+    assert!(!bundle.contains("kind: panic"));
+    assert!(bundle.contains("panic at tests"));
+
+    fs::remove_dir_all(&directory).unwrap();
+}
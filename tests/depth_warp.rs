@@ -0,0 +1,48 @@
+//! Depth testing on the WARP software adapter
+
+mod common;
+
+use common::{quad, ColorVertex, Offscreen};
+use epicx::dx12::{test_device, PipelineOptions};
+use epicx::graphics::Camera3D;
+use epicx::math::{Vec3, Vec4};
+use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_R8G8B8A8_UNORM;
+
+const NEAR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const FAR: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+
+/// Screen-filling quad `distance` units in front of `camera`
+fn wall(camera: &Camera3D, distance: f32, color: [f32; 4]) -> [ColorVertex; 6] {
+    let view_projection = camera.projection_matrix() * camera.view_matrix();
+    let mut vertices = quad([-1000.0, -1000.0], [1000.0, 1000.0], -distance, color);
+    for vertex in &mut vertices {
+        vertex.position = (view_projection * Vec4::from(vertex.position)).to_array();
+    }
+    vertices
+}
+
+#[test]
+fn reversed_z_resolves_nearly_coplanar_quads() {
+    let Some(device) = test_device() else { return };
+    let mut camera = Camera3D::new(Vec3::ZERO, -Vec3::Z, 1.0).with_reverse_z(true);
+    camera.near = 0.01;
+    camera.far = 10_000.0;
+
+    // Half a unit apart at 500 units: well below one step of standard Z at
+    // this near plane, thousands of steps apart with reversed Z
+    let near = wall(&camera, 500.0, NEAR);
+    let far = wall(&camera, 500.5, FAR);
+
+    let mut offscreen = Offscreen::new(device, 64, 64, DXGI_FORMAT_R8G8B8A8_UNORM, camera.depth_mode());
+    assert_eq!(offscreen.depth.as_ref().unwrap().clear_value(), 0.0);
+    let pipeline = offscreen.color_pipeline(PipelineOptions::default());
+
+    for (first, second) in [(&near, &far), (&far, &near)] {
+        offscreen.begin([0.0, 0.0, 1.0, 1.0]);
+        offscreen.draw(&pipeline, first);
+        offscreen.draw(&pipeline, second);
+        let pixels = offscreen.finish();
+        let wrong = pixels.iter().filter(|&&pixel| pixel != [255, 0, 0, 255]).count();
+        assert_eq!(wrong, 0, "{} of {} pixels are not the nearer quad", wrong, pixels.len());
+    }
+}
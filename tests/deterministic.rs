@@ -0,0 +1,83 @@
+//! `EasyApp::run_deterministic` against committed hash sequences
+//!
+//! The expected hashes were recorded from this scene. If a rendering change
+//! is intended, re-record them; any other mismatch is a regression in the
+//! software backend, the draw order or the replay of time, seed and input.
+//! The scene avoids trigonometry so the hashes don't depend on the platform's
+//! math library.
+
+use epicx::easy::{DrawContext, EasyApp, FrameInfo};
+use epicx::events::{Event, MouseButton, MouseEvent};
+use epicx::math::{Color, Vec2};
+use epicx::testing::RecordedInput;
+
+const FRAMES: u32 = 8;
+
+const EXPECTED: [u64; FRAMES as usize] = [
+    0xD6E0_3B31_686C_99B3, 0xE64B_7765_9C3E_D786, 0x944B_E1D3_48D9_1519, 0xFDAC_ADF4_E048_A74E,
+    0xA6F7_28EB_4068_CE86, 0xD860_6990_C794_97BE, 0x1BC9_FF17_A46C_FB9E, 0x2B6B_5E2D_CA88_112C,
+];
+
+const EXPECTED_WITH_INPUT: [u64; FRAMES as usize] = [
+    0xD6E0_3B31_686C_99B3, 0xE64B_7765_9C3E_D786, 0xFD15_CA09_4CA4_F9C9, 0x701B_F453_F9AC_CC2D,
+    0x8EC2_27A0_4021_C138, 0x98A6_71E1_CF86_1CE8, 0x3262_AF71_58C6_30A4, 0x31BD_D5FB_972C_6158,
+];
+
+/// Moving rectangles, seeded sparks and a cursor that changes color while pressed
+///
+/// Only filled rectangles and clears reach the software backend so far.
+fn scene(ctx: &mut DrawContext, info: &mut FrameInfo) {
+    ctx.clear(Color::new(0.1, 0.1, 0.15, 1.0));
+    let x = 4.0 + info.time * 120.0;
+    ctx.fill_rect(x, 8.0, 16.0, 12.0, Color::RED);
+    ctx.fill_rect(40.0 - x * 0.5, 24.0, 20.0, 16.0, Color::new(0.0, 1.0, 0.0, 0.5));
+    for _ in 0..3 {
+        let (sx, sy) = (info.rng.range_f32(0.0, 64.0), info.rng.range_f32(0.0, 48.0));
+        let size = info.rng.range_f32(1.0, 4.0);
+        ctx.fill_rect(sx, sy, size, size, Color::new(1.0, 0.8, 0.2, 0.5));
+    }
+
+    let cursor = info.input.mouse_position();
+    let color = if info.input.is_mouse_down(MouseButton::Left) { Color::BLUE } else { Color::WHITE };
+    ctx.fill_rect(cursor.x - 3.0, cursor.y - 3.0, 6.0, 6.0, color);
+}
+
+fn run(seed: u64, input: Option<RecordedInput>) -> Vec<u64> {
+    EasyApp::new("deterministic", 64, 48).run_deterministic(FRAMES, seed, input, scene)
+}
+
+/// The cursor moves in on frame 2, is pressed on frame 4 and released on frame 6
+fn recorded_input() -> RecordedInput {
+    let mouse = |x: f32, y: f32, button: Option<MouseButton>| MouseEvent {
+        position: Vec2::new(x, y),
+        button,
+        ..Default::default()
+    };
+    let mut input = RecordedInput::new();
+    input.record(2, Event::MouseMove(mouse(20.0, 30.0, None)));
+    input.record(4, Event::MouseDown(mouse(20.0, 30.0, Some(MouseButton::Left))));
+    input.record(4, Event::MouseMove(mouse(30.0, 20.0, None)));
+    input.record(6, Event::MouseUp(mouse(30.0, 20.0, Some(MouseButton::Left))));
+    input
+}
+
+#[test]
+fn hashes_match_the_committed_sequence() {
+    assert_eq!(run(7, None), EXPECTED);
+    assert_eq!(run(7, Some(recorded_input())), EXPECTED_WITH_INPUT);
+}
+
+#[test]
+fn runs_depend_only_on_seed_and_input() {
+    let hashes = run(7, Some(recorded_input()));
+    assert_eq!(run(7, Some(recorded_input())), hashes);
+
+    // Another seed changes every frame's sparks
+    let reseeded = run(8, Some(recorded_input()));
+    assert!(hashes.iter().zip(&reseeded).all(|(a, b)| a != b));
+
+    // Input only changes the frames from the first recorded event on
+    let without_input = run(7, None);
+    assert_eq!(hashes[..2], without_input[..2]);
+    assert!(hashes[2..].iter().zip(&without_input[2..]).all(|(a, b)| a != b));
+}
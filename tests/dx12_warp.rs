@@ -0,0 +1,296 @@
+//! Level A (dx12) on the WARP software adapter
+//!
+//! Every test returns early when no WARP device can be created, so the
+//! suite passes on machines without D3D12 instead of failing.
+
+use epicx::dx12::{
+    builtin, test_device, BarrierRecorder, Buffer, BufferDesc, BufferUsage, CommandAllocator, CommandList,
+    CommandQueue, ConstantBuffer, DescriptorHeap, Device, Dx12Error, Fence, Pipeline, ResourceStateTracker,
+    RootSignature, ShaderCompiler, ShaderType, StateChanges,
+};
+use std::cell::RefCell;
+use windows::core::{Interface, PCSTR};
+use windows::Win32::Graphics::Direct3D12::*;
+use windows::Win32::Graphics::Dxgi::Common::*;
+
+#[test]
+fn device_without_debug_layer() {
+    let Ok(device) = Device::new_warp(false) else { return };
+    assert!(!device.is_debug_enabled());
+    assert!(device.removed_reason().is_none());
+}
+
+#[test]
+fn device_with_debug_layer_falls_back_when_not_installed() {
+    // Succeeds whether or not the SDK layers are installed
+    let Ok(device) = Device::new_warp(true) else { return };
+    assert!(device.removed_reason().is_none());
+}
+
+#[test]
+fn command_queue_signal_and_flush() {
+    let Some(device) = test_device() else { return };
+    let mut queue = CommandQueue::graphics(&device).unwrap();
+
+    let first = queue.signal().unwrap();
+    let second = queue.signal().unwrap();
+    assert_eq!(second, first + 1);
+    queue.wait_for_fence(second).unwrap();
+
+    // An executed (empty) list completes by the time flush returns
+    let allocator = CommandAllocator::graphics(&device).unwrap();
+    let list = CommandList::new(&device, &allocator, None).unwrap();
+    list.close().unwrap();
+    queue.execute(&[&list]);
+    queue.flush().unwrap();
+    allocator.reset().unwrap();
+}
+
+#[test]
+fn fence_wait_and_timeout() {
+    let Some(device) = test_device() else { return };
+    let queue = CommandQueue::graphics(&device).unwrap();
+    let fence = Fence::new(&device, 0).unwrap();
+    assert_eq!(fence.completed_value(), 0);
+
+    fence.signal(queue.raw(), 5).unwrap();
+    fence.wait(5).unwrap();
+    assert!(fence.completed_value() >= 5);
+    assert!(fence.wait_timeout(5, 0).unwrap());
+    // Nothing signals 6
+    assert!(!fence.wait_timeout(6, 10).unwrap());
+}
+
+#[test]
+fn upload_and_readback_buffers_map() {
+    let Some(device) = test_device() else { return };
+
+    let upload = Buffer::new(&device, BufferDesc { size: 64, usage: BufferUsage::Upload, stride: 4 }).unwrap();
+    assert_eq!(upload.size(), 64);
+    assert_ne!(upload.gpu_address(), 0);
+    let data: Vec<u32> = (0..16).collect();
+    upload.write(&data).unwrap();
+    let mapped = upload.map().unwrap();
+    let read = unsafe { std::slice::from_raw_parts(mapped as *const u32, 16) }.to_vec();
+    upload.unmap();
+    assert_eq!(read, data);
+
+    let readback = Buffer::new(&device, BufferDesc { size: 256, usage: BufferUsage::Readback, stride: 0 }).unwrap();
+    assert!(!readback.map().unwrap().is_null());
+    readback.unmap();
+}
+
+#[test]
+fn constant_buffer_is_256_byte_aligned() {
+    let Some(device) = test_device() else { return };
+
+    let buffer = ConstantBuffer::new(&device, 100).unwrap();
+    assert_eq!(buffer.aligned_size(), 256);
+    assert_eq!(buffer.gpu_address() % 256, 0);
+    buffer.write(&[1.0f32; 16]).unwrap();
+
+    assert_eq!(ConstantBuffer::new(&device, 256).unwrap().aligned_size(), 256);
+    assert_eq!(ConstantBuffer::new(&device, 257).unwrap().aligned_size(), 512);
+}
+
+#[test]
+fn descriptor_heap_handle_arithmetic() {
+    let Some(device) = test_device() else { return };
+
+    let mut rtv = DescriptorHeap::rtv(&device, 4).unwrap();
+    let size = rtv.descriptor_size() as usize;
+    assert_eq!(size as u32, device.get_descriptor_increment_size(D3D12_DESCRIPTOR_HEAP_TYPE_RTV));
+    let start = rtv.get_handle(0).cpu.ptr;
+    for index in 0..4 {
+        let handle = rtv.allocate().unwrap();
+        assert_eq!(handle.cpu.ptr, start + index * size);
+        assert!(handle.gpu.is_none());
+    }
+    assert!(rtv.allocate().is_none());
+    assert_eq!(rtv.allocated_count(), 4);
+    rtv.reset();
+    assert_eq!(rtv.allocate().unwrap().cpu.ptr, start);
+
+    // Shader-visible heaps step GPU handles by the same size
+    let srv = DescriptorHeap::cbv_srv_uav(&device, 8).unwrap();
+    let size = srv.descriptor_size() as u64;
+    let (first, last) = (srv.get_handle(0), srv.get_handle(7));
+    assert_eq!(last.gpu.unwrap().ptr - first.gpu.unwrap().ptr, 7 * size);
+    assert_eq!((last.cpu.ptr - first.cpu.ptr) as u64, 7 * size);
+}
+
+#[test]
+fn shader_compile_errors_name_the_problem() {
+    let source = "float4 main() : SV_TARGET { return undefined_color; }";
+    match ShaderCompiler::new().compile(source, "main", ShaderType::Pixel) {
+        Err(Dx12Error::ShaderCompilation(message)) => {
+            assert!(message.contains("undefined_color"), "{}", message);
+            assert!(message.contains("shader.hlsl(1,"), "{}", message);
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("invalid shader compiled"),
+    }
+
+    match ShaderCompiler::new().compile(builtin::PIXEL_SIMPLE, "missing_entry", ShaderType::Pixel) {
+        Err(Dx12Error::ShaderCompilation(message)) => assert!(message.contains("missing_entry"), "{}", message),
+        _ => panic!("missing entry point compiled"),
+    }
+}
+
+#[test]
+fn pipeline_without_swap_chain() {
+    let Some(device) = test_device() else { return };
+    let compiler = ShaderCompiler::new();
+    let vertex_shader = compiler.compile(builtin::VERTEX_2D, "main", ShaderType::Vertex).unwrap();
+    let pixel_shader = compiler.compile(builtin::PIXEL_SIMPLE, "main", ShaderType::Pixel).unwrap();
+    assert!(!vertex_shader.bytecode().is_empty());
+
+    let element = |name: &'static [u8], format, offset| D3D12_INPUT_ELEMENT_DESC {
+        SemanticName: PCSTR(name.as_ptr()),
+        SemanticIndex: 0,
+        Format: format,
+        InputSlot: 0,
+        AlignedByteOffset: offset,
+        InputSlotClass: D3D12_INPUT_CLASSIFICATION_PER_VERTEX_DATA,
+        InstanceDataStepRate: 0,
+    };
+    let layout = [
+        element(b"POSITION\0", DXGI_FORMAT_R32G32_FLOAT, 0),
+        element(b"COLOR\0", DXGI_FORMAT_R32G32B32A32_FLOAT, 8),
+    ];
+
+    let root_signature = RootSignature::new_simple(&device).unwrap();
+    Pipeline::create_graphics_pipeline(
+        &device,
+        &root_signature,
+        vertex_shader.bytecode(),
+        pixel_shader.bytecode(),
+        &layout,
+    )
+    .unwrap();
+
+    // A layout that doesn't match the shader is rejected, not a crash
+    let result = Pipeline::create_graphics_pipeline(
+        &device,
+        &root_signature,
+        vertex_shader.bytecode(),
+        pixel_shader.bytecode(),
+        &layout[..1],
+    );
+    assert!(result.is_err());
+}
+
+type RecordedBarrier = (usize, D3D12_RESOURCE_STATES, D3D12_RESOURCE_STATES);
+
+/// Keeps barrier batches instead of recording them into a command list
+#[derive(Default)]
+struct FakeRecorder {
+    batches: RefCell<Vec<Vec<RecordedBarrier>>>,
+}
+
+impl BarrierRecorder for FakeRecorder {
+    fn record_barriers(&self, barriers: &[D3D12_RESOURCE_BARRIER]) {
+        let batch = barriers
+            .iter()
+            .map(|barrier| {
+                assert_eq!(barrier.Type, D3D12_RESOURCE_BARRIER_TYPE_TRANSITION);
+                // SAFETY: transition barriers use the Transition member
+                let transition = unsafe { &barrier.Anonymous.Transition };
+                let resource = transition.pResource.as_ref().unwrap().as_raw() as usize;
+                (resource, transition.StateBefore, transition.StateAfter)
+            })
+            .collect();
+        self.batches.borrow_mut().push(batch);
+    }
+}
+
+impl FakeRecorder {
+    fn take(&self) -> Vec<Vec<RecordedBarrier>> {
+        std::mem::take(&mut self.batches.borrow_mut())
+    }
+}
+
+fn buffer(device: &Device) -> Buffer {
+    Buffer::new(device, BufferDesc { size: 256, usage: BufferUsage::Vertex, stride: 16 }).unwrap()
+}
+
+fn id(buffer: &Buffer) -> usize {
+    buffer.raw().as_raw() as usize
+}
+
+#[test]
+fn state_tracker_batches_and_elides_barriers() {
+    let Some(device) = test_device() else { return };
+    let (a, b, untracked) = (buffer(&device), buffer(&device), buffer(&device));
+    let recorder = FakeRecorder::default();
+    let mut states = ResourceStateTracker::new();
+    states.track(a.raw(), D3D12_RESOURCE_STATE_COMMON);
+    states.track(b.raw(), D3D12_RESOURCE_STATE_COMMON);
+
+    assert!(states.transition(a.raw(), D3D12_RESOURCE_STATE_COPY_DEST));
+    assert!(states.transition(b.raw(), D3D12_RESOURCE_STATE_COPY_DEST));
+    // Already there, and not tracked: nothing to do
+    assert!(!states.transition(a.raw(), D3D12_RESOURCE_STATE_COPY_DEST));
+    assert!(!states.transition(untracked.raw(), D3D12_RESOURCE_STATE_COPY_DEST));
+    assert_eq!(states.pending_barriers(), 2);
+
+    states.flush(&recorder);
+    let common_to_copy = |buffer: &Buffer| (id(buffer), D3D12_RESOURCE_STATE_COMMON, D3D12_RESOURCE_STATE_COPY_DEST);
+    assert_eq!(recorder.take(), vec![vec![common_to_copy(&a), common_to_copy(&b)]]);
+
+    // An empty flush records nothing
+    states.flush(&recorder);
+    assert!(recorder.take().is_empty());
+
+    // A round trip within one batch keeps both barriers, in order
+    states.transition(a.raw(), D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE);
+    states.transition(a.raw(), D3D12_RESOURCE_STATE_COPY_DEST);
+    states.flush(&recorder);
+    let batches = recorder.take();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0][1], (id(&a), D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE, D3D12_RESOURCE_STATE_COPY_DEST));
+    assert!(states.validate().is_empty());
+}
+
+#[test]
+fn state_changes_record_against_committed_states() {
+    let Some(device) = test_device() else { return };
+    let (a, b, c) = (buffer(&device), buffer(&device), buffer(&device));
+    let recorder = FakeRecorder::default();
+    let mut committed = ResourceStateTracker::new();
+    committed.track(a.raw(), D3D12_RESOURCE_STATE_COMMON);
+    committed.track(b.raw(), D3D12_RESOURCE_STATE_COMMON);
+
+    // An aborted list leaves the committed states alone
+    let mut changes = StateChanges::new();
+    assert!(changes.transition(&committed, a.raw(), D3D12_RESOURCE_STATE_RENDER_TARGET));
+    assert_eq!(changes.state(&committed, a.raw()), Some(D3D12_RESOURCE_STATE_RENDER_TARGET));
+    assert_eq!(committed.state(a.raw()), Some(D3D12_RESOURCE_STATE_COMMON));
+    drop(changes);
+
+    let mut changes = StateChanges::new();
+    assert!(!changes.transition(&committed, b.raw(), D3D12_RESOURCE_STATE_COMMON));
+    changes.transition(&committed, a.raw(), D3D12_RESOURCE_STATE_RENDER_TARGET);
+    changes.track(c.raw(), D3D12_RESOURCE_STATE_COPY_DEST);
+    changes.transition(&committed, c.raw(), D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE);
+    changes.expect_final(&committed, a.raw(), D3D12_RESOURCE_STATE_PRESENT);
+    // Only what the list changed is copied, not b
+    assert_eq!(changes.touched_count(), 2);
+
+    changes.flush(&recorder);
+    assert_eq!(
+        recorder.take(),
+        vec![vec![
+            (id(&a), D3D12_RESOURCE_STATE_COMMON, D3D12_RESOURCE_STATE_RENDER_TARGET),
+            (id(&c), D3D12_RESOURCE_STATE_COPY_DEST, D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE),
+        ]]
+    );
+    assert_eq!(changes.validate().len(), 1);
+
+    committed.apply(changes);
+    assert_eq!(committed.state(a.raw()), Some(D3D12_RESOURCE_STATE_RENDER_TARGET));
+    assert_eq!(committed.state(b.raw()), Some(D3D12_RESOURCE_STATE_COMMON));
+    assert_eq!(committed.state(c.raw()), Some(D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE));
+    // Final-state expectations end with their list
+    assert!(committed.validate().is_empty());
+}
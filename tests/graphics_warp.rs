@@ -0,0 +1,389 @@
+//! Level B (graphics) presenting to a hidden window on the WARP adapter
+//!
+//! Every test returns early when no WARP device can be created.
+
+mod common;
+
+use common::{color_pipeline, ColorVertex, Readback, TestWindow};
+use epicx::backend::DrawBatch;
+use epicx::dx12::{
+    Dx12Error, PipelineOptions, PipelineState, ResourceState, SwapChainConfig, Texture, TextureDesc, VertexBuffer,
+};
+use epicx::events::GraphicsEvent;
+use epicx::graphics::{splitscreen_layout, Camera3D, Graphics, GraphicsConfig, RenderFrame};
+use epicx::math::{linear_to_srgb, Color, Rect, Vec3};
+use windows::Win32::Graphics::Direct3D::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST;
+use windows::Win32::Graphics::Dxgi::Common::{DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_R8G8B8A8_UNORM};
+
+fn config(width: u32, height: u32) -> GraphicsConfig {
+    GraphicsConfig { width, height, ..Default::default() }
+}
+
+#[test]
+fn suspend_resume_cycles_leave_nothing_behind() {
+    let window = TestWindow::new(320, 240);
+    let Some(mut graphics) = window.graphics(config(320, 240)) else { return };
+    let texture = Texture::new(graphics.device(), TextureDesc { width: 16, height: 16, ..Default::default() }).unwrap();
+    graphics.track_resource(&(&texture).into(), ResourceState::Common);
+    let tracked = graphics.state_tracker().tracked_count();
+
+    for cycle in 0..50 {
+        graphics.suspend().unwrap();
+        assert!(graphics.begin_frame().is_err());
+        // A resize while suspended is applied by resume
+        let width = 320 + (cycle % 4) * 16;
+        graphics.resize(width, 240).unwrap();
+        graphics.resume().unwrap();
+
+        // Recreating the buffers fails if anything still references the old ones
+        let swap_chain = graphics.backend().swap_chain();
+        assert_eq!(swap_chain.back_buffers().len(), 2);
+        assert_eq!((swap_chain.width(), graphics.width()), (width, width));
+        assert_eq!(graphics.state_tracker().tracked_count(), tracked);
+
+        let frame = graphics.begin_frame().unwrap();
+        frame.clear(Color::BLACK);
+        graphics.end_frame(frame).unwrap();
+    }
+}
+
+#[test]
+fn failed_resize_keeps_the_previous_size() {
+    let window = TestWindow::new(320, 240);
+    let Some(mut graphics) = window.graphics(config(320, 240)) else { return };
+
+    // ResizeBuffers refuses while a back buffer is still referenced
+    let held = graphics.backend().swap_chain().back_buffers()[0].clone();
+    assert!(graphics.resize(640, 480).is_err());
+    assert_eq!((graphics.width(), graphics.height()), (320, 240));
+    // The previous buffers were taken back, so frames still render
+    assert!(!graphics.is_suspended());
+    render_frame(&mut graphics);
+    drop(held);
+
+    graphics.resize(640, 480).unwrap();
+    assert_eq!((graphics.width(), graphics.height()), (640, 480));
+    let frame = graphics.begin_frame().unwrap();
+    frame.clear(Color::BLACK);
+    graphics.end_frame(frame).unwrap();
+}
+
+#[test]
+fn reconfigure_loop_renders_every_setting() {
+    let window = TestWindow::new(320, 240);
+    let Some(mut graphics) = window.graphics(config(320, 240)) else { return };
+    let texture = Texture::new(graphics.device(), TextureDesc { width: 16, height: 16, ..Default::default() }).unwrap();
+    graphics.track_resource(&(&texture).into(), ResourceState::Common);
+    let tracked = graphics.state_tracker().tracked_count();
+    let base = graphics.swap_chain_config().clone();
+
+    for i in 0..100u32 {
+        let config = SwapChainConfig {
+            buffer_count: 2 + i % 3,
+            format: if i % 4 < 2 { DXGI_FORMAT_R8G8B8A8_UNORM } else { DXGI_FORMAT_B8G8R8A8_UNORM },
+            vsync: i % 10 == 0,
+            srgb_views: i % 2 == 1,
+            ..base.clone()
+        };
+        let event = graphics.reconfigure(config.clone()).unwrap();
+        assert!(matches!(event, GraphicsEvent::SwapChainReconfigured));
+
+        // ResizeBuffers fails if anything still references the old buffers
+        assert_eq!(graphics.backend().swap_chain().back_buffers().len(), config.buffer_count as usize);
+        assert_eq!(graphics.swap_chain_config().format, config.format);
+        assert_eq!(graphics.config().linear_blending, config.srgb_views);
+        assert_eq!(graphics.state_tracker().tracked_count(), tracked);
+        render_frame(&mut graphics);
+    }
+}
+
+#[test]
+fn failed_reconfigure_keeps_the_previous_settings() {
+    let window = TestWindow::new(320, 240);
+    let Some(mut graphics) = window.graphics(config(320, 240)) else { return };
+    let previous = graphics.swap_chain_config().clone();
+    let config = SwapChainConfig { buffer_count: 3, ..previous.clone() };
+
+    // Neither the new settings nor the restore can resize while a buffer is held
+    let held = graphics.backend().swap_chain().back_buffers()[0].clone();
+    assert!(graphics.reconfigure(config.clone()).is_err());
+    assert!(!graphics.is_suspended());
+    assert_eq!(graphics.swap_chain_config().buffer_count, previous.buffer_count);
+    assert_eq!(graphics.backend().swap_chain().back_buffers().len(), previous.buffer_count as usize);
+    render_frame(&mut graphics);
+    drop(held);
+
+    graphics.reconfigure(config).unwrap();
+    assert_eq!(graphics.backend().swap_chain().back_buffers().len(), 3);
+    render_frame(&mut graphics);
+}
+
+/// A correct frame, to check the instance still works after a misuse
+fn render_frame(graphics: &mut Graphics) {
+    let frame = graphics.begin_frame().unwrap();
+    frame.clear(Color::BLACK);
+    graphics.end_frame(frame).unwrap();
+    assert!(!graphics.is_frame_in_progress());
+}
+
+#[test]
+fn second_begin_frame_is_refused() {
+    let window = TestWindow::new(320, 240);
+    let Some(mut graphics) = window.graphics(config(320, 240)) else { return };
+
+    let frame = graphics.begin_frame().unwrap();
+    assert!(matches!(graphics.begin_frame(), Err(Dx12Error::FrameInProgress)));
+    // The refused call leaves the first frame usable
+    frame.clear(Color::BLACK);
+    graphics.end_frame(frame).unwrap();
+    render_frame(&mut graphics);
+}
+
+#[test]
+fn dropped_frame_is_aborted() {
+    let window = TestWindow::new(320, 240);
+    let Some(mut graphics) = window.graphics(config(320, 240)) else { return };
+    let texture = Texture::new(graphics.device(), TextureDesc { width: 16, height: 16, ..Default::default() }).unwrap();
+    let resource = (&texture).into();
+    graphics.track_resource(&resource, ResourceState::Common);
+
+    let frame = graphics.begin_frame().unwrap();
+    frame.transition(&resource, ResourceState::CopyDest);
+    frame.clear(Color::RED);
+    drop(frame);
+    assert!(!graphics.is_frame_in_progress());
+    // The list was discarded, so its transition never happened
+    assert_eq!(graphics.state_tracker().state(texture.raw()), Some(ResourceState::Common.raw()));
+    render_frame(&mut graphics);
+
+    // The same transition in an ended frame is committed
+    let frame = graphics.begin_frame().unwrap();
+    frame.transition(&resource, ResourceState::CopyDest);
+    frame.flush_barriers();
+    graphics.end_frame(frame).unwrap();
+    assert_eq!(graphics.state_tracker().state(texture.raw()), Some(ResourceState::CopyDest.raw()));
+}
+
+#[test]
+fn frame_from_another_instance_is_stale() {
+    let (window, other_window) = (TestWindow::new(320, 240), TestWindow::new(320, 240));
+    let Some(mut graphics) = window.graphics(config(320, 240)) else { return };
+    let Some(mut other) = other_window.graphics(config(320, 240)) else { return };
+
+    let foreign = other.begin_frame().unwrap();
+    assert!(matches!(graphics.end_frame(foreign), Err(Dx12Error::StaleFrame)));
+    // The stale frame was aborted, which frees its own instance
+    assert!(!other.is_frame_in_progress());
+
+    // Also while this instance has a frame of its own, which stays current
+    let own = graphics.begin_frame().unwrap();
+    let foreign = other.begin_frame().unwrap();
+    assert!(matches!(graphics.end_frame(foreign), Err(Dx12Error::StaleFrame)));
+    assert!(graphics.is_frame_in_progress());
+    graphics.end_frame(own).unwrap();
+
+    render_frame(&mut graphics);
+    render_frame(&mut other);
+}
+
+const GRADIENT_WIDTH: u32 = 64;
+
+/// Render one frame and read back its pixels, row by row
+///
+/// Whatever `draw` returns (e.g. vertex buffers) is kept until the frame has executed.
+fn render<T>(graphics: &mut Graphics, draw: impl FnOnce(&mut Graphics, &mut RenderFrame) -> T) -> Vec<[u8; 4]> {
+    let readback = Readback::new(graphics.device(), graphics.width(), graphics.height());
+    let mut frame = graphics.begin_frame().unwrap();
+    let _uploads = draw(graphics, &mut frame);
+    readback.copy(frame.cmd_list(), graphics.backend().swap_chain().current_back_buffer());
+    graphics.end_frame(frame).unwrap();
+    readback.pixels()
+}
+
+/// Record a draw of `vertices` with the current viewport; keep the buffer until the frame ends
+fn draw_colored(
+    graphics: &Graphics,
+    frame: &RenderFrame,
+    pipeline: &PipelineState,
+    vertices: &[ColorVertex],
+) -> VertexBuffer {
+    let stride = std::mem::size_of::<ColorVertex>() as u32;
+    let buffer = VertexBuffer::new(graphics.device(), std::mem::size_of_val(vertices) as u64, stride).unwrap();
+    buffer.write(vertices).unwrap();
+    let list = frame.cmd_list();
+    list.set_root_signature(graphics.backend().root_signature());
+    list.set_pipeline_state(pipeline);
+    list.set_primitive_topology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+    list.set_vertex_buffers(0, &[*buffer.view()]);
+    list.draw_instanced(vertices.len() as u32, 1, 0, 0);
+    buffer
+}
+
+/// `ColorVertex` pipeline for the back buffer views of `graphics`
+fn back_buffer_pipeline(graphics: &Graphics) -> PipelineState {
+    let options = PipelineOptions {
+        render_target_format: Some(graphics.backend().swap_chain().rtv_format()),
+        ..Default::default()
+    };
+    color_pipeline(graphics.device(), graphics.backend().root_signature(), options)
+}
+
+/// Byte the target should hold for a display value
+fn byte(value: f32) -> u8 {
+    (value * 255.0).round() as u8
+}
+
+fn assert_close(actual: u8, expected: u8, what: &str) {
+    assert!(actual.abs_diff(expected) <= 1, "{}: got {}, expected {}", what, actual, expected);
+}
+
+/// Check that a read back pixel holds `color` as display bytes
+fn assert_color(pixel: [u8; 4], color: Color, what: &str) {
+    for (channel, value) in [color.r, color.g, color.b, color.a].into_iter().enumerate() {
+        assert_close(pixel[channel], byte(value), &format!("{} channel {}", what, channel));
+    }
+}
+
+#[test]
+fn startup_frames_show_the_clear_color() {
+    for linear_blending in [false, true] {
+        let window = TestWindow::new(64, 48);
+        let config = GraphicsConfig { linear_blending, clear_color: Color::from_hex(0x336699), ..config(64, 48) };
+        let Some(mut graphics) = window.graphics(config.clone()) else { return };
+
+        // Nothing is drawn: every back buffer still holds what Graphics::new presented
+        for buffer in 0..graphics.backend().swap_chain().back_buffers().len() {
+            let pixels = render(&mut graphics, |_, _| ());
+            assert_eq!(pixels.len(), 64 * 48);
+            for pixel in pixels {
+                assert_color(pixel, config.clear_color, &format!("buffer {} (linear {})", buffer, linear_blending));
+            }
+        }
+    }
+}
+
+#[test]
+fn startup_frames_letterbox_the_internal_resolution() {
+    let window = TestWindow::new(320, 200);
+    let config = GraphicsConfig {
+        internal_resolution: Some((80, 60)),
+        clear_color: Color::from_hex(0x336699),
+        letterbox_color: Color::from_hex(0x802010),
+        ..config(320, 200)
+    };
+    let Some(mut graphics) = window.graphics(config.clone()) else { return };
+
+    // 3x integer scale: a 240x180 image at (40, 10) with bars on all four sides
+    let content = graphics.letterbox().unwrap().content;
+    assert_eq!(content, Rect::new(40.0, 10.0, 240.0, 180.0));
+    for buffer in 0..graphics.backend().swap_chain().back_buffers().len() {
+        let pixels = render(&mut graphics, |_, _| ());
+        for (i, &pixel) in pixels.iter().enumerate() {
+            let (x, y) = ((i % 320) as f32, (i / 320) as f32);
+            let inside = (content.x..content.x + content.width).contains(&x)
+                && (content.y..content.y + content.height).contains(&y);
+            let (expected, what) = if inside { (config.clear_color, "image") } else { (config.letterbox_color, "bar") };
+            assert_color(pixel, expected, &format!("buffer {} {} pixel ({}, {})", buffer, what, x, y));
+        }
+    }
+}
+
+#[test]
+fn fills_keep_display_values_in_both_modes() {
+    for linear_blending in [false, true] {
+        let window = TestWindow::new(GRADIENT_WIDTH, 8);
+        let config = GraphicsConfig { linear_blending, ..config(GRADIENT_WIDTH, 8) };
+        let Some(mut graphics) = window.graphics(config) else { return };
+
+        // A white-to-black gradient of one-pixel fills: each column keeps its value
+        let value = |x: u32| 1.0 - x as f32 / (GRADIENT_WIDTH - 1) as f32;
+        let pixels = render(&mut graphics, |graphics, frame| {
+            let mut batch = DrawBatch::new().with_clear(Color::BLACK);
+            for x in 0..GRADIENT_WIDTH {
+                batch.quad(Rect::new(x as f32, 0.0, 1.0, 8.0), Color::rgb(value(x), value(x), value(x)));
+            }
+            graphics.submit(frame, &batch).unwrap();
+        });
+        for (x, pixel) in pixels[..GRADIENT_WIDTH as usize].iter().enumerate() {
+            let what = format!("gradient column {} (linear {})", x, linear_blending);
+            assert_close(pixel[0], byte(value(x as u32)), &what);
+        }
+
+        // Half-transparent white over black blends in the configured space
+        let pixels = render(&mut graphics, |graphics, frame| {
+            let mut batch = DrawBatch::new().with_clear(Color::BLACK);
+            batch.quad(Rect::new(0.0, 0.0, GRADIENT_WIDTH as f32, 8.0), Color::WHITE.with_alpha(0.5));
+            graphics.submit(frame, &batch).unwrap();
+        });
+        let expected = Color::WHITE.with_alpha(0.5).blend_over(Color::BLACK, linear_blending);
+        assert_close(pixels[0][0], byte(expected.r), &format!("50% white over black (linear {})", linear_blending));
+        assert_close(pixels[0][0], if linear_blending { 188 } else { 128 }, "analytic composite");
+    }
+}
+
+#[test]
+fn vertex_colors_interpolate_in_the_blending_space() {
+    for linear_blending in [false, true] {
+        let window = TestWindow::new(GRADIENT_WIDTH, 8);
+        let config = GraphicsConfig { linear_blending, ..config(GRADIENT_WIDTH, 8) };
+        let Some(mut graphics) = window.graphics(config) else { return };
+        let pipeline = back_buffer_pipeline(&graphics);
+
+        // White on the left edge, black on the right, across the whole target
+        let pixels = render(&mut graphics, |graphics, frame| {
+            let (white, black) = (frame.vertex_color(Color::WHITE), frame.vertex_color(Color::BLACK));
+            let vertex = |x: f32, y: f32, color| ColorVertex { position: [x, y, 0.0, 1.0], color };
+            let (top_left, top_right) = (vertex(-1.0, 1.0, white), vertex(1.0, 1.0, black));
+            let (bottom_left, bottom_right) = (vertex(-1.0, -1.0, white), vertex(1.0, -1.0, black));
+            let vertices = [bottom_left, top_left, top_right, bottom_left, top_right, bottom_right];
+            frame.set_full_viewport();
+            draw_colored(graphics, frame, &pipeline, &vertices)
+        });
+
+        // Pixel centers sit half a pixel in; the view decides where the midpoint lands
+        for (x, pixel) in pixels[..GRADIENT_WIDTH as usize].iter().enumerate() {
+            let t = 1.0 - (x as f32 + 0.5) / GRADIENT_WIDTH as f32;
+            let expected = if linear_blending { linear_to_srgb(t) } else { t };
+            let what = format!("vertex gradient column {} (linear {})", x, linear_blending);
+            assert_close(pixel[0], byte(expected), &what);
+        }
+    }
+}
+
+#[test]
+fn splitscreen_halves_hold_their_own_view() {
+    let (width, height) = (64, 32);
+    let window = TestWindow::new(width, height);
+    let Some(mut graphics) = window.graphics(config(width, height)) else { return };
+    let pipeline = back_buffer_pipeline(&graphics);
+    let camera = Camera3D::new(Vec3::new(0.0, 2.0, 5.0), Vec3::ZERO, width as f32 / height as f32);
+    let rects = splitscreen_layout(2, Rect::new(0.0, 0.0, width as f32, height as f32));
+    let views: Vec<_> = rects.iter().map(|rect| (camera.clone(), *rect)).collect();
+    const CONTENT: [[f32; 4]; 2] = [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]];
+
+    let pixels = render(&mut graphics, |graphics, frame| {
+        frame.clear(Color::BLACK);
+        // Each view fills the middle of its own viewport
+        let mut uploads = Vec::new();
+        frame.render_splitscreen(&views, |view| {
+            assert_eq!(view.camera.aspect, 1.0);
+            let vertices = common::quad([-0.5, -0.5], [0.5, 0.5], 0.0, CONTENT[view.index]);
+            uploads.push(draw_colored(graphics, frame, &pipeline, &vertices));
+        });
+
+        // UI afterwards spans both halves
+        let mut batch = DrawBatch::new();
+        batch.quad(Rect::new(28.0, 0.0, 8.0, 4.0), Color::WHITE);
+        graphics.submit(frame, &batch).unwrap();
+        uploads
+    });
+
+    let at = |x: u32, y: u32| pixels[(y * width + x) as usize];
+    let (black, red, green, white) = ([0, 0, 0, 255], [255, 0, 0, 255], [0, 255, 0, 255], [255, 255, 255, 255]);
+    // Left view: cleared border, content from x 8 to 24
+    assert_eq!((at(2, 16), at(7, 16), at(8, 16), at(23, 16), at(24, 16)), (black, black, red, red, black));
+    // Right view: the same, shifted by half the window
+    assert_eq!((at(34, 16), at(39, 16), at(40, 16), at(55, 16), at(56, 16)), (black, black, green, green, black));
+    assert_eq!((at(16, 7), at(16, 8), at(48, 23), at(48, 24)), (black, red, green, black));
+    assert_eq!((at(28, 1), at(35, 1), at(27, 1), at(36, 1)), (white, white, black, black));
+}